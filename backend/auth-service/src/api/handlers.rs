@@ -3,7 +3,7 @@ use crate::db::postgres::Database;
 use crate::api::responses::{VerifyRequest, VerifyResponse, AuthorizationChecks, AgentResponse, ErrorResponse};
 use crate::crypto::verification::TransactionVerifier;
 use crate::models::transaction::TransactionRequest;
-use rust_decimal::prelude::ToPrimitive;
+use security_gateway::ledger::money;
 use serde::{Deserialize, Serialize};
 
 pub async fn health_check() -> impl Responder {
@@ -65,8 +65,7 @@ pub async fn verify_transaction(
     let tx_request = TransactionRequest {
         agent_id: req.agent_id.clone(),
         merchant_id: req.merchant_id.clone(),
-        amount: req.amount,
-        currency: req.currency.clone(),
+        amount: req.amount.clone(),
         nonce: req.nonce.clone(),
         timestamp,
     };
@@ -106,8 +105,10 @@ pub async fn verify_transaction(
     let signature_valid = verifier.verify_transaction(&tx_request, &signature).unwrap_or(false);
     
     // Authorization checks
-    let agent_limit = agent.spending_limit_per_tx.to_f64().unwrap_or(0.0);
-    let within_spending_limit = req.amount <= agent_limit;
+    // Limits are kept in the ledger currency; other currencies cannot be compared
+    let agent_limit = money(agent.spending_limit_per_tx);
+    let within_spending_limit = req.amount.currency == agent_limit.currency
+        && req.amount.minor_units <= agent_limit.minor_units;
     let agent_active = agent.status == "active";
     let certificate_valid = chrono::Utc::now() < agent.expires_at;
     
@@ -116,7 +117,7 @@ pub async fn verify_transaction(
     let reason = if !signature_valid {
         Some("Invalid signature".to_string())
     } else if !within_spending_limit {
-        Some(format!("Amount ${:.2} exceeds limit ${:.2}", req.amount, agent_limit))
+        Some(format!("Amount {} exceeds limit {}", req.amount, agent_limit))
     } else if !agent_active {
         Some(format!("Agent status is {}", agent.status))
     } else if !certificate_valid {
@@ -152,8 +153,8 @@ pub async fn get_agent(
                 model_version: agent.model_version,
                 tier: agent.tier.to_string(),
                 status: agent.status,
-                spending_limit_daily: agent.spending_limit_daily.to_f64().unwrap_or(0.0),
-                spending_limit_per_tx: agent.spending_limit_per_tx.to_f64().unwrap_or(0.0),
+                spending_limit_daily: money(agent.spending_limit_daily),
+                spending_limit_per_tx: money(agent.spending_limit_per_tx),
                 created_at: agent.created_at.to_rfc3339(),
                 expires_at: agent.expires_at.to_rfc3339(),
             };
@@ -179,8 +180,8 @@ pub async fn list_agents(db: web::Data<Database>) -> impl Responder {
                     model_version: agent.model_version,
                     tier: agent.tier.to_string(),
                     status: agent.status,
-                    spending_limit_daily: agent.spending_limit_daily.to_f64().unwrap_or(0.0),
-                    spending_limit_per_tx: agent.spending_limit_per_tx.to_f64().unwrap_or(0.0),
+                    spending_limit_daily: money(agent.spending_limit_daily),
+                    spending_limit_per_tx: money(agent.spending_limit_per_tx),
                     created_at: agent.created_at.to_rfc3339(),
                     expires_at: agent.expires_at.to_rfc3339(),
                 })
@@ -1,11 +1,11 @@
+use security_gateway::Money;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerifyRequest {
     pub agent_id: String,
     pub merchant_id: String,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub nonce: String,
     pub timestamp: String,
     pub signature: String,
@@ -36,8 +36,8 @@ pub struct AgentResponse {
     pub model_version: String,
     pub tier: String,
     pub status: String,
-    pub spending_limit_daily: f64,
-    pub spending_limit_per_tx: f64,
+    pub spending_limit_daily: Money,
+    pub spending_limit_per_tx: Money,
     pub created_at: String,
     pub expires_at: String,
 }
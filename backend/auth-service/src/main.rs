@@ -3,7 +3,8 @@ use clap::{Parser, Subcommand};
 use tracing::info;
 use tracing_subscriber;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
+use security_gateway::ledger::money;
+use security_gateway::Money;
 
 mod crypto;
 mod db;
@@ -37,9 +38,9 @@ enum Commands {
         #[arg(long)]
         tier: String,
         #[arg(long)]
-        daily_limit: f64,
+        daily_limit: Decimal,
         #[arg(long)]
-        tx_limit: f64,
+        tx_limit: Decimal,
     },
     
     /// List all agents
@@ -51,8 +52,9 @@ enum Commands {
         agent_id: String,
         #[arg(long)]
         merchant: String,
+        /// In major units, e.g. 12.34
         #[arg(long)]
-        amount: f64,
+        amount: String,
         #[arg(long, default_value = "USD")]
         currency: String,
     },
@@ -111,7 +113,8 @@ async fn main() -> Result<()> {
         } => {
             let db = Database::connect().await?;
             info!("Connected to database");
-            sign_transaction(&db, &agent_id, &merchant, amount, &currency).await?;
+            let amount = Money::parse(&amount, &currency)?;
+            sign_transaction(&db, &agent_id, &merchant, amount).await?;
         }
         
         Commands::VerifyTransaction { transaction_file } => {
@@ -129,8 +132,8 @@ async fn register_agent(
     provider_name: &str,
     model_version: &str,
     tier_str: &str,
-    daily_limit: f64,
-    tx_limit: f64,
+    daily_limit: Decimal,
+    tx_limit: Decimal,
 ) -> Result<()> {
     info!("Registering new agent...");
     
@@ -161,8 +164,8 @@ async fn register_agent(
         &base64::encode(public_key.as_bytes()),
         &certificate,
         tier.clone(),
-        Decimal::from_f64_retain(daily_limit).unwrap(),
-        Decimal::from_f64_retain(tx_limit).unwrap(),
+        daily_limit,
+        tx_limit,
     ).await?;
     
     // Save private key to file (in production, this would be stored securely)
@@ -175,8 +178,8 @@ async fn register_agent(
     println!("Provider:       {}", provider_name);
     println!("Model:          {}", model_version);
     println!("Tier:           {:?}", tier);
-    println!("Daily Limit:    ${:.2}", daily_limit);
-    println!("TX Limit:       ${:.2}", tx_limit);
+    println!("Daily Limit:    {}", money(daily_limit));
+    println!("TX Limit:       {}", money(tx_limit));
     println!("Private Key:    {} (KEEP SECRET!)", private_key_file);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     
@@ -200,8 +203,8 @@ async fn list_agents(db: &Database) -> Result<()> {
             println!("Model:          {}", agent.model_version);
             println!("Tier:           {:?}", agent.tier);
             println!("Status:         {}", agent.status);
-            println!("Daily Limit:    ${:.2}", agent.spending_limit_daily.to_f64().unwrap_or(0.0));
-            println!("TX Limit:       ${:.2}", agent.spending_limit_per_tx.to_f64().unwrap_or(0.0));
+            println!("Daily Limit:    {}", money(agent.spending_limit_daily));
+            println!("TX Limit:       {}", money(agent.spending_limit_per_tx));
             println!("Expires:        {}", agent.expires_at);
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         }
@@ -214,8 +217,7 @@ async fn sign_transaction(
     _db: &Database,
     agent_id: &str,
     merchant_id: &str,
-    amount: f64,
-    currency: &str,
+    amount: Money,
 ) -> Result<()> {
     info!("Signing transaction for agent: {}", agent_id);
     
@@ -231,8 +233,7 @@ async fn sign_transaction(
     let tx_request = TransactionRequest {
         agent_id: agent_id.to_string(),
         merchant_id: merchant_id.to_string(),
-        amount,
-        currency: currency.to_string(),
+        amount: amount.clone(),
         nonce: nonce.clone(),
         timestamp,
     };
@@ -246,7 +247,6 @@ async fn sign_transaction(
         "agent_id": agent_id,
         "merchant_id": merchant_id,
         "amount": amount,
-        "currency": currency,
         "nonce": nonce,
         "timestamp": timestamp.to_rfc3339(),
         "signature": base64::encode(&signature),
@@ -260,7 +260,7 @@ async fn sign_transaction(
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Agent:          {}", agent_id);
     println!("Merchant:       {}", merchant_id);
-    println!("Amount:         {} {}", amount, currency);
+    println!("Amount:         {}", amount);
     println!("Nonce:          {}", nonce);
     println!("File:           {}", filename);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
@@ -280,8 +280,7 @@ async fn verify_transaction(
     
     let agent_id = tx_data["agent_id"].as_str().unwrap();
     let merchant_id = tx_data["merchant_id"].as_str().unwrap();
-    let amount = tx_data["amount"].as_f64().unwrap();
-    let currency = tx_data["currency"].as_str().unwrap();
+    let amount: Money = serde_json::from_value(tx_data["amount"].clone())?;
     let nonce = tx_data["nonce"].as_str().unwrap();
     let timestamp_str = tx_data["timestamp"].as_str().unwrap();
     let signature_b64 = tx_data["signature"].as_str().unwrap();
@@ -302,7 +301,7 @@ async fn verify_transaction(
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("Agent:          {}", agent_id);
         println!("Merchant:       {}", merchant_id);
-        println!("Amount:         {} {}", amount, currency);
+        println!("Amount:         {}", amount);
         println!("Nonce:          {}", nonce);
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("❌ NONCE ALREADY USED - REPLAY ATTACK DETECTED");
@@ -314,8 +313,7 @@ async fn verify_transaction(
     let tx_request = TransactionRequest {
         agent_id: agent_id.to_string(),
         merchant_id: merchant_id.to_string(),
-        amount,
-        currency: currency.to_string(),
+        amount: amount.clone(),
         nonce: nonce.to_string(),
         timestamp,
     };
@@ -329,7 +327,7 @@ async fn verify_transaction(
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Agent:          {}", agent_id);
     println!("Merchant:       {}", merchant_id);
-    println!("Amount:         {} {}", amount, currency);
+    println!("Amount:         {}", amount);
     println!("Nonce:          {}", nonce);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     
@@ -343,11 +341,11 @@ async fn verify_transaction(
         let mut all_checks_passed = true;
         
         // Check spending limit
-        let agent_limit = agent.spending_limit_per_tx.to_f64().unwrap_or(0.0);
-        if amount <= agent_limit {
-            println!("✅ Amount within transaction limit (${:.2})", agent_limit);
+        let agent_limit = money(agent.spending_limit_per_tx);
+        if amount.currency == agent_limit.currency && amount.minor_units <= agent_limit.minor_units {
+            println!("✅ Amount within transaction limit ({})", agent_limit);
         } else {
-            println!("❌ Amount exceeds transaction limit (${:.2})", agent_limit);
+            println!("❌ Amount exceeds transaction limit ({})", agent_limit);
             all_checks_passed = false;
         }
         
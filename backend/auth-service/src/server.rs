@@ -3,7 +3,8 @@ use actix_cors::Cors;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 use base64::{engine::general_purpose, Engine as _};
-use rust_decimal::prelude::ToPrimitive;
+use security_gateway::ledger::money;
+use security_gateway::Money;
 
 use crate::api::handlers;
 use crate::crypto::{signing::TransactionSigner, verification::TransactionVerifier};
@@ -13,8 +14,7 @@ use crate::models::transaction::TransactionRequest;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SignRequest {
     pub agent_id: String,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub merchant_id: String,
     pub nonce: String,
     pub timestamp: String,
@@ -23,8 +23,7 @@ pub struct SignRequest {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SignResponse {
     pub agent_id: String,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub merchant_id: String,
     pub nonce: String,
     pub timestamp: String,
@@ -34,8 +33,7 @@ pub struct SignResponse {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct VerifyRequest {
     pub agent_id: String,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub merchant_id: String,
     pub nonce: String,
     pub timestamp: String,
@@ -115,8 +113,7 @@ async fn sign_transaction(request: web::Json<SignRequest>) -> impl Responder {
     let tx_request = TransactionRequest {
         agent_id: request.agent_id.clone(),
         merchant_id: request.merchant_id.clone(),
-        amount: request.amount,
-        currency: request.currency.clone(),
+        amount: request.amount.clone(),
         nonce: request.nonce.clone(),
         timestamp,
     };
@@ -145,8 +142,7 @@ async fn sign_transaction(request: web::Json<SignRequest>) -> impl Responder {
 
     HttpResponse::Ok().json(SignResponse {
         agent_id: request.agent_id.clone(),
-        amount: request.amount,
-        currency: request.currency.clone(),
+        amount: request.amount.clone(),
         merchant_id: request.merchant_id.clone(),
         nonce: request.nonce.clone(),
         timestamp: request.timestamp.clone(),
@@ -210,8 +206,7 @@ async fn verify_transaction(request: web::Json<VerifyRequest>) -> impl Responder
     let tx_request = TransactionRequest {
         agent_id: request.agent_id.clone(),
         merchant_id: request.merchant_id.clone(),
-        amount: request.amount,
-        currency: request.currency.clone(),
+        amount: request.amount.clone(),
         nonce: request.nonce.clone(),
         timestamp,
     };
@@ -248,8 +243,9 @@ async fn verify_transaction(request: web::Json<VerifyRequest>) -> impl Responder
         }
     };
 
-    let agent_limit = agent.spending_limit_per_tx.to_f64().unwrap_or(0.0);
-    checks.within_spending_limit = request.amount <= agent_limit;
+    let agent_limit = money(agent.spending_limit_per_tx);
+    checks.within_spending_limit = request.amount.currency == agent_limit.currency
+        && request.amount.minor_units <= agent_limit.minor_units;
     checks.agent_active = agent.status == "active";
     checks.certificate_valid = chrono::Utc::now() < agent.expires_at;
 
@@ -10,6 +10,8 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::AppState;
+use security_gateway::ledger;
+use security_gateway::Money;
 
 #[derive(Debug, Serialize)]
 pub struct BlockLedgerEntry {
@@ -23,7 +25,7 @@ pub struct BlockLedgerEntry {
     pub reason: String,
     pub blocked_at: String,
     pub status: Option<String>, // Only for refund_requests
-    pub refund_amount: Option<f64>, // Only for refund_requests
+    pub refund_amount: Option<Money>, // Only for refund_requests
     pub transaction_id: Option<String>,
 }
 
@@ -106,8 +108,7 @@ pub async fn get_all_blocks_ledger(
             blocked_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
                 .format("%Y-%m-%d %H:%M:%S").to_string(),
             status: Some(row.get("status")),
-            refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount")
-                .map(|d| d.to_string().parse().unwrap_or(0.0)),
+            refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount").map(ledger::money),
             transaction_id: row.get::<Option<Uuid>, _>("transaction_id").map(|id| id.to_string()),
         });
     }
@@ -10,6 +10,9 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::AppState;
+use security_gateway::balance;
+use security_gateway::ledger::{self, Journal};
+use security_gateway::Money;
 
 #[derive(Debug, Deserialize)]
 pub struct DenyTransactionRequest {
@@ -42,7 +45,7 @@ pub struct BlockRequestResponse {
     pub agent_owner_email: String,
     pub transaction_id: Option<String>,
     pub reason: String,
-    pub refund_amount: Option<f64>,
+    pub refund_amount: Option<Money>,
     pub status: String,
     pub created_at: String,
     pub reviewed_at: Option<String>,
@@ -59,29 +62,44 @@ pub async fn deny_transaction(
     let transaction_uuid = Uuid::parse_str(&transaction_id)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let result = sqlx::query(
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let row = sqlx::query(
         "UPDATE transactions 
          SET status = 'failed', completed_at = NOW() 
-         WHERE id = $1 AND status = 'pending'"
+         WHERE id = $1 AND status = 'pending'
+         RETURNING agent_id, COALESCE(ledger_amount, amount) AS amount, balance_held"
     )
     .bind(transaction_uuid)
-    .execute(&state.db.pool)
-    .await;
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to deny transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or_else(|| {
+        error!("Transaction not found or not pending: {}", transaction_id);
+        StatusCode::NOT_FOUND
+    })?;
 
-    match result {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                error!("Transaction not found or not pending: {}", transaction_id);
-                return Err(StatusCode::NOT_FOUND);
-            }
-            info!("✅ Transaction denied: {} - Reason: {}", transaction_id, req.reason);
-            Ok(StatusCode::OK)
-        }
-        Err(e) => {
-            error!("Failed to deny transaction: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+    if row.get::<bool, _>("balance_held") {
+        balance::release(&mut tx, &row.get::<String, _>("agent_id"), row.get("amount")).await
+            .map_err(|e| {
+                error!("Failed to release held balance: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
     }
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Err(e) = state.reservations.release_transaction(transaction_uuid).await {
+        error!("Failed to release spend hold: {}", e);
+    }
+
+    info!("✅ Transaction denied: {} - Reason: {}", transaction_id, req.reason);
+    Ok(StatusCode::OK)
 }
 
 pub async fn block_agent_simple(
@@ -181,8 +199,8 @@ pub async fn request_block_with_refund(
     let transaction_uuid = Uuid::parse_str(&req.transaction_id)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let transaction: (f64, String) = sqlx::query_as(
-        "SELECT amount::FLOAT8, status FROM transactions WHERE id = $1"
+    let transaction: (rust_decimal::Decimal, String) = sqlx::query_as(
+        "SELECT COALESCE(ledger_amount, amount), status FROM transactions WHERE id = $1"
     )
     .bind(transaction_uuid)
     .fetch_optional(&state.db.pool)
@@ -258,8 +276,7 @@ pub async fn request_block_with_refund(
         agent_owner_email: row.get::<Option<String>, _>("owner_email").unwrap_or_else(|| "Unknown".to_string()),
         transaction_id: Some(row.get::<Uuid, _>("transaction_id").to_string()),
         reason: row.get("reason"),
-        refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount")
-            .map(|d| d.to_string().parse().unwrap_or(0.0)),
+        refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount").map(ledger::money),
         status: row.get("status"),
         created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
             .format("%Y-%m-%d %H:%M:%S").to_string(),
@@ -306,8 +323,7 @@ pub async fn list_block_requests(
             agent_owner_email: row.get::<Option<String>, _>("owner_email").unwrap_or_else(|| "Unknown".to_string()),
             transaction_id: row.get::<Option<Uuid>, _>("transaction_id").map(|id| id.to_string()),
             reason: row.get("reason"),
-            refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount")
-                .map(|d| d.to_string().parse().unwrap_or(0.0)),
+            refund_amount: row.get::<Option<rust_decimal::Decimal>, _>("refund_amount").map(ledger::money),
             status: row.get("status"),
             created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
                 .format("%Y-%m-%d %H:%M:%S").to_string(),
@@ -439,6 +455,12 @@ pub async fn approve_block_request(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+        ledger::post(&mut tx, &Journal::refund(&agent_id, merchant_id, amount, trans_id)).await
+            .map_err(|e| {
+                error!("Failed to post refund to ledger: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
         info!("✅ Refunded ${} to agent {} and decreased merchant revenue", amount, agent_id);
     }
 
@@ -9,6 +9,8 @@ use std::sync::Arc;
 use tracing::{error, info};
 
 use crate::AppState;
+use security_gateway::ledger::{self, Journal};
+use security_gateway::Money;
 
 #[derive(Debug, Serialize)]
 pub struct Agent {
@@ -17,9 +19,9 @@ pub struct Agent {
     pub foundational_model: String,
     pub tier: String,
     pub status: String,
-    pub balance: f64,
-    pub remaining_balance: f64,
-    pub total_volume: f64,
+    pub balance: Money,
+    pub remaining_balance: Money,
+    pub total_volume: Money,
     pub transaction_count: i64,
     pub risk_score: i32,
     pub created_at: String,
@@ -30,7 +32,7 @@ pub struct CreateAgentRequest {
     pub agent_name: String,
     pub foundational_model: String,
     pub tier: String,
-    pub balance: i64,
+    pub balance: Money,
 }
 
 pub async fn create_agent(
@@ -50,6 +52,27 @@ pub async fn create_agent(
     let user_id = uuid::Uuid::parse_str(&claims.sub)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if req.balance.minor_units < 0 || req.balance.currency != ledger::LEDGER_CURRENCY {
+        error!("Invalid opening balance: {}", req.balance);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Tiers are the profiles in `tier_profiles`; limits and capabilities come from them
+    let tier = req.tier.trim().to_lowercase();
+    let profile = state.db.get_tier_profile(&tier).await
+        .map_err(|e| {
+            error!("Failed to fetch tier profile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if profile.is_none() {
+        error!("Unknown tier: {}", req.tier);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let opening_balance = req.balance.to_decimal();
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     sqlx::query(
         "INSERT INTO agents 
          (id, user_id, agent_name, foundational_model, tier, status, balance, 
@@ -60,24 +83,36 @@ pub async fn create_agent(
     .bind(user_id)
     .bind(&req.agent_name)
     .bind(&req.foundational_model)
-    .bind(&req.tier)
-    .bind(req.balance)
-    .execute(&state.db.pool)
+    .bind(&tier)
+    .bind(opening_balance)
+    .execute(&mut *tx)
     .await
     .map_err(|e| {
         error!("Failed to create agent: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    // The initial balance is the wallet's first top-up
+    if req.balance.is_positive() {
+        ledger::post(&mut tx, &Journal::topup(&agent_id, opening_balance, None)).await
+            .map_err(|e| {
+                error!("Failed to post opening top-up: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let agent = Agent {
         id: agent_id,
         agent_name: req.agent_name,
         foundational_model: req.foundational_model,
-        tier: req.tier,
+        tier,
         status: "active".to_string(),
-        balance: req.balance as f64,
-        remaining_balance: req.balance as f64,
-        total_volume: 0.0,
+        remaining_balance: req.balance.clone(),
+        balance: req.balance,
+        total_volume: ledger::money(rust_decimal::Decimal::ZERO),
         transaction_count: 0,
         risk_score: 0,
         created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
@@ -121,12 +156,9 @@ pub async fn list_agents(
             foundational_model: row.get("foundational_model"),
             tier: row.get("tier"),
             status: row.get("status"),
-            balance: row.get::<rust_decimal::Decimal, _>("balance")
-                .to_string().parse().unwrap_or(0.0),
-            remaining_balance: row.get::<rust_decimal::Decimal, _>("remaining_balance")
-                .to_string().parse().unwrap_or(0.0),
-            total_volume: row.get::<rust_decimal::Decimal, _>("total_volume")
-                .to_string().parse().unwrap_or(0.0),
+            balance: ledger::money(row.get("balance")),
+            remaining_balance: ledger::money(row.get("remaining_balance")),
+            total_volume: ledger::money(row.get("total_volume")),
             transaction_count: row.get("transaction_count"),
             risk_score: row.get("risk_score"),
             created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
@@ -172,12 +204,9 @@ pub async fn get_agent(
         foundational_model: row.get("foundational_model"),
         tier: row.get("tier"),
         status: row.get("status"),
-        balance: row.get::<rust_decimal::Decimal, _>("balance")
-            .to_string().parse().unwrap_or(0.0),
-        remaining_balance: row.get::<rust_decimal::Decimal, _>("remaining_balance")
-            .to_string().parse().unwrap_or(0.0),
-        total_volume: row.get::<rust_decimal::Decimal, _>("total_volume")
-            .to_string().parse().unwrap_or(0.0),
+        balance: ledger::money(row.get("balance")),
+        remaining_balance: ledger::money(row.get("remaining_balance")),
+        total_volume: ledger::money(row.get("total_volume")),
         transaction_count: row.get("transaction_count"),
         risk_score: row.get("risk_score"),
         created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
@@ -251,7 +280,7 @@ pub async fn get_agent_transactions(
     info!("📋 Fetching transactions for agent: {}", agent_id);
 
     let rows = sqlx::query(
-        "SELECT t.id, t.amount, t.description, t.status, t.created_at, t.merchant_id, m.merchant_name
+        "SELECT t.id, t.amount, t.currency, t.description, t.status, t.created_at, t.merchant_id, m.merchant_name
          FROM transactions t
          JOIN agents a ON a.id = t.agent_id
          LEFT JOIN merchants m ON m.id = t.merchant_id
@@ -273,7 +302,7 @@ pub async fn get_agent_transactions(
         .map(|row| {
             serde_json::json!({
                 "id": row.get::<uuid::Uuid, _>("id").to_string(),
-                "amount": row.get::<rust_decimal::Decimal, _>("amount").to_string().parse::<f64>().unwrap_or(0.0),
+                "amount": Money::from_stored(row.get("amount"), row.get("currency")),
                 "description": row.get::<Option<String>, _>("description"),
                 "status": row.get::<String, _>("status"),
                 "created_at": row.get::<chrono::DateTime<chrono::Utc>, _>("created_at").to_rfc3339(),
@@ -324,9 +353,9 @@ pub async fn list_all_agents_admin(
                 "foundational_model": row.get::<String, _>("foundational_model"),
                 "tier": row.get::<String, _>("tier"),
                 "status": row.get::<String, _>("status"),
-                "balance": row.get::<rust_decimal::Decimal, _>("balance").to_string().parse::<f64>().unwrap_or(0.0),
-                "remaining_balance": row.get::<rust_decimal::Decimal, _>("remaining_balance").to_string().parse::<f64>().unwrap_or(0.0),
-                "total_volume": row.get::<rust_decimal::Decimal, _>("total_volume").to_string().parse::<f64>().unwrap_or(0.0),
+                "balance": ledger::money(row.get("balance")),
+                "remaining_balance": ledger::money(row.get("remaining_balance")),
+                "total_volume": ledger::money(row.get("total_volume")),
                 "transaction_count": row.get::<i64, _>("transaction_count"),
                 "risk_score": row.get::<i32, _>("risk_score"),
                 "created_at": row.get::<chrono::DateTime<chrono::Utc>, _>("created_at").format("%Y-%m-%d %H:%M:%S").to_string(),
@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::handlers::extract_user_from_headers;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct AddBlacklistRequest {
+    pub agent_id: String,
+    pub reason: String,
+    /// Merchant whose report led to the ban, if any
+    pub merchant_id: Option<String>,
+    pub transaction_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveBlacklistRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlacklistAuditFilter {
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlacklistEntry {
+    pub id: String,
+    pub agent_id: String,
+    pub reason: String,
+    pub banned_by: String,
+    pub merchant_id: Option<String>,
+    pub transaction_id: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlacklistAuditEntry {
+    pub agent_id: String,
+    pub action: String,
+    pub reason: String,
+    pub actor_id: String,
+    pub created_at: String,
+}
+
+fn entry_from_row(row: &sqlx::postgres::PgRow) -> BlacklistEntry {
+    BlacklistEntry {
+        id: row.get::<Uuid, _>("id").to_string(),
+        agent_id: row.get("agent_id"),
+        reason: row.get("reason"),
+        banned_by: row.get::<Uuid, _>("banned_by").to_string(),
+        merchant_id: row.get::<Option<Uuid>, _>("merchant_id").map(|id| id.to_string()),
+        transaction_id: row.get::<Option<Uuid>, _>("transaction_id").map(|id| id.to_string()),
+        created_at: row.get::<chrono::NaiveDateTime, _>("created_at")
+            .format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// Admin caller's user id; everyone else is refused
+fn require_admin(headers: &HeaderMap) -> Result<Uuid, StatusCode> {
+    let claims = extract_user_from_headers(headers)?;
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+fn parse_optional_uuid(value: Option<&str>) -> Result<Option<Uuid>, StatusCode> {
+    value.map(Uuid::parse_str).transpose().map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+pub async fn list_blacklist(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<BlacklistEntry>>, StatusCode> {
+    require_admin(&headers)?;
+
+    let rows = sqlx::query(
+        "SELECT id, agent_id, reason, banned_by, merchant_id, transaction_id, created_at
+         FROM agent_blacklist
+         ORDER BY created_at DESC"
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch blacklist: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.iter().map(entry_from_row).collect()))
+}
+
+/// Ban an agent on every merchant and protocol
+pub async fn add_to_blacklist(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<AddBlacklistRequest>,
+) -> Result<(StatusCode, Json<BlacklistEntry>), StatusCode> {
+    let admin_id = require_admin(&headers)?;
+
+    if req.reason.trim().is_empty() {
+        error!("Blacklist reason is required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let merchant_id = parse_optional_uuid(req.merchant_id.as_deref())?;
+    let transaction_id = parse_optional_uuid(req.transaction_id.as_deref())?;
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let row = sqlx::query(
+        "INSERT INTO agent_blacklist (agent_id, reason, banned_by, merchant_id, transaction_id)
+         SELECT $1, $2, $3, $4, $5
+         WHERE EXISTS (SELECT 1 FROM agents WHERE id = $1)
+         ON CONFLICT (agent_id) DO NOTHING
+         RETURNING id, agent_id, reason, banned_by, merchant_id, transaction_id, created_at"
+    )
+    .bind(&req.agent_id)
+    .bind(&req.reason)
+    .bind(admin_id)
+    .bind(merchant_id)
+    .bind(transaction_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to blacklist agent: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(row) = row else {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM agent_blacklist WHERE agent_id = $1)")
+            .bind(&req.agent_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if exists {
+            error!("Agent {} is already blacklisted", req.agent_id);
+            return Err(StatusCode::CONFLICT);
+        }
+        error!("Agent not found: {}", req.agent_id);
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    record_audit(&mut tx, &req.agent_id, "added", &req.reason, admin_id).await?;
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("⛔ Agent {} blacklisted: {}", req.agent_id, req.reason);
+    Ok((StatusCode::CREATED, Json(entry_from_row(&row))))
+}
+
+/// Lift a ban; the reason is kept in the audit trail
+pub async fn remove_from_blacklist(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<RemoveBlacklistRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let admin_id = require_admin(&headers)?;
+
+    if req.reason.trim().is_empty() {
+        error!("Reason for lifting the ban is required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let removed = sqlx::query("DELETE FROM agent_blacklist WHERE agent_id = $1")
+        .bind(&agent_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to remove blacklist entry: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if removed.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    record_audit(&mut tx, &agent_id, "removed", &req.reason, admin_id).await?;
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("✅ Agent {} removed from blacklist: {}", agent_id, req.reason);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Every add and removal, newest first
+pub async fn get_blacklist_audit(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<BlacklistAuditFilter>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<BlacklistAuditEntry>>, StatusCode> {
+    require_admin(&headers)?;
+
+    let rows = sqlx::query(
+        "SELECT agent_id, action, reason, actor_id, created_at
+         FROM agent_blacklist_audit
+         WHERE $1::text IS NULL OR agent_id = $1
+         ORDER BY created_at DESC"
+    )
+    .bind(&filter.agent_id)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch blacklist audit: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let entries = rows
+        .iter()
+        .map(|row| BlacklistAuditEntry {
+            agent_id: row.get("agent_id"),
+            action: row.get("action"),
+            reason: row.get("reason"),
+            actor_id: row.get::<Uuid, _>("actor_id").to_string(),
+            created_at: row.get::<chrono::NaiveDateTime, _>("created_at")
+                .format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+        .collect();
+
+    Ok(Json(entries))
+}
+
+async fn record_audit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    agent_id: &str,
+    action: &str,
+    reason: &str,
+    actor_id: Uuid,
+) -> Result<(), StatusCode> {
+    sqlx::query(
+        "INSERT INTO agent_blacklist_audit (agent_id, action, reason, actor_id)
+         VALUES ($1, $2, $3, $4)"
+    )
+    .bind(agent_id)
+    .bind(action)
+    .bind(reason)
+    .bind(actor_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to record blacklist audit: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::handlers::{extract_user_from_headers, Claims};
+use crate::AppState;
+use security_gateway::categories::CategoryRule;
+
+/// An agent's full set of category rules; a PUT replaces it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRuleSet {
+    pub rules: Vec<CategoryRule>,
+}
+
+/// The agent's owner or an admin; returns the caller's user id
+pub(super) async fn ensure_agent_owner(state: &AppState, claims: &Claims, agent_id: &str) -> Result<Uuid, StatusCode> {
+    let caller = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let owned: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM agents WHERE id = $1 AND ($2 OR user_id = $3))"
+    )
+    .bind(agent_id)
+    .bind(claims.role == "admin")
+    .bind(caller)
+    .fetch_one(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch agent: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if owned { Ok(caller) } else { Err(StatusCode::NOT_FOUND) }
+}
+
+pub async fn get_category_rules(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<CategoryRuleSet>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    ensure_agent_owner(&state, &claims, &agent_id).await?;
+
+    let rules = state.db.get_category_rules(&agent_id).await
+        .map_err(|e| {
+            error!("Failed to fetch category rules: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(CategoryRuleSet { rules }))
+}
+
+pub async fn update_category_rules(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<CategoryRuleSet>,
+) -> Result<Json<CategoryRuleSet>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let caller = ensure_agent_owner(&state, &claims, &agent_id).await?;
+
+    let mut rules = Vec::with_capacity(req.rules.len());
+    let mut seen = HashSet::new();
+    for rule in &req.rules {
+        let rule = rule.validated().map_err(|e| {
+            error!("Invalid category rule: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+        if !seen.insert((rule.scope, rule.category.clone())) {
+            error!("Category {} {} listed twice", rule.scope.as_str(), rule.category);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        rules.push(rule);
+    }
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM agent_category_rules WHERE agent_id = $1")
+        .bind(&agent_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to clear category rules: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    for rule in &rules {
+        sqlx::query(
+            "INSERT INTO agent_category_rules (agent_id, scope, category, effect, created_by)
+             VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(&agent_id)
+        .bind(rule.scope.as_str())
+        .bind(&rule.category)
+        .bind(rule.effect.as_str())
+        .bind(caller)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to save category rule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("🏷️ {} category rules set for agent {} by {}", rules.len(), agent_id, claims.email);
+    Ok(Json(CategoryRuleSet { rules }))
+}
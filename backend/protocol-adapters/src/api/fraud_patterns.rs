@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::handlers::extract_user_from_headers;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct FraudPatternFilter {
+    /// Defaults to unresolved findings only
+    pub resolved: Option<bool>,
+    pub severity: Option<String>,
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveFraudPatternRequest {
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FraudPatternEntry {
+    pub id: String,
+    pub agent_id: String,
+    pub pattern_type: String,
+    pub severity: String,
+    pub details: serde_json::Value,
+    pub detected_at: String,
+    pub resolved: bool,
+    pub resolution_notes: Option<String>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<String>,
+}
+
+const PATTERN_COLUMNS: &str =
+    "id, agent_id, pattern_type, severity, details, detected_at, resolved, resolution_notes, resolved_by, resolved_at";
+
+fn pattern_from_row(row: &sqlx::postgres::PgRow) -> FraudPatternEntry {
+    let format = |t: chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    FraudPatternEntry {
+        id: row.get::<Uuid, _>("id").to_string(),
+        agent_id: row.get("agent_id"),
+        pattern_type: row.get("pattern_type"),
+        severity: row.get("severity"),
+        details: row.get("details"),
+        detected_at: format(row.get("detected_at")),
+        resolved: row.get("resolved"),
+        resolution_notes: row.get("resolution_notes"),
+        resolved_by: row.get::<Option<Uuid>, _>("resolved_by").map(|id| id.to_string()),
+        resolved_at: row.get::<Option<chrono::NaiveDateTime>, _>("resolved_at").map(format),
+    }
+}
+
+/// Fraud pattern findings, newest first
+pub async fn list_fraud_patterns(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<FraudPatternFilter>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<FraudPatternEntry>>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM fraud_patterns
+         WHERE resolved = $1
+           AND ($2::text IS NULL OR severity = $2)
+           AND ($3::text IS NULL OR agent_id = $3)
+         ORDER BY detected_at DESC",
+        PATTERN_COLUMNS
+    ))
+    .bind(filter.resolved.unwrap_or(false))
+    .bind(&filter.severity)
+    .bind(&filter.agent_id)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch fraud patterns: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.iter().map(pattern_from_row).collect()))
+}
+
+/// Close a finding after investigation; the agent stops being blocked once no high-severity findings remain open
+pub async fn resolve_fraud_pattern(
+    State(state): State<Arc<AppState>>,
+    Path(pattern_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ResolveFraudPatternRequest>,
+) -> Result<Json<FraudPatternEntry>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let pattern_uuid = Uuid::parse_str(&pattern_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let admin_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    if req.notes.trim().is_empty() {
+        error!("Resolution notes are required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let row = sqlx::query(&format!(
+        "UPDATE fraud_patterns
+         SET resolved = true, resolution_notes = $2, resolved_by = $3, resolved_at = NOW()
+         WHERE id = $1 AND NOT resolved
+         RETURNING {}",
+        PATTERN_COLUMNS
+    ))
+    .bind(pattern_uuid)
+    .bind(&req.notes)
+    .bind(admin_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to resolve fraud pattern: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or_else(|| {
+        error!("Fraud pattern not found or already resolved: {}", pattern_id);
+        StatusCode::NOT_FOUND
+    })?;
+
+    let pattern = pattern_from_row(&row);
+    info!("🚩 Fraud pattern {} ({}) for agent {} resolved by {}", pattern_id, pattern.pattern_type, pattern.agent_id, claims.email);
+    Ok(Json(pattern))
+}
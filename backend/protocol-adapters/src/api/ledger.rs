@@ -0,0 +1,74 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::AppState;
+use security_gateway::ledger::{self, Drift};
+use security_gateway::Money;
+
+#[derive(Debug, Serialize)]
+pub struct LedgerDriftEntry {
+    pub id: String,
+    pub subject_type: String,
+    pub subject_id: String,
+    pub expected: Money,
+    pub actual: Money,
+    pub detected_at: String,
+}
+
+/// Unresolved mismatches between derived balances and the ledger
+pub async fn list_ledger_drift(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<LedgerDriftEntry>>, StatusCode> {
+    info!("📒 Fetching open ledger drift");
+
+    let rows = sqlx::query(
+        "SELECT id, subject_type, subject_id, expected, actual, detected_at
+         FROM ledger_drift
+         WHERE resolved_at IS NULL
+         ORDER BY detected_at DESC"
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch ledger drift: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let entries = rows
+        .iter()
+        .map(|row| LedgerDriftEntry {
+            id: row.get::<Uuid, _>("id").to_string(),
+            subject_type: row.get("subject_type"),
+            subject_id: row.get("subject_id"),
+            expected: ledger::money(row.get("expected")),
+            actual: ledger::money(row.get("actual")),
+            detected_at: row.get::<chrono::DateTime<chrono::Utc>, _>("detected_at")
+                .format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+        .collect();
+
+    Ok(Json(entries))
+}
+
+/// Run reconciliation now instead of waiting for the next scheduled pass
+pub async fn reconcile_ledger(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Drift>>, StatusCode> {
+    info!("📒 Reconciling ledger on request");
+
+    let drift = ledger::reconcile(&state.db.pool).await
+        .map_err(|e| {
+            error!("Ledger reconciliation failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(drift))
+}
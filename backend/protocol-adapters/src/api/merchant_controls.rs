@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::handlers::extract_user_from_headers;
+use crate::AppState;
+use security_gateway::db::MerchantControls;
+use security_gateway::ledger::{self, LEDGER_CURRENCY};
+use security_gateway::Money;
+
+/// A merchant's policies for agent payments; risk decline thresholds live under `/api/v1/risk-thresholds`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MerchantControlSettings {
+    /// Waives the first-payment review
+    #[serde(default)]
+    pub auto_approve_agents: bool,
+    #[serde(default)]
+    pub require_manual_review_first_tx: bool,
+    /// In the ledger currency
+    pub max_agent_tx_amount: Option<Money>,
+    #[serde(default)]
+    pub blocked_agents: Vec<String>,
+    /// When non-empty, only these agents may pay
+    #[serde(default)]
+    pub allowed_agents: Vec<String>,
+    pub fraud_alert_threshold: Option<i32>,
+    pub webhook_url: Option<String>,
+}
+
+impl From<MerchantControls> for MerchantControlSettings {
+    fn from(controls: MerchantControls) -> Self {
+        Self {
+            auto_approve_agents: controls.auto_approve_agents,
+            require_manual_review_first_tx: controls.require_manual_review_first_tx,
+            max_agent_tx_amount: controls.max_agent_tx_amount.map(ledger::money),
+            blocked_agents: controls.blocked_agents.unwrap_or_default(),
+            allowed_agents: controls.allowed_agents.unwrap_or_default(),
+            fraud_alert_threshold: controls.fraud_alert_threshold,
+            webhook_url: controls.webhook_url,
+        }
+    }
+}
+
+/// The merchant itself or an admin
+fn ensure_merchant_access(headers: &HeaderMap, merchant_id: Uuid) -> Result<String, StatusCode> {
+    let claims = extract_user_from_headers(headers)?;
+    let allowed = match claims.role.as_str() {
+        "admin" => true,
+        "merchant" => claims.sub == merchant_id.to_string(),
+        _ => false,
+    };
+    if !allowed {
+        warn!("{} may not manage controls for merchant {}", claims.email, merchant_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(claims.email)
+}
+
+pub async fn get_merchant_controls(
+    State(state): State<Arc<AppState>>,
+    Path(merchant_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<MerchantControlSettings>, StatusCode> {
+    let merchant_uuid = Uuid::parse_str(&merchant_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    ensure_merchant_access(&headers, merchant_uuid)?;
+
+    let controls = state.db.get_merchant_controls(&merchant_id).await
+        .map_err(|e| {
+            error!("Failed to fetch merchant controls: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(controls.map(MerchantControlSettings::from).unwrap_or_default()))
+}
+
+pub async fn update_merchant_controls(
+    State(state): State<Arc<AppState>>,
+    Path(merchant_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<MerchantControlSettings>,
+) -> Result<Json<MerchantControlSettings>, StatusCode> {
+    let merchant_uuid = Uuid::parse_str(&merchant_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let email = ensure_merchant_access(&headers, merchant_uuid)?;
+
+    if let Some(max) = &req.max_agent_tx_amount {
+        if !max.is_positive() || max.currency != LEDGER_CURRENCY {
+            error!("Merchant maximum must be a positive {} amount: {}", LEDGER_CURRENCY, max);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    if req.fraud_alert_threshold.is_some_and(|t| !(0..=100).contains(&t)) {
+        error!("Fraud alert threshold must be between 0 and 100");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(url) = &req.webhook_url {
+        if !(url.starts_with("https://") || url.starts_with("http://")) || url.len() > 500 {
+            error!("Invalid webhook URL: {}", url);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    if let Some(agent) = req.blocked_agents.iter().find(|a| req.allowed_agents.contains(a)) {
+        error!("Agent {} is both blocked and allowed", agent);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    sqlx::query(
+        "INSERT INTO merchant_controls (
+            merchant_id, auto_approve_agents, require_manual_review_first_tx, max_agent_tx_amount,
+            blocked_agents, allowed_agents, fraud_alert_threshold, webhook_url
+         ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (merchant_id) DO UPDATE
+         SET auto_approve_agents = $2, require_manual_review_first_tx = $3, max_agent_tx_amount = $4,
+             blocked_agents = $5, allowed_agents = $6, fraud_alert_threshold = $7, webhook_url = $8,
+             updated_at = NOW()"
+    )
+    .bind(merchant_uuid)
+    .bind(req.auto_approve_agents)
+    .bind(req.require_manual_review_first_tx)
+    .bind(req.max_agent_tx_amount.as_ref().map(Money::to_decimal))
+    .bind(&req.blocked_agents)
+    .bind(&req.allowed_agents)
+    .bind(req.fraud_alert_threshold)
+    .bind(&req.webhook_url)
+    .execute(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to update merchant controls: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("🏪 Controls updated for merchant {} by {}", merchant_id, email);
+    Ok(Json(req))
+}
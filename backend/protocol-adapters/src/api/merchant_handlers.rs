@@ -11,6 +11,7 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::AppState;
+use security_gateway::categories;
 
 #[derive(Debug, Deserialize)]
 pub struct MerchantRegisterRequest {
@@ -21,6 +22,10 @@ pub struct MerchantRegisterRequest {
     pub business_email: Option<String>,
     pub business_address: Option<String>,
     pub checkout_url_pattern: Option<String>,
+    /// e.g. "grocery"; owners' spending policies can allow or deny by category
+    pub category: Option<String>,
+    /// Four-digit merchant category code; owners can restrict agents by MCC
+    pub mcc: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,6 +72,11 @@ pub async fn merchant_register(
 ) -> Result<Json<MerchantAuthResponse>, StatusCode> {
     info!("🏪 Merchant registration attempt: {}", req.email);
 
+    if req.mcc.as_deref().is_some_and(|mcc| !categories::is_mcc(mcc)) {
+        error!("Invalid MCC for {}: {:?}", req.email, req.mcc);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     let password_hash = hash(&req.password, DEFAULT_COST)
         .map_err(|e| {
             error!("Failed to hash password: {}", e);
@@ -80,10 +90,10 @@ pub async fn merchant_register(
         "INSERT INTO merchants (
             id, email, password_hash, merchant_name, domain, 
             business_email, business_address, checkout_url_pattern, 
-            api_key, status, created_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 'pending', NOW())"
+            api_key, category, mcc, status, created_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, 'pending', NOW())"
     )
-    .bind(&merchant_id)
+    .bind(merchant_id)
     .bind(&req.email)
     .bind(&password_hash)
     .bind(&req.merchant_name)
@@ -92,6 +102,8 @@ pub async fn merchant_register(
     .bind(&req.business_address)
     .bind(&req.checkout_url_pattern)
     .bind(&api_key)
+    .bind(req.category.as_deref().map(str::to_lowercase))
+    .bind(&req.mcc)
     .execute(&state.db.pool)
     .await;
 
@@ -41,6 +41,86 @@ pub use admin_block_ledger::{
     get_all_blocks_ledger,
 };
 
+mod ledger;
+
+pub use ledger::{
+    list_ledger_drift,
+    reconcile_ledger,
+};
+
+mod wallet;
+
+pub use wallet::{
+    topup_wallet,
+    withdraw_wallet,
+    get_wallet_history,
+    approve_wallet_operation,
+    reject_wallet_operation,
+    list_pending_wallet_operations,
+};
+
+mod reviews;
+
+pub use reviews::{
+    list_pending_reviews,
+    review_transaction,
+    get_risk_thresholds,
+    update_risk_thresholds,
+};
+
+mod fraud_patterns;
+
+pub use fraud_patterns::{
+    list_fraud_patterns,
+    resolve_fraud_pattern,
+};
+
+mod merchant_controls;
+
+pub use merchant_controls::{
+    get_merchant_controls,
+    update_merchant_controls,
+};
+
+mod policies;
+
+pub use policies::{
+    list_policies,
+    create_policy,
+    update_policy,
+    delete_policy,
+    dry_run_policy,
+};
+
+mod tiers;
+
+pub use tiers::{
+    list_tier_profiles,
+    update_tier_profile,
+    get_agent_tier,
+    change_agent_tier,
+};
+
+mod category_rules;
+
+pub use category_rules::{
+    get_category_rules,
+    update_category_rules,
+};
+
+mod payment_tokens;
+
+pub use payment_tokens::register_payment_token;
+
+mod blacklist;
+
+pub use blacklist::{
+    list_blacklist,
+    add_to_blacklist,
+    remove_from_blacklist,
+    get_blacklist_audit,
+};
+
 mod teams;
 mod network;
 
@@ -1,16 +1,18 @@
 use axum::{
-    extract::Query,
-    extract::{Path, State},
+    extract::State,
     http::{StatusCode, HeaderMap},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
 use crate::AppState;
+use rust_decimal::Decimal;
+use security_gateway::ledger;
+use security_gateway::Money;
 
 #[derive(Debug, Deserialize)]
 pub struct EvaluateRequest {
@@ -29,7 +31,7 @@ pub struct AgentEvaluationResult {
     pub agent_id: String,
     pub agent_name: String,
     pub foundational_model: String,
-    pub predicted_price: f64,
+    pub predicted_price: Money,
     pub predicted_merchant_id: String,
     pub predicted_merchant_name: String,
     pub predicted_risk_score: i32,
@@ -62,10 +64,10 @@ pub struct GraphNode {
 pub struct NodeStats {
     pub transaction_count: i64,
     pub win_count: i64,
-    pub avg_price: f64,
+    pub avg_price: Money,
     pub risk_score: i32,
     pub last_item: Option<String>,
-    pub last_transaction_amount: Option<f64>,
+    pub last_transaction_amount: Option<Money>,
     pub last_transaction_item: Option<String>,
 }
 
@@ -135,7 +137,7 @@ pub async fn evaluate_agents(
 
         if let Some(agent) = agent_row {
             // Simulate price prediction (random for demo, replace with AI call)
-            let base_price = 899.0;
+            let base_price_cents = 89_900;
             let model: String = agent.get("foundational_model");
             let price_variance = match model.as_str() {
                 "gpt-4" => rand::random::<f64>() * 50.0,
@@ -144,7 +146,7 @@ pub async fn evaluate_agents(
                 _ => rand::random::<f64>() * 70.0,
             };
             
-            let predicted_price = base_price + price_variance;
+            let predicted_price = ledger::money(Decimal::new(base_price_cents + (price_variance * 100.0) as i64, 2));
             let merchant = &merchants[rand::random::<usize>() % merchants.len()];
             let risk_score: i32 = agent.get("risk_score");
 
@@ -158,7 +160,7 @@ pub async fn evaluate_agents(
             .bind(session_id)
             .bind(&agent_id)
             .bind(&req.item_description)
-            .bind(predicted_price)
+            .bind(predicted_price.to_decimal())
             .bind(merchant.get::<Uuid, _>("id"))
             .bind(risk_score)
             .execute(&state.db.pool)
@@ -179,7 +181,7 @@ pub async fn evaluate_agents(
     }
 
     // Sort by price (lowest wins)
-    evaluations.sort_by(|a, b| a.predicted_price.partial_cmp(&b.predicted_price).unwrap());
+    evaluations.sort_by_key(|e| e.predicted_price.minor_units);
     
     // Mark winner
     if !evaluations.is_empty() {
@@ -240,6 +242,7 @@ pub async fn get_network_graph(
             COALESCE(COUNT(DISTINCT ae.id) FILTER (WHERE ae.was_selected = true), 0) as win_count,
             COALESCE(AVG(ae.predicted_price), 0) as avg_price,
             (SELECT t.amount FROM transactions t WHERE t.agent_id = a.id ORDER BY t.created_at DESC LIMIT 1) as last_transaction_amount,
+            (SELECT t.currency FROM transactions t WHERE t.agent_id = a.id ORDER BY t.created_at DESC LIMIT 1) as last_transaction_currency,
             (SELECT t.description FROM transactions t WHERE t.agent_id = a.id ORDER BY t.created_at DESC LIMIT 1) as last_transaction_item
          FROM agents a
          LEFT JOIN agent_evaluations ae ON ae.agent_id = a.id
@@ -266,18 +269,18 @@ pub async fn get_network_graph(
             foundational_model: row.get("foundational_model"),
             tier: Some(row.get("tier")),
             team_id: team_ids.first().map(|id| id.to_string()),
-            team_color: team_colors.last().map(|c| c.clone()),
+            team_color: team_colors.last().cloned(),
             team_ids: team_ids.iter().map(|id| id.to_string()).collect(),
             team_names: team_names.clone(),
             team_colors: team_colors.clone(),
             stats: NodeStats {
                 transaction_count: row.get("transaction_count"),
                 win_count: row.get("win_count"),
-                avg_price: row.get::<rust_decimal::Decimal, _>("avg_price").to_string().parse().unwrap_or(0.0),
+                avg_price: ledger::money(row.get("avg_price")),
                 risk_score: row.get("risk_score"),
                 last_item: None,
-                last_transaction_amount: row.get::<Option<rust_decimal::Decimal>, _>("last_transaction_amount")
-                    .map(|d| d.to_string().parse().unwrap_or(0.0)),
+                last_transaction_amount: row.get::<Option<Decimal>, _>("last_transaction_amount")
+                    .map(|amount| Money::from_stored(amount, row.get::<Option<&str>, _>("last_transaction_currency").unwrap_or(ledger::LEDGER_CURRENCY))),
                 last_transaction_item: row.get("last_transaction_item"),
             },
         });
@@ -312,7 +315,7 @@ pub async fn get_network_graph(
             stats: NodeStats {
                 transaction_count: row.get("tx_count"),
                 win_count: 0,
-                avg_price: 0.0,
+                avg_price: ledger::money(Decimal::ZERO),
                 risk_score: 0,
                 last_item: None,
                 last_transaction_amount: None,
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use super::category_rules::ensure_agent_owner;
+use crate::auth::handlers::extract_user_from_headers;
+use crate::AppState;
+use security_gateway::{payment_token_fingerprint, DelegatedAllowance};
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterPaymentTokenRequest {
+    /// The Shared Payment Token as issued; only its fingerprint is kept
+    pub token: String,
+    /// ACP `allowance` object the token was issued under
+    pub allowance: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentTokenRegistration {
+    pub agent_id: String,
+    pub token_fingerprint: String,
+    pub allowance: DelegatedAllowance,
+}
+
+/// The agent's owner delegates a Shared Payment Token to it.
+///
+/// The gateway only honours tokens registered here, under the allowance given
+/// here; whatever allowance the agent sends alongside a token is ignored.
+pub async fn register_payment_token(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<RegisterPaymentTokenRequest>,
+) -> Result<(StatusCode, Json<PaymentTokenRegistration>), StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let caller = ensure_agent_owner(&state, &claims, &agent_id).await?;
+
+    if req.token.trim().is_empty() {
+        error!("Payment token is required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let allowance = DelegatedAllowance::from_acp(&req.allowance).ok_or_else(|| {
+        error!("Invalid payment token allowance: {}", req.allowance);
+        StatusCode::BAD_REQUEST
+    })?;
+    if !allowance.max_amount.is_positive() || allowance.expires_at <= Utc::now() {
+        error!("Payment token allowance must be positive and unexpired");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let fingerprint = payment_token_fingerprint(&req.token);
+    let registered = state.db.register_payment_token(&fingerprint, &agent_id, &allowance, caller).await
+        .map_err(|e| {
+            error!("Failed to register payment token: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !registered {
+        error!("Payment token {} is already registered", fingerprint);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    info!("🎟️ Payment token {} delegated to agent {} up to {}", fingerprint, agent_id, allowance.max_amount);
+    Ok((StatusCode::CREATED, Json(PaymentTokenRegistration {
+        agent_id,
+        token_fingerprint: fingerprint,
+        allowance,
+    })))
+}
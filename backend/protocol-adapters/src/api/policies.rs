@@ -0,0 +1,329 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::handlers::{extract_user_from_headers, Claims};
+use crate::AppState;
+use security_gateway::fx;
+use security_gateway::policy::{self, Effect, PolicyDocument, PolicyInput, RuleOutcome};
+use security_gateway::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePolicyRequest {
+    pub name: String,
+    /// Exactly one of `agent_id` and `team_id`
+    pub agent_id: Option<String>,
+    pub team_id: Option<String>,
+    /// A policy object, or JSON/YAML text
+    pub document: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePolicyRequest {
+    pub name: Option<String>,
+    pub document: Option<Value>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunRequest {
+    pub agent_id: String,
+    pub merchant_id: String,
+    pub amount: Option<Money>,
+    /// Defaults to now
+    pub at: Option<DateTime<Utc>>,
+    /// Evaluate this document instead of the agent's stored policies
+    pub document: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyResponse {
+    pub id: String,
+    pub name: String,
+    pub agent_id: Option<String>,
+    pub team_id: Option<String>,
+    pub document: Value,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunResponse {
+    /// `None` when no rule matches and the payment would pass the policies
+    pub effect: Option<Effect>,
+    pub matched_rule: Option<RuleOutcome>,
+    /// The amount the rules compared, in the owner's base currency
+    pub base_amount: Option<Money>,
+    pub rules: Vec<RuleOutcome>,
+}
+
+const POLICY_COLUMNS: &str = "id, name, agent_id, team_id, document, enabled, created_at, updated_at";
+
+fn policy_from_row(row: &sqlx::postgres::PgRow) -> PolicyResponse {
+    let format = |t: chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    PolicyResponse {
+        id: row.get::<Uuid, _>("id").to_string(),
+        name: row.get("name"),
+        agent_id: row.get("agent_id"),
+        team_id: row.get::<Option<Uuid>, _>("team_id").map(|id| id.to_string()),
+        document: row.get("document"),
+        enabled: row.get("enabled"),
+        created_at: format(row.get("created_at")),
+        updated_at: format(row.get("updated_at")),
+    }
+}
+
+fn parse_document(value: Value) -> Result<PolicyDocument, StatusCode> {
+    let parsed = match value {
+        Value::String(raw) => PolicyDocument::parse(&raw),
+        other => PolicyDocument::from_json(other),
+    };
+    parsed.map_err(|e| {
+        error!("Invalid policy document: {}", e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+fn caller_id(claims: &Claims) -> Result<Uuid, StatusCode> {
+    Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+async fn ensure_agent_owner(state: &AppState, claims: &Claims, agent_id: &str) -> Result<(), StatusCode> {
+    let owned: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM agents WHERE id = $1 AND ($2 OR user_id = $3))"
+    )
+    .bind(agent_id)
+    .bind(claims.role == "admin")
+    .bind(caller_id(claims)?)
+    .fetch_one(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch agent: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if owned { Ok(()) } else { Err(StatusCode::NOT_FOUND) }
+}
+
+async fn ensure_team_owner(state: &AppState, claims: &Claims, team_id: Uuid) -> Result<(), StatusCode> {
+    let owned: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM agent_teams WHERE id = $1 AND ($2 OR owner_user_id = $3))"
+    )
+    .bind(team_id)
+    .bind(claims.role == "admin")
+    .bind(caller_id(claims)?)
+    .fetch_one(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch team: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if owned { Ok(()) } else { Err(StatusCode::NOT_FOUND) }
+}
+
+/// The caller's policies; admins see everyone's
+pub async fn list_policies(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PolicyResponse>>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM spending_policies WHERE $1 OR user_id = $2 ORDER BY created_at",
+        POLICY_COLUMNS
+    ))
+    .bind(claims.role == "admin")
+    .bind(caller_id(&claims)?)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch policies: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.iter().map(policy_from_row).collect()))
+}
+
+pub async fn create_policy(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<CreatePolicyRequest>,
+) -> Result<(StatusCode, Json<PolicyResponse>), StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+
+    if req.name.trim().is_empty() {
+        error!("Policy name is required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let team_id = match (&req.agent_id, &req.team_id) {
+        (Some(agent_id), None) => {
+            ensure_agent_owner(&state, &claims, agent_id).await?;
+            None
+        }
+        (None, Some(team_id)) => {
+            let team_id = Uuid::parse_str(team_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+            ensure_team_owner(&state, &claims, team_id).await?;
+            Some(team_id)
+        }
+        _ => {
+            error!("A policy is attached to exactly one agent or team");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    let document = parse_document(req.document)?;
+
+    let row = sqlx::query(&format!(
+        "INSERT INTO spending_policies (user_id, agent_id, team_id, name, document)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING {}",
+        POLICY_COLUMNS
+    ))
+    .bind(caller_id(&claims)?)
+    .bind(&req.agent_id)
+    .bind(team_id)
+    .bind(&req.name)
+    .bind(serde_json::json!(document))
+    .fetch_one(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to create policy: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("📜 Policy '{}' created by {} with {} rules", req.name, claims.email, document.rules.len());
+    Ok((StatusCode::CREATED, Json(policy_from_row(&row))))
+}
+
+pub async fn update_policy(
+    State(state): State<Arc<AppState>>,
+    Path(policy_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<UpdatePolicyRequest>,
+) -> Result<Json<PolicyResponse>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let policy_uuid = Uuid::parse_str(&policy_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let document = req.document.map(parse_document).transpose()?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE spending_policies
+         SET name = COALESCE($4, name), document = COALESCE($5, document), enabled = COALESCE($6, enabled),
+             updated_at = NOW()
+         WHERE id = $1 AND ($2 OR user_id = $3)
+         RETURNING {}",
+        POLICY_COLUMNS
+    ))
+    .bind(policy_uuid)
+    .bind(claims.role == "admin")
+    .bind(caller_id(&claims)?)
+    .bind(&req.name)
+    .bind(document.map(|d| serde_json::json!(d)))
+    .bind(req.enabled)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to update policy: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("📜 Policy {} updated by {}", policy_id, claims.email);
+    Ok(Json(policy_from_row(&row)))
+}
+
+pub async fn delete_policy(
+    State(state): State<Arc<AppState>>,
+    Path(policy_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let policy_uuid = Uuid::parse_str(&policy_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let result = sqlx::query("DELETE FROM spending_policies WHERE id = $1 AND ($2 OR user_id = $3)")
+        .bind(policy_uuid)
+        .bind(claims.role == "admin")
+        .bind(caller_id(&claims)?)
+        .execute(&state.db.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete policy: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!("🗑️ Policy {} deleted by {}", policy_id, claims.email);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Explain how the agent's policies (or a draft document) would treat a hypothetical payment
+pub async fn dry_run_policy(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<DryRunRequest>,
+) -> Result<Json<DryRunResponse>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    ensure_agent_owner(&state, &claims, &req.agent_id).await?;
+
+    let agent = state.db.get_agent(&req.agent_id).await
+        .map_err(|e| {
+            error!("Failed to fetch agent: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let base_amount = match &req.amount {
+        Some(amount) => Some(
+            fx::to_base(state.rates.as_ref(), amount, agent.base_currency()).await
+                .map_err(|e| {
+                    error!("Cannot convert {} to {}: {}", amount.currency, agent.base_currency(), e);
+                    StatusCode::UNPROCESSABLE_ENTITY
+                })?
+                .base,
+        ),
+        None => None,
+    };
+
+    let policies = match req.document {
+        Some(document) => vec![("draft".to_string(), parse_document(document)?)],
+        None => state.db.get_policies(&req.agent_id).await
+            .map_err(|e| {
+                error!("Failed to load policies: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?,
+    };
+
+    let input = PolicyInput {
+        agent_id: &req.agent_id,
+        merchant_id: &req.merchant_id,
+        amount: base_amount.as_ref(),
+        tier: agent.tier.as_deref(),
+        timezone: agent.timezone(),
+        now: req.at.unwrap_or_else(Utc::now),
+    };
+    let evaluation = policy::evaluate(&policies, state.db.as_ref(), &input).await
+        .map_err(|e| {
+            error!("Policy evaluation failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(DryRunResponse {
+        effect: evaluation.effect(),
+        matched_rule: evaluation.decisive().cloned(),
+        base_amount,
+        rules: evaluation.rules,
+    }))
+}
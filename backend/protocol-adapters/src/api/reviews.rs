@@ -0,0 +1,320 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::handlers::{extract_user_from_headers, Claims};
+use crate::parked::{self, Release};
+use crate::AppState;
+use security_gateway::{balance, reservations, DeclineCode, Money, ReviewParty};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewAction {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewRequest {
+    pub action: ReviewAction,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingReview {
+    pub transaction_id: String,
+    pub agent_id: String,
+    pub merchant_id: String,
+    pub amount: Option<Money>,
+    pub risk_score: Option<i32>,
+    pub risk_factors: Option<serde_json::Value>,
+    pub review_reason: Option<String>,
+    /// `owner` or `merchant`: who must resolve it
+    pub review_party: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewedTransaction {
+    pub transaction_id: String,
+    pub status: String,
+    pub decline_code: Option<String>,
+}
+
+/// Risk thresholds for the caller: an owner's apply to their agents, a merchant's to payments it receives
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RiskThresholdSettings {
+    pub review_threshold: Option<i32>,
+    pub decline_threshold: Option<i32>,
+}
+
+/// Transactions waiting for the caller's review: those parked for the owner of their agents,
+/// or for the merchant being paid
+pub async fn list_pending_reviews(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PendingReview>>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let caller = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let rows = sqlx::query(
+        "SELECT t.id, t.agent_id, t.merchant_id, t.amount, t.currency, t.risk_score, t.risk_factors,
+                t.review_reason, COALESCE(t.review_party, $3) AS review_party, t.created_at
+         FROM transactions t
+         JOIN agents a ON a.id = t.agent_id
+         WHERE t.status = 'pending_review'
+           AND ($1 = 'admin'
+                OR ($1 = 'merchant' AND t.merchant_id = $2 AND t.review_party = $4)
+                OR ($1 <> 'merchant' AND a.user_id = $2 AND COALESCE(t.review_party, $3) = $3))
+         ORDER BY t.created_at"
+    )
+    .bind(&claims.role)
+    .bind(caller)
+    .bind(ReviewParty::Owner.as_str())
+    .bind(ReviewParty::Merchant.as_str())
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to list pending reviews: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let reviews = rows
+        .into_iter()
+        .map(|row| PendingReview {
+            transaction_id: row.get::<Uuid, _>("id").to_string(),
+            agent_id: row.get("agent_id"),
+            merchant_id: row.get::<Uuid, _>("merchant_id").to_string(),
+            amount: row.get::<Option<rust_decimal::Decimal>, _>("amount")
+                .map(|amount| Money::from_stored(amount, &row.get::<String, _>("currency"))),
+            risk_score: row.get("risk_score"),
+            risk_factors: row.get("risk_factors"),
+            review_reason: row.get("review_reason"),
+            review_party: row.get("review_party"),
+            created_at: row.get::<chrono::NaiveDateTime, _>("created_at")
+                .format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+        .collect();
+
+    Ok(Json(reviews))
+}
+
+/// Approve or reject a parked transaction as the party it waits on (owner or merchant) or an admin
+pub async fn review_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(transaction_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ReviewRequest>,
+) -> Result<Json<ReviewedTransaction>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    info!("🔎 Reviewing transaction {}: {:?}", transaction_id, req.action);
+
+    let transaction_uuid = Uuid::parse_str(&transaction_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let reviewer = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let row = sqlx::query(
+        "SELECT t.merchant_id, a.user_id, t.review_party
+         FROM transactions t
+         JOIN agents a ON a.id = t.agent_id
+         WHERE t.id = $1 AND t.status = 'pending_review'
+         FOR UPDATE OF t"
+    )
+    .bind(transaction_uuid)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or_else(|| {
+        error!("Transaction not found or not awaiting review: {}", transaction_id);
+        StatusCode::NOT_FOUND
+    })?;
+
+    // Rows parked before the party was recorded are the owner's
+    let party = match row.get::<Option<String>, _>("review_party").as_deref() {
+        Some("merchant") => ReviewParty::Merchant,
+        _ => ReviewParty::Owner,
+    };
+    if !may_review(&claims, reviewer, party, row.get("merchant_id"), row.get("user_id")) {
+        warn!("{} may not review transaction {}", claims.email, transaction_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Approved payments are completed only once the merchant has accepted the parked request
+    let (status, decline_code) = match req.action {
+        ReviewAction::Approve => ("approved", None),
+        ReviewAction::Reject => ("declined", Some(DeclineCode::ReviewRejected.as_str())),
+    };
+
+    let reviewed = sqlx::query(
+        "UPDATE transactions
+         SET status = $2, decline_code = $3, reviewed_by = $4, reviewer_role = $5, review_note = $6,
+             reviewed_at = NOW()
+         WHERE id = $1
+         RETURNING agent_id, COALESCE(ledger_amount, amount) AS amount, balance_held"
+    )
+    .bind(transaction_uuid)
+    .bind(status)
+    .bind(decline_code)
+    .bind(reviewer)
+    .bind(&claims.role)
+    .bind(&req.note)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to record review: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // An approved payment keeps its wallet hold until the parked request is sent
+    if matches!(req.action, ReviewAction::Reject) && reviewed.get::<bool, _>("balance_held") {
+        balance::release(&mut tx, &reviewed.get::<String, _>("agent_id"), reviewed.get("amount")).await
+            .map_err(|e| {
+                error!("Failed to release held balance: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    // Approved transactions count against the budgets themselves, so the spend
+    // hold is settled rather than left to expire while the payment waits to be sent
+    if matches!(req.action, ReviewAction::Approve) {
+        reservations::settle_transaction(&mut tx, transaction_uuid).await
+            .map_err(|e| {
+                error!("Failed to settle spend hold: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("✅ Transaction {} {} by {}", transaction_id, status, claims.email);
+
+    let (status, decline_code) = match req.action {
+        ReviewAction::Reject => {
+            if let Err(e) = state.reservations.release_transaction(transaction_uuid).await {
+                error!("Failed to release spend hold: {}", e);
+            }
+            (status, decline_code)
+        }
+        ReviewAction::Approve => send_approved(&state, transaction_uuid).await,
+    };
+
+    Ok(Json(ReviewedTransaction {
+        transaction_id,
+        status: status.to_string(),
+        decline_code: decline_code.map(|c| c.to_string()),
+    }))
+}
+
+/// Send the parked request now; if that fails it stays approved for the agent to retry
+async fn send_approved(state: &AppState, transaction_id: Uuid) -> (&'static str, Option<&'static str>) {
+    match parked::release(state, transaction_id, None).await {
+        Ok(Release::Sent(response)) => {
+            // Read the reply to the end so a streamed payment confirmation is still recorded
+            tokio::spawn(async move {
+                if let Err(e) = axum::body::to_bytes(response.into_body(), usize::MAX).await {
+                    warn!("Merchant reply for transaction {} ended early: {}", transaction_id, e);
+                }
+            });
+            ("completed", None)
+        }
+        Ok(Release::Declined(code)) => ("declined", Some(code.as_str())),
+        Ok(Release::Failed(_)) | Ok(Release::Unavailable) => ("approved", None),
+        Err(e) => {
+            error!("Failed to send approved transaction {}: {}", transaction_id, e);
+            ("approved", None)
+        }
+    }
+}
+
+fn may_review(claims: &Claims, reviewer: Uuid, party: ReviewParty, merchant_id: Uuid, owner_id: Option<Uuid>) -> bool {
+    match (claims.role.as_str(), party) {
+        ("admin", _) => true,
+        ("merchant", ReviewParty::Merchant) => reviewer == merchant_id,
+        ("merchant", ReviewParty::Owner) => false,
+        (_, ReviewParty::Owner) => owner_id == Some(reviewer),
+        (_, ReviewParty::Merchant) => false,
+    }
+}
+
+pub async fn get_risk_thresholds(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<RiskThresholdSettings>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let caller = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let query = if claims.role == "merchant" {
+        "SELECT fraud_alert_threshold, risk_decline_threshold FROM merchant_controls WHERE merchant_id = $1"
+    } else {
+        "SELECT risk_review_threshold, risk_decline_threshold FROM users WHERE id = $1"
+    };
+    let row: Option<(Option<i32>, Option<i32>)> = sqlx::query_as(query)
+        .bind(caller)
+        .fetch_optional(&state.db.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch risk thresholds: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (review_threshold, decline_threshold) = row.unwrap_or_default();
+    Ok(Json(RiskThresholdSettings { review_threshold, decline_threshold }))
+}
+
+/// Set the caller's thresholds; `null` falls back to the platform default
+pub async fn update_risk_thresholds(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<RiskThresholdSettings>,
+) -> Result<Json<RiskThresholdSettings>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    let caller = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let in_range = |t: Option<i32>| t.is_none_or(|t| (0..=100).contains(&t));
+    if !in_range(req.review_threshold) || !in_range(req.decline_threshold) {
+        error!("Risk thresholds must be between 0 and 100");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let (Some(review), Some(decline)) = (req.review_threshold, req.decline_threshold) {
+        if review > decline {
+            error!("Review threshold {} is above decline threshold {}", review, decline);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let query = if claims.role == "merchant" {
+        "INSERT INTO merchant_controls (merchant_id, fraud_alert_threshold, risk_decline_threshold)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (merchant_id) DO UPDATE
+         SET fraud_alert_threshold = $2, risk_decline_threshold = $3, updated_at = NOW()"
+    } else {
+        "UPDATE users SET risk_review_threshold = $2, risk_decline_threshold = $3 WHERE id = $1"
+    };
+    sqlx::query(query)
+        .bind(caller)
+        .bind(req.review_threshold)
+        .bind(req.decline_threshold)
+        .execute(&state.db.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to update risk thresholds: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("⚖️ Risk thresholds updated for {} ({})", claims.email, claims.role);
+    Ok(Json(req))
+}
@@ -10,6 +10,8 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::AppState;
+use security_gateway::ledger;
+use security_gateway::Money;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateTeamRequest {
@@ -331,7 +333,7 @@ pub async fn get_team_evaluation_history(
                     "agent_id": r.get::<String, _>("agent_id"),
                     "agent_name": r.get::<String, _>("agent_name"),
                     "foundational_model": r.get::<String, _>("foundational_model"),
-                    "predicted_price": r.get::<rust_decimal::Decimal, _>("predicted_price").to_string().parse::<f64>().unwrap_or(0.0),
+                    "predicted_price": ledger::money(r.get("predicted_price")),
                     "predicted_merchant_name": r.get::<Option<String>, _>("predicted_merchant_name"),
                     "predicted_risk_score": r.get::<i32, _>("predicted_risk_score"),
                     "was_selected": r.get::<bool, _>("was_selected"),
@@ -344,7 +346,7 @@ pub async fn get_team_evaluation_history(
 
         let transaction = if let Some(w) = winner {
             let tx_row = sqlx::query(
-                "SELECT t.amount, t.status, m.merchant_name
+                "SELECT t.amount, t.currency, t.status, m.merchant_name
                  FROM transactions t
                  JOIN merchants m ON m.id = t.merchant_id
                  WHERE t.agent_id::text = $1
@@ -359,7 +361,7 @@ pub async fn get_team_evaluation_history(
 
             tx_row.map(|r| {
                 serde_json::json!({
-                    "amount": r.get::<rust_decimal::Decimal, _>("amount").to_string().parse::<f64>().unwrap_or(0.0),
+                    "amount": Money::from_stored(r.get("amount"), r.get("currency")),
                     "merchant_name": r.get::<String, _>("merchant_name"),
                     "status": r.get::<String, _>("status"),
                 })
@@ -0,0 +1,295 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::handlers::extract_user_from_headers;
+use crate::AppState;
+use security_gateway::tiers::{self, TierHistory, TierProfile, TierRecommendation};
+
+/// Editable part of a tier profile; amounts are in each owner's base currency
+#[derive(Debug, Deserialize)]
+pub struct UpdateTierProfileRequest {
+    pub max_per_tx: Option<Decimal>,
+    pub max_daily: Option<Decimal>,
+    pub max_monthly: Option<Decimal>,
+    #[serde(default)]
+    pub allowed_protocols: Vec<String>,
+    pub velocity_per_minute: Option<i32>,
+    #[serde(default)]
+    pub requires_manual_review: bool,
+    #[serde(default)]
+    pub min_completed_transactions: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeTierRequest {
+    pub tier: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TierChange {
+    pub agent_id: String,
+    pub from_tier: Option<String>,
+    pub to_tier: String,
+    pub reason: String,
+    pub changed_by: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentTierStatus {
+    pub agent_id: String,
+    pub tier: Option<String>,
+    pub profile: Option<TierProfile>,
+    /// Over the last `tiers::HISTORY_DAYS` days
+    pub history: TierHistory,
+    /// `None` when the agent's tier has no profile
+    pub recommendation: Option<TierRecommendation>,
+    /// Earlier promotions and demotions, newest first
+    pub changes: Vec<TierChange>,
+}
+
+/// Admin caller's user id; everyone else is refused
+fn require_admin(headers: &HeaderMap) -> Result<Uuid, StatusCode> {
+    let claims = extract_user_from_headers(headers)?;
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+fn validate_profile(req: &UpdateTierProfileRequest) -> Result<(), String> {
+    let maxima = [req.max_per_tx, req.max_daily, req.max_monthly];
+    if maxima.iter().flatten().any(|max| *max <= Decimal::ZERO) {
+        return Err("Maxima must be positive".to_string());
+    }
+    let set: Vec<_> = maxima.iter().flatten().collect();
+    if set.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err("Maxima must not shrink from per-transaction to daily to monthly".to_string());
+    }
+    if req.velocity_per_minute.is_some_and(|v| v <= 0) {
+        return Err("Velocity ceiling must be positive".to_string());
+    }
+    if req.min_completed_transactions < 0 {
+        return Err("Completed transactions for promotion cannot be negative".to_string());
+    }
+    if req.allowed_protocols.iter().any(|p| p.trim().is_empty()) {
+        return Err("Protocol names cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Every tier profile, lowest first; visible to any signed-in user
+pub async fn list_tier_profiles(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TierProfile>>, StatusCode> {
+    extract_user_from_headers(&headers)?;
+
+    let profiles = state.db.get_tier_profiles().await
+        .map_err(|e| {
+            error!("Failed to fetch tier profiles: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(profiles))
+}
+
+pub async fn update_tier_profile(
+    State(state): State<Arc<AppState>>,
+    Path(tier): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateTierProfileRequest>,
+) -> Result<Json<TierProfile>, StatusCode> {
+    require_admin(&headers)?;
+
+    if let Err(reason) = validate_profile(&req) {
+        error!("Invalid profile for tier {}: {}", tier, reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let protocols: Vec<String> = req.allowed_protocols.iter().map(|p| p.trim().to_uppercase()).collect();
+
+    let updated = sqlx::query(
+        "UPDATE tier_profiles
+         SET max_per_tx = $2, max_daily = $3, max_monthly = $4, allowed_protocols = $5,
+             velocity_per_minute = $6, requires_manual_review = $7, min_completed_transactions = $8,
+             updated_at = NOW()
+         WHERE tier = $1"
+    )
+    .bind(&tier)
+    .bind(req.max_per_tx)
+    .bind(req.max_daily)
+    .bind(req.max_monthly)
+    .bind(&protocols)
+    .bind(req.velocity_per_minute)
+    .bind(req.requires_manual_review)
+    .bind(req.min_completed_transactions)
+    .execute(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to update tier profile: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if updated.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let profile = state.db.get_tier_profile(&tier).await
+        .map_err(|e| {
+            error!("Failed to fetch tier profile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("🎖️ Tier profile {} updated", tier);
+    Ok(Json(profile))
+}
+
+/// The agent's tier, recent history and a suggested promotion or demotion
+pub async fn get_agent_tier(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<AgentTierStatus>, StatusCode> {
+    require_admin(&headers)?;
+
+    let tier: Option<String> = sqlx::query_scalar("SELECT tier FROM agents WHERE id = $1")
+        .bind(&agent_id)
+        .fetch_optional(&state.db.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch agent: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let internal = |e: anyhow::Error| {
+        error!("Failed to assess agent tier: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let profiles = state.db.get_tier_profiles().await.map_err(internal)?;
+    let history = state.db.tier_history(&agent_id).await.map_err(internal)?;
+
+    let profile = profiles.iter().find(|p| Some(&p.tier) == tier.as_ref()).cloned();
+    let recommendation = tier.as_deref().and_then(|t| tiers::recommend(&profiles, t, &history));
+
+    let rows = sqlx::query(
+        "SELECT agent_id, from_tier, to_tier, reason, changed_by, created_at
+         FROM agent_tier_changes
+         WHERE agent_id = $1
+         ORDER BY created_at DESC"
+    )
+    .bind(&agent_id)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch tier changes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(AgentTierStatus {
+        agent_id,
+        tier,
+        profile,
+        history,
+        recommendation,
+        changes: rows.iter().map(change_from_row).collect(),
+    }))
+}
+
+/// Promote or demote an agent; the change and its reason are kept
+pub async fn change_agent_tier(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ChangeTierRequest>,
+) -> Result<Json<TierChange>, StatusCode> {
+    let admin_id = require_admin(&headers)?;
+
+    if req.reason.trim().is_empty() {
+        error!("Reason for the tier change is required");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let to_tier = req.tier.trim().to_lowercase();
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let known: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM tier_profiles WHERE tier = $1)")
+        .bind(&to_tier)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !known {
+        error!("Unknown tier: {}", to_tier);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let from_tier: Option<String> = sqlx::query_scalar("SELECT tier FROM agents WHERE id = $1 FOR UPDATE")
+        .bind(&agent_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch agent: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if from_tier.as_deref() == Some(to_tier.as_str()) {
+        error!("Agent {} is already {}", agent_id, to_tier);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    sqlx::query("UPDATE agents SET tier = $2 WHERE id = $1")
+        .bind(&agent_id)
+        .bind(&to_tier)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to change agent tier: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let row = sqlx::query(
+        "INSERT INTO agent_tier_changes (agent_id, from_tier, to_tier, reason, changed_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING agent_id, from_tier, to_tier, reason, changed_by, created_at"
+    )
+    .bind(&agent_id)
+    .bind(&from_tier)
+    .bind(&to_tier)
+    .bind(&req.reason)
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to record tier change: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("🎖️ Agent {} moved from {} to {}: {}", agent_id, from_tier.as_deref().unwrap_or("none"), to_tier, req.reason);
+    Ok(Json(change_from_row(&row)))
+}
+
+fn change_from_row(row: &sqlx::postgres::PgRow) -> TierChange {
+    TierChange {
+        agent_id: row.get("agent_id"),
+        from_tier: row.get("from_tier"),
+        to_tier: row.get("to_tier"),
+        reason: row.get("reason"),
+        changed_by: row.get::<Uuid, _>("changed_by").to_string(),
+        created_at: row.get::<chrono::NaiveDateTime, _>("created_at")
+            .format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
@@ -10,14 +10,24 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::AppState;
+use security_gateway::balance;
+use security_gateway::categories::{self, CategoryEffect, LineItem};
+use security_gateway::fx::{self, Conversion};
+use security_gateway::ledger;
+use security_gateway::reservations::{HoldOutcome, HoldRequest};
+use security_gateway::Money;
+
+/// Pending transactions wait on the merchant, so their holds outlive gateway holds
+const PENDING_HOLD_TTL_HOURS: i64 = 24;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateTransactionRequest {
     pub agent_id: String,
     pub merchant_id: String,
-    pub amount: f64,
+    pub amount: Money,
     pub checkout_url: Option<String>,
-    pub items: Option<Vec<String>>,
+    /// Item names, or `{id, name, category}` objects
+    pub items: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,13 +39,18 @@ pub struct TransactionResponse {
     pub agent_owner_email: String,
     pub merchant_id: String,
     pub merchant_name: String,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub status: String,
     pub checkout_url: Option<String>,
-    pub items: Option<Vec<String>>,
+    pub items: Option<serde_json::Value>,
     pub created_at: String,
     pub completed_at: Option<String>,
+    /// Set on declined transactions, e.g. `PER_TX_LIMIT`
+    pub decline_code: Option<String>,
+    pub risk_score: Option<i32>,
+    /// Per-feature contributions to `risk_score`, for gateway-verified transactions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk_factors: Option<serde_json::Value>,
     pub is_blocked: bool,
 }
 
@@ -48,82 +63,144 @@ pub async fn create_transaction(
     let merchant_uuid = Uuid::parse_str(&req.merchant_id)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    // Check if agent is blocked by this merchant
-    let is_blocked: bool = sqlx::query_scalar(
-        "SELECT EXISTS(
-            SELECT 1 FROM merchant_agent_blocks 
-            WHERE merchant_id = $1 AND agent_id = $2
-        )"
-    )
-    .bind(merchant_uuid)
-    .bind(&req.agent_id)
-    .fetch_one(&state.db.pool)
-    .await
-    .unwrap_or(false);
+    // Same access rules the gateway applies on protocol traffic
+    let blacklisted = state.db.blacklist_reason(&req.agent_id).await
+        .map_err(|e| {
+            error!("Failed to check blacklist: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if let Some(reason) = blacklisted {
+        error!("Agent {} is blacklisted: {}", req.agent_id, reason);
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    if is_blocked {
-        error!("Agent {} is blocked by merchant {}", req.agent_id, req.merchant_id);
+    let access = state.db.merchant_access(&merchant_uuid.to_string(), &req.agent_id).await
+        .map_err(|e| {
+            error!("Failed to check merchant access: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !access.allows(&req.agent_id) {
+        error!("Agent {} is not allowed by merchant {}", req.agent_id, req.merchant_id);
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Check agent's remaining balance
-    let agent_row = sqlx::query(
-        "SELECT remaining_balance FROM agents WHERE id = $1"
+    let mut line_items = Vec::new();
+    for item in req.items.iter().flatten() {
+        let Some(line_item) = LineItem::from_value(item) else {
+            error!("Invalid line item: {}", item);
+            return Err(StatusCode::BAD_REQUEST);
+        };
+        line_items.push(line_item);
+    }
+
+    // Pending transactions already wait on the merchant, so only denials apply here
+    let category_rules = state.db.get_category_rules(&req.agent_id).await
+        .map_err(|e| {
+            error!("Failed to fetch category rules: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !category_rules.is_empty() {
+        let mcc = state.db.merchant_mcc(&req.merchant_id).await
+            .map_err(|e| {
+                error!("Failed to fetch merchant MCC: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        let denied: Vec<_> = categories::evaluate(&category_rules, mcc.as_deref(), &line_items)
+            .into_iter()
+            .filter(|r| r.effect == CategoryEffect::Deny)
+            .collect();
+        if !denied.is_empty() {
+            error!("❌ Category rules deny {}: {}", req.agent_id, categories::describe(&denied));
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    if !req.amount.is_positive() {
+        error!("Invalid transaction amount: {}", req.amount);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let base_currency: Option<String> = sqlx::query_scalar(
+        "SELECT COALESCE(u.base_currency, $2)
+         FROM agents a
+         LEFT JOIN users u ON u.id = a.user_id
+         WHERE a.id = $1"
     )
     .bind(&req.agent_id)
+    .bind(fx::DEFAULT_BASE_CURRENCY)
     .fetch_optional(&state.db.pool)
     .await
     .map_err(|e| {
         error!("Failed to fetch agent: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
-    })?
-    .ok_or_else(|| {
-        error!("Agent not found: {}", req.agent_id);
-        StatusCode::NOT_FOUND
     })?;
 
-    let remaining_balance: f64 = agent_row.get::<rust_decimal::Decimal, _>("remaining_balance")
-        .to_string().parse().unwrap_or(0.0);
+    let Some(base_currency) = base_currency else {
+        error!("Agent not found: {}", req.agent_id);
+        return Err(StatusCode::NOT_FOUND);
+    };
 
+    // Budgets are in the owner's base currency
+    let conversion = fx::to_base(state.rates.as_ref(), &req.amount, &base_currency)
+        .await
+        .map_err(|e| {
+            error!("Cannot convert {} to {}: {}", req.amount, base_currency, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    // Agent wallets are held in the ledger currency
+    let ledger_amount = fx::to_base(state.rates.as_ref(), &req.amount, ledger::LEDGER_CURRENCY)
+        .await
+        .map_err(|e| {
+            error!("Cannot convert {} to {}: {}", req.amount, ledger::LEDGER_CURRENCY, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?
+        .base;
 
-    info!("💰 Balance check - Remaining: ${}, Requested: ${}", 
-        remaining_balance, req.amount);
+    let transaction_id = Uuid::new_v4();
 
-    // Reject if transaction would exceed balance
-    if req.amount > remaining_balance {
-        error!("❌ Insufficient balance. Available: ${}, Requested: ${}", remaining_balance, req.amount);
-        return Err(StatusCode::PAYMENT_REQUIRED);
+    // Reserve against the daily and monthly budgets while the transaction is pending
+    let nonce = transaction_id.to_string();
+    let hold = HoldRequest {
+        agent_id: &req.agent_id,
+        nonce: &nonce,
+        transaction_id: Some(transaction_id),
+        amount: &conversion.base,
+        ttl: chrono::Duration::hours(PENDING_HOLD_TTL_HOURS),
+    };
+    match state.reservations.hold(&hold).await {
+        Ok(HoldOutcome::Held(_)) => {}
+        Ok(HoldOutcome::Exceeded { reason, .. }) => {
+            error!("❌ Spending limit reached for {}: {}", req.agent_id, reason);
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Err(e) => {
+            error!("Failed to reserve spend: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
     }
 
-    info!("✅ Balance check passed");
-
-    // Create transaction
-    let transaction_id = Uuid::new_v4();
-    let items_json = req.items.as_ref().map(|i| serde_json::to_value(i).ok()).flatten();
-
-    sqlx::query(
-        "INSERT INTO transactions 
-         (id, agent_id, merchant_id, amount, currency, status, checkout_url, items, created_at)
-         VALUES ($1, $2, $3, $4, 'USD', 'pending', $5, $6, NOW())"
-    )
-    .bind(transaction_id)
-    .bind(&req.agent_id)
-    .bind(merchant_uuid)
-    .bind(req.amount)
-    .bind(&req.checkout_url)
-    .bind(&items_json)
-    .execute(&state.db.pool)
-    .await
-    .map_err(|e| {
-        error!("Failed to create transaction: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    // Hold the funds and create the pending transaction together
+    match create_pending_transaction(&state, &req, merchant_uuid, transaction_id, &conversion, &ledger_amount).await {
+        Ok(true) => info!("✅ Balance held: {}", ledger_amount),
+        Ok(false) => {
+            error!("❌ Insufficient balance for {}. Requested: {}", req.agent_id, ledger_amount);
+            release_spend_hold(&state, transaction_id).await;
+            return Err(StatusCode::PAYMENT_REQUIRED);
+        }
+        Err(e) => {
+            error!("Failed to create transaction: {}", e);
+            release_spend_hold(&state, transaction_id).await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
 
     // Fetch complete transaction details
     let row = sqlx::query(
         "SELECT 
             t.id, t.agent_id, t.merchant_id, t.amount, t.currency, t.status,
-            t.checkout_url, t.items, t.created_at, t.completed_at,
+            t.checkout_url, t.items, t.created_at, t.completed_at, t.decline_code,
+            t.risk_score, t.risk_factors,
             a.agent_name, a.owner_company, a.owner_email,
             m.merchant_name,
             EXISTS(SELECT 1 FROM merchant_agent_blocks WHERE merchant_id = t.merchant_id AND agent_id = t.agent_id) as is_blocked
@@ -148,15 +225,16 @@ pub async fn create_transaction(
         agent_owner_email: row.get::<Option<String>, _>("owner_email").unwrap_or_else(|| "unknown@example.com".to_string()),
         merchant_id: row.get::<Uuid, _>("merchant_id").to_string(),
         merchant_name: row.get("merchant_name"),
-        amount: row.get::<rust_decimal::Decimal, _>("amount").to_string().parse().unwrap_or(0.0),
-        currency: row.get("currency"),
+        amount: Money::from_stored(row.get("amount"), row.get("currency")),
         status: row.get("status"),
         checkout_url: row.get("checkout_url"),
-        items: row.get::<Option<serde_json::Value>, _>("items")
-            .and_then(|v| serde_json::from_value(v).ok()),
+        items: row.get("items"),
         created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
             .format("%Y-%m-%d %H:%M:%S").to_string(),
         completed_at: None,
+        decline_code: row.get("decline_code"),
+        risk_score: row.get("risk_score"),
+        risk_factors: row.get("risk_factors"),
         is_blocked: row.get("is_blocked"),
     };
 
@@ -164,6 +242,52 @@ pub async fn create_transaction(
     Ok(Json(response))
 }
 
+/// Insert a pending transaction with its funds held; false if the balance is short
+async fn create_pending_transaction(
+    state: &AppState,
+    req: &CreateTransactionRequest,
+    merchant_id: Uuid,
+    transaction_id: Uuid,
+    conversion: &Conversion,
+    ledger_amount: &Money,
+) -> anyhow::Result<bool> {
+    let items_json = req.items.as_ref().and_then(|i| serde_json::to_value(i).ok());
+    let mut tx = state.db.pool.begin().await?;
+
+    if !balance::hold(&mut tx, &req.agent_id, ledger_amount.to_decimal()).await? {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "INSERT INTO transactions 
+         (id, agent_id, merchant_id, amount, currency, status, checkout_url, items, balance_held, created_at,
+          base_amount, base_currency, fx_rate, ledger_amount)
+         VALUES ($1, $2, $3, $4, $5, 'pending', $6, $7, TRUE, NOW(), $8, $9, $10, $11)"
+    )
+    .bind(transaction_id)
+    .bind(&req.agent_id)
+    .bind(merchant_id)
+    .bind(req.amount.to_decimal())
+    .bind(&req.amount.currency)
+    .bind(&req.checkout_url)
+    .bind(items_json)
+    .bind(conversion.base.to_decimal())
+    .bind(&conversion.base.currency)
+    .bind(serde_json::json!(conversion.rate))
+    .bind(ledger_amount.to_decimal())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn release_spend_hold(state: &AppState, transaction_id: Uuid) {
+    if let Err(e) = state.reservations.release_transaction(transaction_id).await {
+        error!("Failed to release spend hold: {}", e);
+    }
+}
+
 pub async fn complete_transaction(
     State(state): State<Arc<AppState>>,
     Path(transaction_id): Path<String>,
@@ -173,55 +297,55 @@ pub async fn complete_transaction(
     let transaction_uuid = Uuid::parse_str(&transaction_id)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    // Get transaction details
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Claiming the pending row first means a transaction can only be completed once;
+    // wallets and revenue move in the ledger currency
     let row = sqlx::query(
-        "SELECT agent_id, amount FROM transactions WHERE id = $1 AND status = 'pending'"
+        "UPDATE transactions 
+         SET status = 'completed', completed_at = NOW() 
+         WHERE id = $1 AND status = 'pending'
+         RETURNING agent_id, merchant_id, COALESCE(ledger_amount, amount) AS amount, balance_held"
     )
     .bind(transaction_uuid)
-    .fetch_optional(&state.db.pool)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
-        error!("Failed to fetch transaction: {}", e);
+        error!("Failed to complete transaction: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?
     .ok_or(StatusCode::NOT_FOUND)?;
 
     let agent_id: String = row.get("agent_id");
+    let merchant_id: Uuid = row.get("merchant_id");
     let amount: rust_decimal::Decimal = row.get("amount");
+    let balance_held: bool = row.get("balance_held");
 
-    let mut tx = state.db.pool.begin().await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let debited = balance::capture(&mut tx, &agent_id, amount, balance_held).await
+        .map_err(|e| {
+            error!("Failed to update agent: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
-    // Update transaction status
-    sqlx::query(
-        "UPDATE transactions 
-         SET status = 'completed', completed_at = NOW() 
-         WHERE id = $1"
-    )
-    .bind(transaction_uuid)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        error!("Failed to complete transaction: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    if !debited {
+        // Dropping tx rolls back the status change
+        error!("❌ Insufficient balance to complete {} for agent {}", transaction_id, agent_id);
+        return Err(StatusCode::PAYMENT_REQUIRED);
+    }
 
-    // Update agent - NO last_transaction_at column
-    sqlx::query(
-        "UPDATE agents 
-         SET remaining_balance = remaining_balance - $1,
-             total_volume = total_volume + $1,
-             transaction_count = transaction_count + 1
-         WHERE id = $2"
-    )
-    .bind(amount)
-    .bind(&agent_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        error!("Failed to update agent: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    ledger::record_completion(&mut tx, &agent_id, merchant_id, amount, transaction_uuid).await
+        .map_err(|e| {
+            error!("Failed to record completion: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // The completed row now counts toward the budgets in place of the hold
+    security_gateway::reservations::settle_transaction(&mut tx, transaction_uuid).await
+        .map_err(|e| {
+            error!("Failed to settle spend hold: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
     tx.commit().await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -242,7 +366,8 @@ pub async fn get_merchant_transactions(
     let rows = sqlx::query(
         "SELECT 
             t.id, t.agent_id, t.merchant_id, t.amount, t.currency, t.status,
-            t.checkout_url, t.items, t.created_at, t.completed_at,
+            t.checkout_url, t.items, t.created_at, t.completed_at, t.decline_code,
+            t.risk_score, t.risk_factors,
             a.agent_name, a.owner_company, a.owner_email,
             m.merchant_name,
             EXISTS(SELECT 1 FROM merchant_agent_blocks WHERE merchant_id = t.merchant_id AND agent_id = t.agent_id) as is_blocked
@@ -270,16 +395,17 @@ pub async fn get_merchant_transactions(
             agent_owner_email: row.get::<Option<String>, _>("owner_email").unwrap_or_else(|| "unknown@example.com".to_string()),
             merchant_id: row.get::<Uuid, _>("merchant_id").to_string(),
             merchant_name: row.get("merchant_name"),
-            amount: row.get::<rust_decimal::Decimal, _>("amount").to_string().parse().unwrap_or(0.0),
-            currency: row.get("currency"),
+            amount: Money::from_stored(row.get("amount"), row.get("currency")),
             status: row.get("status"),
             checkout_url: row.get("checkout_url"),
-            items: row.get::<Option<serde_json::Value>, _>("items")
-                .and_then(|v| serde_json::from_value(v).ok()),
+            items: row.get("items"),
             created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
                 .format("%Y-%m-%d %H:%M:%S").to_string(),
             completed_at: row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("completed_at")
                 .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string()),
+            decline_code: row.get("decline_code"),
+            risk_score: row.get("risk_score"),
+            risk_factors: row.get("risk_factors"),
             is_blocked: row.get("is_blocked"),
         })
         .collect();
@@ -296,7 +422,8 @@ pub async fn list_all_transactions(
     let rows = sqlx::query(
         "SELECT 
             t.id, t.agent_id, t.merchant_id, t.amount, t.currency, t.status,
-            t.checkout_url, t.items, t.created_at, t.completed_at,
+            t.checkout_url, t.items, t.created_at, t.completed_at, t.decline_code,
+            t.risk_score, t.risk_factors,
             a.agent_name, a.owner_company, a.owner_email,
             m.merchant_name,
             EXISTS(SELECT 1 FROM merchant_agent_blocks WHERE merchant_id = t.merchant_id AND agent_id = t.agent_id) as is_blocked
@@ -322,16 +449,17 @@ pub async fn list_all_transactions(
             agent_owner_email: row.get::<Option<String>, _>("owner_email").unwrap_or_else(|| "unknown@example.com".to_string()),
             merchant_id: row.get::<Uuid, _>("merchant_id").to_string(),
             merchant_name: row.get("merchant_name"),
-            amount: row.get::<rust_decimal::Decimal, _>("amount").to_string().parse().unwrap_or(0.0),
-            currency: row.get("currency"),
+            amount: Money::from_stored(row.get("amount"), row.get("currency")),
             status: row.get("status"),
             checkout_url: row.get("checkout_url"),
-            items: row.get::<Option<serde_json::Value>, _>("items")
-                .and_then(|v| serde_json::from_value(v).ok()),
+            items: row.get("items"),
             created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
                 .format("%Y-%m-%d %H:%M:%S").to_string(),
             completed_at: row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("completed_at")
                 .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string()),
+            decline_code: row.get("decline_code"),
+            risk_score: row.get("risk_score"),
+            risk_factors: row.get("risk_factors"),
             is_blocked: row.get("is_blocked"),
         })
         .collect();
@@ -66,8 +66,7 @@ pub async fn complete_transaction(
         agent_owner_name: transaction.get::<Option<String>, _>("owner_name").unwrap_or_else(|| "Unknown".to_string()),
         merchant_id: transaction.get("merchant_id"),
         merchant_name: transaction.get::<Option<String>, _>("merchant_name").unwrap_or_else(|| "Unknown".to_string()),
-        amount: transaction.get::<rust_decimal::Decimal, _>("amount").to_string().parse().unwrap_or(0.0),
-        currency: transaction.get("currency"),
+        amount: Money::from_stored(transaction.get("amount"), transaction.get("currency")),
         status: transaction.get("status"),
         checkout_url: transaction.get::<Option<String>, _>("checkout_url").unwrap_or_else(|| "".to_string()),
         items: transaction.get("items"),
@@ -0,0 +1,624 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, PgConnection, Row};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::handlers::{extract_user_from_headers, Claims};
+use crate::AppState;
+use security_gateway::balance;
+use security_gateway::ledger::{self, Journal, LEDGER_CURRENCY};
+use security_gateway::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct WalletAmountRequest {
+    pub amount: Money,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletOperation {
+    pub id: String,
+    pub agent_id: String,
+    pub kind: String,
+    pub amount: Money,
+    pub status: String,
+    pub idempotency_key: String,
+    pub requested_by: String,
+    pub approved_by: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletHistoryEntry {
+    pub journal_id: String,
+    pub kind: String,
+    pub direction: String,
+    pub amount: Money,
+    pub balance_after: Money,
+    pub description: Option<String>,
+    pub reference_id: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletHistory {
+    pub agent_id: String,
+    pub balance: Money,
+    pub remaining_balance: Money,
+    pub held_balance: Money,
+    pub entries: Vec<WalletHistoryEntry>,
+    pub pending_operations: Vec<WalletOperation>,
+}
+
+const OPERATION_COLUMNS: &str =
+    "id, agent_id, kind, amount, currency, status, idempotency_key, requested_by,
+     approved_by, note, created_at, resolved_at";
+
+/// Top-ups at or above `WALLET_TOPUP_APPROVAL_THRESHOLD` wait for a second approver
+fn approval_threshold() -> Option<Decimal> {
+    std::env::var("WALLET_TOPUP_APPROVAL_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse().ok())
+}
+
+pub async fn topup_wallet(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<WalletAmountRequest>,
+) -> Result<(StatusCode, Json<WalletOperation>), StatusCode> {
+    info!("💵 Top-up requested for agent: {}", agent_id);
+    submit_operation(&state, &agent_id, &headers, "topup", req).await
+}
+
+pub async fn withdraw_wallet(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<WalletAmountRequest>,
+) -> Result<(StatusCode, Json<WalletOperation>), StatusCode> {
+    info!("🏧 Withdrawal requested for agent: {}", agent_id);
+    submit_operation(&state, &agent_id, &headers, "withdrawal", req).await
+}
+
+async fn submit_operation(
+    state: &AppState,
+    agent_id: &str,
+    headers: &HeaderMap,
+    kind: &str,
+    req: WalletAmountRequest,
+) -> Result<(StatusCode, Json<WalletOperation>), StatusCode> {
+    let claims = extract_user_from_headers(headers)?;
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let idempotency_key = headers
+        .get("idempotency-key")
+        .and_then(|v| v.to_str().ok())
+        .filter(|k| !k.is_empty() && k.len() <= 255)
+        .ok_or_else(|| {
+            error!("Wallet operation without an Idempotency-Key header");
+            StatusCode::BAD_REQUEST
+        })?
+        .to_string();
+
+    // Wallets are held in the ledger currency
+    if !req.amount.is_positive() || req.amount.currency != LEDGER_CURRENCY {
+        error!("Invalid wallet amount: {}", req.amount);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let amount = req.amount.to_decimal();
+
+    ensure_owner(state, agent_id, user_id).await?;
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let needs_approval = kind == "topup" && approval_threshold().is_some_and(|threshold| amount >= threshold);
+
+    let inserted = sqlx::query(&format!(
+        "INSERT INTO wallet_operations (agent_id, kind, amount, currency, status, idempotency_key, requested_by, note)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (agent_id, idempotency_key) DO NOTHING
+         RETURNING {}", OPERATION_COLUMNS
+    ))
+    .bind(agent_id)
+    .bind(kind)
+    .bind(amount)
+    .bind(&req.amount.currency)
+    .bind(if needs_approval { "pending_approval" } else { "processing" })
+    .bind(&idempotency_key)
+    .bind(user_id)
+    .bind(&req.note)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to record wallet operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(row) = inserted else {
+        // A retry: answer with the original outcome, unless the key was reused for something else
+        let existing = fetch_operation_by_key(&mut tx, agent_id, &idempotency_key).await?;
+        if existing.kind != kind || existing.amount != req.amount {
+            warn!("Idempotency key {} reused for a different wallet operation", idempotency_key);
+            return Err(StatusCode::CONFLICT);
+        }
+        info!("↩️ Replaying wallet operation {}", existing.id);
+        return Ok((StatusCode::OK, Json(existing)));
+    };
+
+    let operation_id: Uuid = row.get("id");
+
+    if needs_approval {
+        tx.commit().await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        info!("⏳ Top-up {} of {} awaits a second approver", operation_id, req.amount);
+        return Ok((StatusCode::ACCEPTED, Json(operation_from_row(&row))));
+    }
+
+    apply_operation(&mut tx, operation_id, agent_id, kind, amount, None).await?;
+    let operation = fetch_operation(&mut tx, operation_id).await?;
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("✅ Wallet {} of {} applied for {}", kind, req.amount, agent_id);
+    Ok((StatusCode::CREATED, Json(operation)))
+}
+
+/// Move the money, post it to the ledger and mark the operation completed
+async fn apply_operation(
+    conn: &mut PgConnection,
+    operation_id: Uuid,
+    agent_id: &str,
+    kind: &str,
+    amount: Decimal,
+    approved_by: Option<Uuid>,
+) -> Result<(), StatusCode> {
+    let (applied, journal) = match kind {
+        "topup" => (
+            balance::fund(&mut *conn, agent_id, amount).await,
+            Journal::topup(agent_id, amount, Some(operation_id)),
+        ),
+        _ => (
+            balance::withdraw(&mut *conn, agent_id, amount).await,
+            Journal::withdrawal(agent_id, amount, Some(operation_id)),
+        ),
+    };
+
+    let applied = applied.map_err(|e| {
+        error!("Failed to update wallet balance: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !applied {
+        error!("❌ Insufficient available balance to withdraw {} from {}", ledger::money(amount), agent_id);
+        return Err(StatusCode::PAYMENT_REQUIRED);
+    }
+
+    let journal_id = ledger::post(&mut *conn, &journal).await
+        .map_err(|e| {
+            error!("Failed to post wallet {} to ledger: {}", kind, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    sqlx::query(
+        "UPDATE wallet_operations
+         SET status = 'completed', journal_id = $2, approved_by = COALESCE($3, approved_by), resolved_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(operation_id)
+    .bind(journal_id)
+    .bind(approved_by)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to complete wallet operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+pub async fn approve_wallet_operation(
+    State(state): State<Arc<AppState>>,
+    Path(operation_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WalletOperation>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    info!("✅ Approving wallet operation: {}", operation_id);
+
+    let operation_uuid = Uuid::parse_str(&operation_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let approver = second_approver(&claims)?;
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let pending = lock_pending(&mut tx, operation_uuid, approver).await?;
+    apply_operation(&mut tx, operation_uuid, &pending.agent_id, &pending.kind, pending.amount, Some(approver)).await?;
+    let operation = fetch_operation(&mut tx, operation_uuid).await?;
+
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    info!("✅ Wallet operation {} approved by {}", operation_id, claims.email);
+    Ok(Json(operation))
+}
+
+pub async fn reject_wallet_operation(
+    State(state): State<Arc<AppState>>,
+    Path(operation_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WalletOperation>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    info!("❌ Rejecting wallet operation: {}", operation_id);
+
+    let operation_uuid = Uuid::parse_str(&operation_id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let approver = second_approver(&claims)?;
+
+    let mut tx = state.db.pool.begin().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    lock_pending(&mut tx, operation_uuid, approver).await?;
+
+    sqlx::query(
+        "UPDATE wallet_operations
+         SET status = 'rejected', approved_by = $2, resolved_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(operation_uuid)
+    .bind(approver)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Failed to reject wallet operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let operation = fetch_operation(&mut tx, operation_uuid).await?;
+    tx.commit().await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(operation))
+}
+
+/// Operations waiting for a second approver
+pub async fn list_pending_wallet_operations(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WalletOperation>>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM wallet_operations WHERE status = 'pending_approval' ORDER BY created_at",
+        OPERATION_COLUMNS
+    ))
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch pending wallet operations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.iter().map(operation_from_row).collect()))
+}
+
+pub async fn get_wallet_history(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WalletHistory>, StatusCode> {
+    let claims = extract_user_from_headers(&headers)?;
+    info!("📒 Fetching wallet history for agent: {}", agent_id);
+
+    if claims.role != "admin" {
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        ensure_owner(&state, &agent_id, user_id).await?;
+    }
+
+    let agent = sqlx::query(
+        "SELECT balance, remaining_balance, held_balance FROM agents WHERE id = $1"
+    )
+    .bind(&agent_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch agent: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Running balance of the agent's wallet account, newest first
+    let rows = sqlx::query(
+        "SELECT * FROM (
+            SELECT j.id AS journal_id, j.kind, j.description, j.reference_id,
+                   e.seq, e.direction, e.amount, e.created_at,
+                   SUM(CASE e.direction WHEN 'credit' THEN e.amount ELSE -e.amount END)
+                       OVER (ORDER BY e.seq) AS balance_after
+            FROM ledger_entries e
+            JOIN ledger_accounts la ON la.id = e.account_id
+            JOIN ledger_journals j ON j.id = e.journal_id
+            WHERE la.account_type = 'agent_wallet' AND la.owner_id = $1
+         ) history
+         ORDER BY seq DESC"
+    )
+    .bind(&agent_id)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch wallet history: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let entries = rows
+        .iter()
+        .map(|row| WalletHistoryEntry {
+            journal_id: row.get::<Uuid, _>("journal_id").to_string(),
+            kind: row.get("kind"),
+            direction: row.get("direction"),
+            amount: ledger::money(row.get("amount")),
+            balance_after: ledger::money(row.get("balance_after")),
+            description: row.get("description"),
+            reference_id: row.get::<Option<Uuid>, _>("reference_id").map(|id| id.to_string()),
+            created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
+                .format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+        .collect();
+
+    let pending = sqlx::query(&format!(
+        "SELECT {} FROM wallet_operations
+         WHERE agent_id = $1 AND status = 'pending_approval'
+         ORDER BY created_at DESC", OPERATION_COLUMNS
+    ))
+    .bind(&agent_id)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch pending wallet operations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(WalletHistory {
+        agent_id,
+        balance: ledger::money(agent.get("balance")),
+        remaining_balance: ledger::money(agent.get("remaining_balance")),
+        held_balance: ledger::money(agent.get("held_balance")),
+        entries,
+        pending_operations: pending.iter().map(operation_from_row).collect(),
+    }))
+}
+
+async fn ensure_owner(state: &AppState, agent_id: &str, user_id: Uuid) -> Result<(), StatusCode> {
+    let owned: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM agents WHERE id = $1 AND user_id = $2)"
+    )
+    .bind(agent_id)
+    .bind(user_id)
+    .fetch_one(&state.db.pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch agent: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if owned { Ok(()) } else { Err(StatusCode::NOT_FOUND) }
+}
+
+/// Only admins approve, and never their own request
+fn second_approver(claims: &Claims) -> Result<Uuid, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+struct PendingOperation {
+    agent_id: String,
+    kind: String,
+    amount: Decimal,
+}
+
+async fn lock_pending(conn: &mut PgConnection, operation_id: Uuid, approver: Uuid) -> Result<PendingOperation, StatusCode> {
+    let row = sqlx::query(
+        "SELECT agent_id, kind, amount, requested_by
+         FROM wallet_operations
+         WHERE id = $1 AND status = 'pending_approval'
+         FOR UPDATE"
+    )
+    .bind(operation_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch wallet operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if row.get::<Uuid, _>("requested_by") == approver {
+        warn!("Requester tried to approve their own wallet operation {}", operation_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(PendingOperation {
+        agent_id: row.get("agent_id"),
+        kind: row.get("kind"),
+        amount: row.get("amount"),
+    })
+}
+
+async fn fetch_operation(conn: &mut PgConnection, operation_id: Uuid) -> Result<WalletOperation, StatusCode> {
+    sqlx::query(&format!("SELECT {} FROM wallet_operations WHERE id = $1", OPERATION_COLUMNS))
+        .bind(operation_id)
+        .fetch_one(conn)
+        .await
+        .map(|row| operation_from_row(&row))
+        .map_err(|e| {
+            error!("Failed to fetch wallet operation: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+async fn fetch_operation_by_key(conn: &mut PgConnection, agent_id: &str, key: &str) -> Result<WalletOperation, StatusCode> {
+    sqlx::query(&format!(
+        "SELECT {} FROM wallet_operations WHERE agent_id = $1 AND idempotency_key = $2",
+        OPERATION_COLUMNS
+    ))
+    .bind(agent_id)
+    .bind(key)
+    .fetch_one(conn)
+    .await
+    .map(|row| operation_from_row(&row))
+    .map_err(|e| {
+        error!("Failed to fetch wallet operation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn operation_from_row(row: &PgRow) -> WalletOperation {
+    WalletOperation {
+        id: row.get::<Uuid, _>("id").to_string(),
+        agent_id: row.get("agent_id"),
+        kind: row.get("kind"),
+        amount: Money::from_stored(row.get("amount"), row.get("currency")),
+        status: row.get("status"),
+        idempotency_key: row.get("idempotency_key"),
+        requested_by: row.get::<Uuid, _>("requested_by").to_string(),
+        approved_by: row.get::<Option<Uuid>, _>("approved_by").map(|id| id.to_string()),
+        note: row.get("note"),
+        created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at")
+            .format("%Y-%m-%d %H:%M:%S").to_string(),
+        resolved_at: row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("resolved_at")
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::handlers::generate_token;
+    use crate::proxy::ProxyConfig;
+    use security_gateway::fx::StaticRateProvider;
+    use security_gateway::reservations::ReservationStore;
+    use security_gateway::{Database, GatewayConfig, SecurityGateway};
+    use sqlx::PgPool;
+
+    async fn state() -> Arc<AppState> {
+        let db = Arc::new(Database::connect().await.expect("connect to DATABASE_URL"));
+        let pool = db.pool.clone();
+        Arc::new(AppState {
+            gateway: Arc::new(SecurityGateway::with_config(GatewayConfig::default()).await.unwrap()),
+            interceptors: Vec::new(),
+            proxy_config: ProxyConfig::from_env(),
+            db,
+            reservations: ReservationStore::new(pool),
+            rates: Arc::new(StaticRateProvider::empty()),
+        })
+    }
+
+    async fn create_user(pool: &PgPool, role: &str) -> (Uuid, String) {
+        let email = format!("wallet-test-{}@example.com", Uuid::new_v4());
+        let user_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (email, password_hash, role) VALUES ($1, 'x', $2) RETURNING id"
+        )
+        .bind(&email)
+        .bind(role)
+        .fetch_one(pool)
+        .await
+        .expect("insert test user");
+        (user_id, email)
+    }
+
+    fn headers(user: &(Uuid, String), role: &str, idempotency_key: Option<&str>) -> HeaderMap {
+        let token = generate_token(&user.0, &user.1, role).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", token).parse().unwrap());
+        if let Some(key) = idempotency_key {
+            headers.insert("idempotency-key", key.parse().unwrap());
+        }
+        headers
+    }
+
+    fn amount(major: &str) -> Json<WalletAmountRequest> {
+        Json(WalletAmountRequest { amount: Money::parse(major, "USD").unwrap(), note: None })
+    }
+
+    async fn remaining_balance(pool: &PgPool, agent_id: &str) -> Decimal {
+        sqlx::query_scalar("SELECT remaining_balance FROM agents WHERE id = $1")
+            .bind(agent_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn retries_replay_and_large_topups_need_another_admin() {
+        std::env::set_var("WALLET_TOPUP_APPROVAL_THRESHOLD", "500");
+        let state = state().await;
+        let pool = state.db.pool.clone();
+
+        // The owner is an admin too, so only the self-approval rule stops them
+        let owner = create_user(&pool, "admin").await;
+        let approver = create_user(&pool, "admin").await;
+        let agent_id = format!("test-wallet-{}", Uuid::new_v4());
+        sqlx::query(
+            "INSERT INTO agents (id, user_id, owner_company, owner_email, protocol)
+             VALUES ($1, $2, 'Wallet Test', 'wallet-test@example.com', 'MCP')"
+        )
+        .bind(&agent_id)
+        .bind(owner.0)
+        .execute(&pool)
+        .await
+        .expect("insert test agent");
+
+        let topup = |key: &'static str, major: &'static str| {
+            let (state, agent_id, headers) = (state.clone(), agent_id.clone(), headers(&owner, "admin", Some(key)));
+            async move { topup_wallet(State(state), Path(agent_id), headers, amount(major)).await }
+        };
+
+        let (status, Json(first)) = topup("topup-1", "100.00").await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(first.status, "completed");
+
+        // A retry answers with the original operation and moves no money
+        let (status, Json(replayed)) = topup("topup-1", "100.00").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replayed.id, first.id);
+        assert_eq!(remaining_balance(&pool, &agent_id).await, Decimal::from(100));
+        assert_eq!(topup("topup-1", "200.00").await.unwrap_err(), StatusCode::CONFLICT);
+
+        let (status, Json(large)) = topup("topup-2", "1000.00").await.unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(large.status, "pending_approval");
+        assert_eq!(remaining_balance(&pool, &agent_id).await, Decimal::from(100));
+
+        let approve = |user: &(Uuid, String)| {
+            let (state, id, headers) = (state.clone(), large.id.clone(), headers(user, "admin", None));
+            async move { approve_wallet_operation(State(state), Path(id), headers).await }
+        };
+        assert_eq!(approve(&owner).await.unwrap_err(), StatusCode::FORBIDDEN);
+        let Json(approved) = approve(&approver).await.unwrap();
+        assert_eq!(approved.status, "completed");
+        assert_eq!(approved.approved_by, Some(approver.0.to_string()));
+        assert_eq!(remaining_balance(&pool, &agent_id).await, Decimal::from(1100));
+        assert_eq!(approve(&approver).await.unwrap_err(), StatusCode::NOT_FOUND);
+
+        sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM users WHERE id = ANY($1)").bind(vec![owner.0, approver.0]).execute(&pool).await.unwrap();
+    }
+}
@@ -106,7 +106,7 @@ pub async fn register(
         "INSERT INTO users (id, email, password_hash, full_name, role, created_at) 
          VALUES ($1, $2, $3, $4, 'user', NOW())"
     )
-    .bind(&user_id)
+    .bind(user_id)
     .bind(&req.email)
     .bind(&password_hash)
     .bind(&req.full_name)
@@ -1,34 +1,185 @@
+use super::buffered::BufferedRequest;
+use super::checkout_sessions::{is_terminal_status, CheckoutSession, CheckoutSessionStore, MerchantCheckoutState};
 use super::trait_::ProtocolInterceptor;
+use super::upstream::{response_builder, UpstreamRegistry};
 use anyhow::{anyhow, Result};
 use axum::body::Body;
-use axum::http::{Request, Response};
-use security_gateway::{PaymentMethodType, Protocol, SecurityContext};
+use axum::http::{Method, Request, Response, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use security_gateway::{Decision, Money, PaymentMethodType, Protocol, SecurityContext, VerificationResult};
 use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
-use tracing::{info};
+use std::sync::Arc;
+use tracing::{error, info, warn};
 
 pub struct ACPInterceptor {
-    client: reqwest::Client,
+    upstreams: Arc<UpstreamRegistry>,
+    sessions: CheckoutSessionStore,
 }
 
 impl ACPInterceptor {
-    pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
+    pub fn new(upstreams: Arc<UpstreamRegistry>, sessions: CheckoutSessionStore) -> Self {
+        Self { upstreams, sessions }
+    }
+    
+    /// Fetch the merchant's current view of a checkout session
+    async fn merchant_state(
+        &self,
+        merchant_id: &str,
+        path: &str,
+        request: &BufferedRequest,
+    ) -> Result<MerchantCheckoutState> {
+        let upstream = self.upstreams.resolve(merchant_id).await?;
+        let merchant_url = upstream.acp_url(path, None);
+        
+        let unavailable = || AcpRejection::new(
+            StatusCode::BAD_GATEWAY,
+            "merchant_unavailable",
+            "Could not confirm checkout total with merchant",
+        );
+        
+        let response = upstream.client
+            .get(&merchant_url)
+            .timeout(upstream.timeout)
+            .headers(upstream.outbound_headers(&request.parts.headers))
+            .send()
+            .await
+            .map_err(|e| {
+                warn!("Checkout lookup at {} failed: {}", merchant_url, e);
+                unavailable()
+            })?;
+        
+        if !response.status().is_success() {
+            warn!("Checkout lookup at {} returned {}", merchant_url, response.status());
+            return Err(unavailable().into());
+        }
+        
+        let body: Value = response.json().await.map_err(|_| unavailable())?;
+        Ok(MerchantCheckoutState::from_response(&body).ok_or_else(unavailable)?)
+    }
+    
+    /// Record what the merchant said about the session after a successful call
+    async fn track_session(
+        &self,
+        merchant_id: &str,
+        operation: &CheckoutOperation,
+        request: &BufferedRequest,
+        contexts: &[SecurityContext],
+        response_body: &[u8],
+    ) -> Result<()> {
+        let state = serde_json::from_slice::<Value>(response_body)
+            .ok()
+            .and_then(|body| MerchantCheckoutState::from_response(&body));
+        
+        match operation {
+            CheckoutOperation::Create => {
+                let state = state.ok_or_else(|| anyhow!("Merchant returned no checkout session id"))?;
+                let agent_id = request_agent_id(request)
+                    .ok_or_else(|| anyhow!("Checkout created without agent_id"))?;
+                self.sessions.create(merchant_id, &agent_id, &state).await?;
+                info!("🛒 Checkout session {} opened for agent {}", state.id, agent_id);
+            }
+            CheckoutOperation::Retrieve(_) | CheckoutOperation::Update(_) => {
+                if let Some(state) = state {
+                    self.sessions.sync(merchant_id, &state).await?;
+                }
+            }
+            CheckoutOperation::Complete(id) => {
+                let context = contexts.first()
+                    .ok_or_else(|| anyhow!("Checkout {} completed without a verified context", id))?;
+                self.sessions.complete(merchant_id, id, &context.agent_id, &context.nonce).await?;
+                info!("✅ Checkout session {} completed", id);
+            }
+            CheckoutOperation::Cancel(id) => {
+                self.sessions.cancel(merchant_id, id).await?;
+                info!("🚫 Checkout session {} canceled", id);
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// ACP checkout endpoints, relative to `/acp/{merchant_id}`.
+///
+/// Both the spec's `/checkout_sessions` and the older `/checkout` collection are accepted.
+#[derive(Debug, Clone, PartialEq)]
+enum CheckoutOperation {
+    Create,
+    Retrieve(String),
+    Update(String),
+    Complete(String),
+    Cancel(String),
+}
+
+impl CheckoutOperation {
+    /// Returns the collection path (e.g. `/checkout_sessions`) alongside the operation
+    fn parse(method: &Method, path: &str) -> Option<(String, Self)> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let (collection, rest) = segments.split_first()?;
+        if *collection != "checkout_sessions" && *collection != "checkout" {
+            return None;
         }
+        
+        let operation = match (method, rest) {
+            (&Method::POST, []) => CheckoutOperation::Create,
+            (&Method::GET, [id]) => CheckoutOperation::Retrieve(id.to_string()),
+            (&Method::POST, [id]) => CheckoutOperation::Update(id.to_string()),
+            (&Method::POST, [id, "complete"]) => CheckoutOperation::Complete(id.to_string()),
+            (&Method::POST, [id, "cancel"]) => CheckoutOperation::Cancel(id.to_string()),
+            _ => return None,
+        };
+        
+        Some((format!("/{}", collection), operation))
     }
 }
 
-/// ACP Checkout Request (simplified)
-#[derive(Debug, Deserialize, Serialize)]
-struct ACPCheckoutRequest {
-    agent_id: String,
-    items: Vec<HashMap<String, serde_json::Value>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    total: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    shared_payment_token: Option<String>,
+/// A request refused before verification, carrying its ACP error code
+#[derive(Debug)]
+struct AcpRejection {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl AcpRejection {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into() }
+    }
+    
+    fn invalid_state(id: &str, status: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "invalid_state",
+            format!("Checkout session {} is already {}", id, status),
+        )
+    }
+}
+
+impl std::fmt::Display for AcpRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for AcpRejection {}
+
+fn request_body(request: &BufferedRequest) -> Result<Value> {
+    if request.body.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(&request.body).map_err(|e| {
+        AcpRejection::new(StatusCode::BAD_REQUEST, "invalid_json", format!("Invalid request body: {}", e)).into()
+    })
+}
+
+fn request_agent_id(request: &BufferedRequest) -> Option<String> {
+    request_body(request).ok()?
+        .get("agent_id")?
+        .as_str()
+        .map(|s| s.to_string())
 }
 
 #[async_trait::async_trait]
@@ -43,95 +194,240 @@ impl ProtocolInterceptor for ACPInterceptor {
         path.contains("/acp/checkout") || path.contains("/checkout")
     }
     
-    async fn extract_security_context(
+    async fn extract_security_contexts(
         &self,
-        request: Request<Body>,
-    ) -> Result<SecurityContext> {
+        request: &BufferedRequest,
+    ) -> Result<Vec<SecurityContext>> {
         info!("=== ACP Interceptor: Extracting Security Context ===");
         
-        let uri = request.uri().clone();
-        let path = uri.path();
+        let path = request.path();
+        let merchant_id = extract_merchant_from_path(path)?;
+        let (collection, operation) = CheckoutOperation::parse(&request.parts.method, upstream_path(path)?)
+            .ok_or_else(|| AcpRejection::new(
+                StatusCode::NOT_FOUND,
+                "unknown_endpoint",
+                format!("Not an ACP checkout endpoint: {} {}", request.parts.method, path),
+            ))?;
+        let body = request_body(request)?;
+        let body_agent = body.get("agent_id").and_then(|v| v.as_str());
         
-        // Parse ACP request
-        let (parts, body) = request.into_parts();
-        let bytes = axum::body::to_bytes(body, usize::MAX).await?;
-        let acp_request: ACPCheckoutRequest = serde_json::from_slice(&bytes)?;
+        // Only completion moves money; the other operations are checked and passed through
+        let id = match &operation {
+            CheckoutOperation::Create => {
+                let agent_id = body_agent.ok_or_else(|| AcpRejection::new(
+                    StatusCode::BAD_REQUEST,
+                    "missing_agent_id",
+                    "agent_id is required to create a checkout session",
+                ))?;
+                info!("ACP Agent: {}", agent_id);
+                
+                match self.sessions.agent_status(agent_id).await?.as_deref() {
+                    Some("active") => {}
+                    Some(status) => return Err(AcpRejection::new(
+                        StatusCode::FORBIDDEN,
+                        "agent_inactive",
+                        format!("Agent is {}", status),
+                    ).into()),
+                    None => return Err(AcpRejection::new(
+                        StatusCode::FORBIDDEN,
+                        "unknown_agent",
+                        format!("Agent not found: {}", agent_id),
+                    ).into()),
+                }
+                return Ok(Vec::new());
+            }
+            CheckoutOperation::Retrieve(_) => return Ok(Vec::new()),
+            CheckoutOperation::Update(id) | CheckoutOperation::Cancel(id) => {
+                if let Some(session) = self.sessions.get(&merchant_id, id).await? {
+                    if session.is_terminal() {
+                        return Err(AcpRejection::invalid_state(id, &session.status).into());
+                    }
+                }
+                return Ok(Vec::new());
+            }
+            CheckoutOperation::Complete(id) => id,
+        };
         
-        info!("ACP Agent: {}", acp_request.agent_id);
+        let session = self.sessions.get(&merchant_id, id).await?
+            .ok_or_else(|| AcpRejection::new(
+                StatusCode::NOT_FOUND,
+                "checkout_session_not_found",
+                format!("Unknown checkout session: {}", id),
+            ))?;
         
-        // Determine operation from path
-        let is_complete = path.contains("/complete");
+        if session.is_terminal() {
+            return Err(AcpRejection::invalid_state(id, &session.status).into());
+        }
+        
+        // The caller must name the session's agent; it is never inferred from the session
+        let agent_id = body_agent.ok_or_else(|| AcpRejection::new(
+            StatusCode::BAD_REQUEST,
+            "missing_agent_id",
+            "agent_id is required to complete a checkout session",
+        ))?;
+        if agent_id != session.agent_id {
+            return Err(AcpRejection::new(
+                StatusCode::FORBIDDEN,
+                "agent_mismatch",
+                format!("Checkout session {} belongs to another agent", id),
+            ).into());
+        }
         
-        let nonce = uuid::Uuid::new_v4().to_string();
-        let transaction_id = nonce.clone();
+        // Amount checks use the merchant's current total, never the agent's claim
+        let state = self.merchant_state(&merchant_id, &format!("{}/{}", collection, id), request).await?;
+        if is_terminal_status(&state.status) {
+            return Err(AcpRejection::invalid_state(id, &state.status).into());
+        }
+        
+        let total = state.total.ok_or_else(|| AcpRejection::new(
+            StatusCode::CONFLICT,
+            "total_unavailable",
+            format!("Merchant has not priced checkout session {}", id),
+        ))?;
         
-        Ok(SecurityContext {
-            agent_id: acp_request.agent_id.clone(),
+        if let Some(agent_total) = body.get("total") {
+            if Money::parse_json(agent_total, &total.currency).ok().as_ref() != Some(&total) {
+                return Err(AcpRejection::new(
+                    StatusCode::BAD_REQUEST,
+                    "total_mismatch",
+                    format!("Agent total {} does not match merchant total {}", agent_total, total),
+                ).into());
+            }
+        }
+        
+        info!("ACP Agent: {}, checkout {} total {}", agent_id, id, total);
+        
+        let payment_token = body.pointer("/payment_data/token")
+            .or_else(|| body.get("shared_payment_token"))
+            .and_then(|t| t.as_str())
+            .map(|t| t.to_string());
+        
+        let mut metadata = HashMap::new();
+        metadata.insert("checkout_session_id".to_string(), Value::String(id.clone()));
+        metadata.insert("line_items".to_string(), state.line_items.clone());
+        
+        Ok(vec![SecurityContext {
+            agent_id: session.agent_id.clone(),
             agent_owner: None,
             foundational_model: Some("OpenAI".to_string()), // ACP created by OpenAI
             protocol: Protocol::ACP,
-            transaction_id,
-            amount: acp_request.total,
-            currency: "USD".to_string(),
-            merchant_id: extract_merchant_from_path(path)?,
+            transaction_id: session.id.to_string(),
+            currency: total.currency.clone(),
+            amount: Some(total),
+            merchant_id,
             merchant_name: None,
             timestamp: Utc::now(),
             user_id: None,
-            session_id: None,
-            ip_address: parts.headers.get("x-forwarded-for")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string()),
-            user_agent: parts.headers.get("user-agent")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string()),
-            payment_method_type: if is_complete {
-                Some(PaymentMethodType::SharedPaymentToken)
-            } else {
-                None
-            },
-            payment_token: acp_request.shared_payment_token.clone(),
+            session_id: Some(id.clone()),
+            ip_address: request.header("x-forwarded-for").map(|s| s.to_string()),
+            user_agent: request.header("user-agent").map(|s| s.to_string()),
+            payment_method_type: Some(PaymentMethodType::SharedPaymentToken),
+            payment_token,
             signature: None,
-            nonce,
+            // One completion per session, so a replayed complete fails the nonce check
+            nonce: completion_nonce(&session),
             risk_score: None,
-            metadata: HashMap::new(),
-            raw_request: serde_json::to_value(&acp_request)?,
-        })
+            metadata,
+            raw_request: body,
+        }])
     }
     
     async fn forward_request(
         &self,
-        request: Request<Body>,
-        context: &SecurityContext,
+        request: &BufferedRequest,
+        contexts: &[SecurityContext],
     ) -> Result<Response<Body>> {
         info!("=== ACP Interceptor: Forwarding Request ===");
         
-        // Forward to merchant's ACP endpoint
-        let merchant_url = format!("https://{}{}", context.merchant_id, request.uri().path());
+        // Forward to the merchant's registered ACP endpoint
+        let merchant_id = extract_merchant_from_path(request.path())?;
+        let path = upstream_path(request.path())?;
+        let upstream = self.upstreams.resolve(&merchant_id).await?;
+        let merchant_url = upstream.acp_url(path, request.parts.uri.query());
         
-        info!("Forwarding to: {}", merchant_url);
+        info!("Forwarding to: {} (timeout {:?})", merchant_url, upstream.timeout);
         
-        let (parts, body) = request.into_parts();
-        let bytes = axum::body::to_bytes(body, usize::MAX).await?;
-        
-        let response = self.client
-            .request(parts.method, &merchant_url)
-            .headers(parts.headers)
-            .body(bytes.to_vec())
+        let response = upstream.client
+            .request(request.parts.method.clone(), &merchant_url)
+            .timeout(upstream.timeout)
+            .headers(upstream.outbound_headers(&request.parts.headers))
+            .body(request.body.clone())
             .send()
             .await?;
         
         // Convert to axum response
         let status = response.status();
-        let headers = response.headers().clone();
+        let builder = response_builder(&response);
         let body_bytes = response.bytes().await?;
         
-        let mut builder = Response::builder().status(status);
-        for (key, value) in headers.iter() {
-            builder = builder.header(key, value);
+        if status.is_success() {
+            if let Some((_, operation)) = CheckoutOperation::parse(&request.parts.method, path) {
+                // The merchant has already acted, so a tracking failure must not hide that from the agent
+                if let Err(e) = self.track_session(&merchant_id, &operation, request, contexts, &body_bytes).await {
+                    error!("Failed to record checkout session state: {}", e);
+                }
+            }
         }
         
         Ok(builder.body(Body::from(body_bytes))?)
     }
+    
+    fn decline_response(
+        &self,
+        context: &SecurityContext,
+        verification: &VerificationResult,
+    ) -> Response<Body> {
+        warn!("ACP checkout {:?} for agent {}: {:?}", verification.decision, context.agent_id, verification.reason);
+        
+        // Held for review: the session stays open until the owner or merchant decides
+        let (status, code) = match verification.decision {
+            Decision::Review => (StatusCode::ACCEPTED, "payment_pending_review"),
+            _ => (StatusCode::PAYMENT_REQUIRED, "payment_declined"),
+        };
+        let body = serde_json::json!({
+            "type": "invalid_request",
+            "code": code,
+            "message": verification.reason.clone().unwrap_or_else(|| "Payment declined".to_string()),
+            "param": serde_json::Value::Null,
+            "decision": verification.decision,
+            "decline_code": verification.decline_code,
+            "limit": verification.limit,
+        });
+        
+        (status, Json(body)).into_response()
+    }
+    
+    fn error_response(&self, status: StatusCode, message: &str) -> Response<Body> {
+        let error_type = if status.is_server_error() { "processing_error" } else { "invalid_request" };
+        let body = serde_json::json!({
+            "type": error_type,
+            "code": "request_not_processable",
+            "message": message,
+        });
+        
+        (status, Json(body)).into_response()
+    }
+    
+    fn extraction_error_response(&self, error: &anyhow::Error) -> Response<Body> {
+        let Some(rejection) = error.downcast_ref::<AcpRejection>() else {
+            return self.error_response(StatusCode::BAD_REQUEST, &error.to_string());
+        };
+        
+        let error_type = if rejection.status.is_server_error() { "processing_error" } else { "invalid_request" };
+        let body = serde_json::json!({
+            "type": error_type,
+            "code": rejection.code,
+            "message": rejection.message,
+            "param": serde_json::Value::Null,
+        });
+        
+        (rejection.status, Json(body)).into_response()
+    }
+}
+
+/// The nonce every completion of `session` is verified under
+fn completion_nonce(session: &CheckoutSession) -> String {
+    format!("acp-complete:{}", session.id)
 }
 
 fn extract_merchant_from_path(path: &str) -> Result<String> {
@@ -142,4 +438,160 @@ fn extract_merchant_from_path(path: &str) -> Result<String> {
     } else {
         Err(anyhow!("Could not extract merchant from path: {}", path))
     }
-}
\ No newline at end of file
+}
+
+/// Path after the `/acp/{merchant_id}` prefix, e.g. `/checkout/{id}/complete`
+fn upstream_path(path: &str) -> Result<&str> {
+    let rest = path.strip_prefix("/acp/")
+        .ok_or_else(|| anyhow!("Not an ACP path: {}", path))?;
+    Ok(rest.find('/').map(|i| &rest[i..]).unwrap_or(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use tokio::net::TcpListener;
+
+    /// Stub ACP merchant that cancels on request but keeps reporting the session as payable
+    async fn stub_acp_merchant() -> String {
+        let app = Router::new()
+            .route("/checkout_sessions/:id", get(|| async {
+                Json(json!({
+                    "id": "cs_test",
+                    "status": "ready_for_payment",
+                    "currency": "usd",
+                    "totals": [{ "type": "total", "amount": 4500 }],
+                }))
+            }))
+            .route("/checkout_sessions/:id/cancel", post(|| async {
+                Json(json!({ "id": "cs_test", "status": "canceled" }))
+            }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    async fn buffered(uri: &str, body: Value) -> BufferedRequest {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        BufferedRequest::from_request(request, 1024 * 1024).await.unwrap()
+    }
+
+    async fn connect() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+        PgPool::connect(&url).await.expect("connect to DATABASE_URL")
+    }
+
+    async fn seed(pool: &PgPool, merchant_url: &str) -> (uuid::Uuid, String) {
+        let merchant_id = uuid::Uuid::new_v4();
+        let agent_id = format!("test-acp-{}", merchant_id);
+        sqlx::query(
+            "INSERT INTO merchants (id, email, password_hash, merchant_name, domain, upstream_base_url)
+             VALUES ($1, $2, 'x', 'ACP Test', 'acp-test.example.com', $3)"
+        )
+        .bind(merchant_id)
+        .bind(format!("{}@acp-test.example.com", merchant_id))
+        .bind(merchant_url)
+        .execute(pool)
+        .await
+        .expect("insert test merchant");
+        sqlx::query(
+            "INSERT INTO agents (id, owner_company, owner_email, protocol)
+             VALUES ($1, 'ACP Test', 'acp-test@example.com', 'ACP')"
+        )
+        .bind(&agent_id)
+        .execute(pool)
+        .await
+        .expect("insert test agent");
+        sqlx::query(
+            "INSERT INTO acp_checkout_sessions (merchant_id, merchant_session_id, agent_id, status, currency, total)
+             VALUES ($1, 'cs_test', $2, 'ready_for_payment', 'USD', 45.00)"
+        )
+        .bind(merchant_id)
+        .bind(&agent_id)
+        .execute(pool)
+        .await
+        .expect("insert test checkout session");
+        (merchant_id, agent_id)
+    }
+
+    async fn cleanup(pool: &PgPool, merchant_id: uuid::Uuid, agent_id: &str) {
+        for query in [
+            "DELETE FROM acp_checkout_sessions WHERE merchant_id = $1",
+            "DELETE FROM merchants WHERE id = $1",
+        ] {
+            sqlx::query(query).bind(merchant_id).execute(pool).await.expect("clean up test merchant");
+        }
+        sqlx::query("DELETE FROM agents WHERE id = $1").bind(agent_id).execute(pool).await.expect("clean up test agent");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn canceled_session_cannot_be_completed() {
+        let pool = connect().await;
+        let (merchant_id, agent_id) = seed(&pool, &stub_acp_merchant().await).await;
+
+        let sessions = CheckoutSessionStore::new(pool.clone());
+        let acp = ACPInterceptor::new(Arc::new(UpstreamRegistry::new(pool.clone())), CheckoutSessionStore::new(pool.clone()));
+        let base = format!("/acp/{}/checkout_sessions/cs_test", merchant_id);
+
+        let cancel = buffered(&format!("{}/cancel", base), json!({})).await;
+        assert!(acp.extract_security_contexts(&cancel).await.unwrap().is_empty());
+        let response = acp.forward_request(&cancel, &[]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = sessions.get(&merchant_id.to_string(), "cs_test").await.unwrap().unwrap();
+        assert_eq!(session.status, "canceled");
+
+        // The merchant still reports the session as payable; our record of the cancel wins
+        let complete = buffered(&format!("{}/complete", base), json!({ "agent_id": agent_id })).await;
+        let error = acp.extract_security_contexts(&complete).await.unwrap_err();
+        assert_eq!(acp.extraction_error_response(&error).status(), StatusCode::CONFLICT);
+
+        // A completion that raced past extraction still cannot overwrite the cancel
+        assert!(sessions.complete(&merchant_id.to_string(), "cs_test", &agent_id, "nonce-test").await.is_err());
+        let session = sessions.get(&merchant_id.to_string(), "cs_test").await.unwrap().unwrap();
+        assert_eq!(session.status, "canceled");
+
+        cleanup(&pool, merchant_id, &agent_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn completion_names_the_sessions_agent_and_reuses_its_nonce() {
+        let pool = connect().await;
+        let (merchant_id, agent_id) = seed(&pool, &stub_acp_merchant().await).await;
+
+        let acp = ACPInterceptor::new(Arc::new(UpstreamRegistry::new(pool.clone())), CheckoutSessionStore::new(pool.clone()));
+        let uri = format!("/acp/{}/checkout_sessions/cs_test/complete", merchant_id);
+        let rejected = |body: Value| {
+            let (acp, uri) = (&acp, &uri);
+            async move {
+                let error = acp.extract_security_contexts(&buffered(uri, body).await).await.unwrap_err();
+                acp.extraction_error_response(&error).status()
+            }
+        };
+
+        assert_eq!(rejected(json!({})).await, StatusCode::BAD_REQUEST);
+        assert_eq!(rejected(json!({ "agent_id": "someone-else" })).await, StatusCode::FORBIDDEN);
+
+        // A replayed complete carries the same nonce, so the gateway's nonce check catches it
+        let complete = buffered(&uri, json!({ "agent_id": agent_id })).await;
+        let first = acp.extract_security_contexts(&complete).await.unwrap();
+        let replay = acp.extract_security_contexts(&complete).await.unwrap();
+        assert_eq!(first[0].agent_id, agent_id);
+        assert_eq!(first[0].nonce, replay[0].nonce);
+
+        cleanup(&pool, merchant_id, &agent_id).await;
+    }
+}
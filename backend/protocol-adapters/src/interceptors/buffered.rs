@@ -0,0 +1,77 @@
+use axum::body::{Body, Bytes};
+use axum::http::request::Parts;
+use axum::http::{header, Request, StatusCode};
+use http_body_util::LengthLimitError;
+use std::error::Error as _;
+
+/// An incoming request whose body has been read exactly once.
+///
+/// Extraction, verification and forwarding all borrow the same buffer, so
+/// nothing downstream needs to consume the original `Request<Body>`.
+pub struct BufferedRequest {
+    pub parts: Parts,
+    pub body: Bytes,
+}
+
+#[derive(Debug)]
+pub enum BufferError {
+    /// Declared or actual body size is over the configured maximum
+    TooLarge { limit: usize },
+    /// The body stream failed before it was fully read
+    Unreadable(axum::Error),
+}
+
+impl BufferError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BufferError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            BufferError::Unreadable(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl std::fmt::Display for BufferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BufferError::TooLarge { limit } => write!(f, "Request body exceeds {} bytes", limit),
+            BufferError::Unreadable(e) => write!(f, "Could not read request body: {}", e),
+        }
+    }
+}
+
+impl BufferedRequest {
+    /// Read the body into memory, refusing anything larger than `max_body_bytes`.
+    ///
+    /// A `Content-Length` over the limit is rejected before any bytes are read;
+    /// chunked bodies are cut off as soon as they cross the limit.
+    pub async fn from_request(request: Request<Body>, max_body_bytes: usize) -> Result<Self, BufferError> {
+        let (parts, body) = request.into_parts();
+
+        let declared_length = parts.headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+
+        if declared_length.is_some_and(|len| len > max_body_bytes) {
+            return Err(BufferError::TooLarge { limit: max_body_bytes });
+        }
+
+        let body = axum::body::to_bytes(body, max_body_bytes).await.map_err(|e| {
+            if e.source().is_some_and(|s| s.is::<LengthLimitError>()) {
+                BufferError::TooLarge { limit: max_body_bytes }
+            } else {
+                BufferError::Unreadable(e)
+            }
+        })?;
+
+        Ok(Self { parts, body })
+    }
+
+    pub fn path(&self) -> &str {
+        self.parts.uri.path()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.parts.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
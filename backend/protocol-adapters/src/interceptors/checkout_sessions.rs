@@ -0,0 +1,190 @@
+use anyhow::{anyhow, bail, Result};
+use security_gateway::Money;
+use serde_json::Value;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+/// ACP statuses after which a checkout session can no longer change
+const TERMINAL_STATUSES: &[&str] = &["completed", "canceled"];
+
+/// One ACP checkout session as tracked by the gateway
+#[derive(Debug, Clone)]
+pub struct CheckoutSession {
+    pub id: Uuid,
+    pub agent_id: String,
+    pub status: String,
+}
+
+impl CheckoutSession {
+    pub fn is_terminal(&self) -> bool {
+        TERMINAL_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+pub fn is_terminal_status(status: &str) -> bool {
+    TERMINAL_STATUSES.contains(&status)
+}
+
+/// Checkout state as returned by the merchant's ACP endpoint
+#[derive(Debug, Clone)]
+pub struct MerchantCheckoutState {
+    pub id: String,
+    pub status: String,
+    pub currency: Option<String>,
+    pub total: Option<Money>,
+    pub line_items: Value,
+}
+
+impl MerchantCheckoutState {
+    /// Parse a merchant checkout response.
+    ///
+    /// ACP reports `totals` in minor units (`{"type": "total", "amount": 4500}`);
+    /// merchants on the older draft send a top-level `total` in major units.
+    pub fn from_response(body: &Value) -> Option<Self> {
+        let id = body.get("id")?.as_str()?.to_string();
+        let currency = body.get("currency").and_then(|c| c.as_str()).map(|c| c.to_uppercase());
+
+        // A total without a currency is not a price; the session stays unpriced
+        let total = currency.as_deref().and_then(|currency| {
+            body.get("totals")
+                .and_then(|t| t.as_array())
+                .and_then(|totals| totals.iter().find(|t| t.get("type").and_then(|v| v.as_str()) == Some("total")))
+                .and_then(|t| t.get("amount"))
+                .and_then(|a| a.as_i64())
+                .and_then(|minor| Money::new(minor, currency).ok())
+                .or_else(|| body.get("total").and_then(|t| Money::parse_json(t, currency).ok()))
+        });
+
+        Some(Self {
+            id,
+            status: body.get("status").and_then(|s| s.as_str()).unwrap_or("not_ready_for_payment").to_string(),
+            currency,
+            total,
+            line_items: body.get("line_items").or_else(|| body.get("items")).cloned().unwrap_or(Value::Null),
+        })
+    }
+}
+
+/// Persists ACP checkout sessions in `acp_checkout_sessions`
+pub struct CheckoutSessionStore {
+    pool: PgPool,
+}
+
+impl CheckoutSessionStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Status of the agent starting a checkout, or `None` if it is not registered
+    pub async fn agent_status(&self, agent_id: &str) -> Result<Option<String>> {
+        let status = sqlx::query_scalar("SELECT status FROM agents WHERE id = $1")
+            .bind(agent_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(status)
+    }
+
+    pub async fn get(&self, merchant_id: &str, merchant_session_id: &str) -> Result<Option<CheckoutSession>> {
+        let row = sqlx::query(
+            "SELECT id, agent_id, status
+             FROM acp_checkout_sessions
+             WHERE merchant_id = $1 AND merchant_session_id = $2"
+        )
+        .bind(parse_merchant(merchant_id)?)
+        .bind(merchant_session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| CheckoutSession {
+            id: row.get("id"),
+            agent_id: row.get("agent_id"),
+            status: row.get("status"),
+        }))
+    }
+
+    pub async fn create(&self, merchant_id: &str, agent_id: &str, state: &MerchantCheckoutState) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO acp_checkout_sessions (
+                merchant_id, merchant_session_id, agent_id, status, currency, total, line_items
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (merchant_id, merchant_session_id) DO NOTHING"
+        )
+        .bind(parse_merchant(merchant_id)?)
+        .bind(&state.id)
+        .bind(agent_id)
+        .bind(&state.status)
+        .bind(&state.currency)
+        .bind(state.total.as_ref().map(Money::to_decimal))
+        .bind(&state.line_items)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mirror the merchant's latest view of a session; terminal sessions are left untouched
+    pub async fn sync(&self, merchant_id: &str, state: &MerchantCheckoutState) -> Result<()> {
+        sqlx::query(
+            "UPDATE acp_checkout_sessions
+             SET status = $3, currency = COALESCE($4, currency), total = COALESCE($5, total),
+                 line_items = COALESCE($6, line_items), updated_at = NOW()
+             WHERE merchant_id = $1 AND merchant_session_id = $2
+               AND status NOT IN ('completed', 'canceled')"
+        )
+        .bind(parse_merchant(merchant_id)?)
+        .bind(&state.id)
+        .bind(&state.status)
+        .bind(&state.currency)
+        .bind(state.total.as_ref().map(Money::to_decimal))
+        .bind(if state.line_items.is_null() { None } else { Some(&state.line_items) })
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a session completed and link it to the agent's transaction logged under `nonce`.
+    ///
+    /// Fails if the session was completed or canceled in the meantime, e.g. by a concurrent cancel.
+    pub async fn complete(&self, merchant_id: &str, merchant_session_id: &str, agent_id: &str, nonce: &str) -> Result<()> {
+        let merchant_uuid = parse_merchant(merchant_id)?;
+        let result = sqlx::query(
+            "UPDATE acp_checkout_sessions
+             SET status = 'completed',
+                 transaction_id = (SELECT id FROM transactions
+                                   WHERE agent_id = $3 AND merchant_id = $1 AND nonce = $4),
+                 completed_at = NOW(), updated_at = NOW()
+             WHERE merchant_id = $1 AND merchant_session_id = $2
+               AND status NOT IN ('canceled', 'completed')"
+        )
+        .bind(merchant_uuid)
+        .bind(merchant_session_id)
+        .bind(agent_id)
+        .bind(nonce)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            bail!("Checkout session {} is unknown or already completed or canceled", merchant_session_id);
+        }
+        Ok(())
+    }
+
+    pub async fn cancel(&self, merchant_id: &str, merchant_session_id: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE acp_checkout_sessions
+             SET status = 'canceled', updated_at = NOW()
+             WHERE merchant_id = $1 AND merchant_session_id = $2 AND status <> 'completed'"
+        )
+        .bind(parse_merchant(merchant_id)?)
+        .bind(merchant_session_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn parse_merchant(merchant_id: &str) -> Result<Uuid> {
+    Uuid::parse_str(merchant_id).map_err(|_| anyhow!("Invalid merchant id: {}", merchant_id))
+}
@@ -1,91 +1,118 @@
+use super::buffered::BufferedRequest;
+use super::mcp_stream::{self, ConfirmationWatch, PaymentConfirmation};
+use super::mcp_tools::{ArgumentMapping, PaymentToolConfig};
 use super::trait_::ProtocolInterceptor;
+use super::upstream::{response_builder, UpstreamRegistry};
 use anyhow::{anyhow, Result};
-use axum::body::Body;
-use axum::http::{Request, Response, StatusCode};
-use security_gateway::{PaymentMethodType, Protocol, SecurityContext};
+use axum::body::{Body, Bytes};
+use axum::http::{Method, Request, Response, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use security_gateway::SecurityGateway;
+use security_gateway::{Decision, Money, PaymentMethodType, Protocol, SecurityContext, VerificationResult};
 use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 use tracing::{info, warn};
 
+/// JSON-RPC error code used when the security gateway declines a tool call
+const JSONRPC_PAYMENT_DECLINED: i64 = -32001;
+/// JSON-RPC error code used when a tool call is held for owner or merchant review
+const JSONRPC_PAYMENT_REVIEW: i64 = -32002;
+/// JSON-RPC error code for requests we could not parse into a tool call
+const JSONRPC_INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code for a payment tool call with missing or bad arguments
+const JSONRPC_INVALID_PARAMS: i64 = -32602;
+
+const SESSION_HEADER: &str = "mcp-session-id";
+/// Sessions idle for longer than this lose their merchant affinity
+const SESSION_IDLE_TTL: Duration = Duration::from_secs(60 * 60);
+
 pub struct MCPInterceptor {
-    client: reqwest::Client,
+    upstreams: Arc<UpstreamRegistry>,
+    tools: PaymentToolConfig,
+    /// `Mcp-Session-Id` -> merchant, so follow-up requests stay on the same MCP server
+    sessions: RwLock<HashMap<String, (Instant, String)>>,
+    confirmations: mpsc::UnboundedSender<PaymentConfirmation>,
 }
 
 impl MCPInterceptor {
-    pub fn new() -> Self {
+    pub fn new(upstreams: Arc<UpstreamRegistry>, tools: PaymentToolConfig, gateway: Arc<SecurityGateway>) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            upstreams,
+            tools,
+            sessions: RwLock::new(HashMap::new()),
+            confirmations: mcp_stream::spawn_confirmation_recorder(gateway),
         }
     }
-}
-
-/// MCP Tool Call format (simplified)
-#[derive(Debug, Deserialize, Serialize)]
-struct MCPToolCall {
-    jsonrpc: String,
-    method: String,
-    params: MCPParams,
-    id: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct MCPParams {
-    name: String,
-    arguments: HashMap<String, serde_json::Value>,
-}
-
-#[async_trait::async_trait]
-impl ProtocolInterceptor for MCPInterceptor {
-    fn protocol_name(&self) -> &str {
-        "MCP"
-    }
     
-    fn can_handle(&self, request: &Request<Body>) -> bool {
-        // Detect MCP by checking for MCP-specific headers or path
-        if let Some(content_type) = request.headers().get("content-type") {
-            if content_type.to_str().unwrap_or("").contains("application/json") {
-                // Check if path suggests MCP
-                let path = request.uri().path();
-                return path.contains("/mcp") || path.contains("/tools/call");
-            }
+    /// Build a security context for one JSON-RPC message, or `None` if it is not a
+    /// payment-relevant `tools/call` (initialize, tools/list, resources/*, notifications...)
+    fn payment_context(
+        &self,
+        message: &Value,
+        path_merchant: Option<&str>,
+        request: &BufferedRequest,
+    ) -> Result<Option<SecurityContext>, String> {
+        if message.get("method").and_then(|m| m.as_str()) != Some("tools/call") {
+            return Ok(None);
         }
         
-        // Also check for MCP-specific header
-        request.headers().get("x-mcp-version").is_some()
-    }
-    
-    async fn extract_security_context(
-        &self,
-        request: Request<Body>,
-    ) -> Result<SecurityContext> {
-        info!("=== MCP Interceptor: Extracting Security Context ===");
+        let Some(tool_name) = message.pointer("/params/name").and_then(|n| n.as_str()) else {
+            return Ok(None);
+        };
         
-        // Parse MCP tool call from request body
-        let (parts, body) = request.into_parts();
-        let bytes = axum::body::to_bytes(body, usize::MAX).await?;
-        let mcp_call: MCPToolCall = serde_json::from_slice(&bytes)?;
+        let Some(tool) = self.tools.find(tool_name) else {
+            return Ok(None);
+        };
         
-        info!("MCP Tool: {}", mcp_call.params.name);
+        info!("MCP Tool: {} (payment-relevant)", tool_name);
         
-        // Extract payment-related data from arguments
-        let args = &mcp_call.params.arguments;
+        let empty = serde_json::Map::new();
+        let args = message.pointer("/params/arguments")
+            .and_then(|a| a.as_object())
+            .unwrap_or(&empty);
+        let mapping = &tool.arguments;
         
-        let agent_id = args.get("agent_id")
+        let agent_id = ArgumentMapping::lookup(&mapping.agent_id, args)
             .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing agent_id in MCP call"))?
+            .ok_or("Missing agent_id in MCP call")?
             .to_string();
         
-        let amount = args.get("amount")
-            .and_then(|v| v.as_f64());
+        let arg_merchant = ArgumentMapping::lookup(&mapping.merchant_id, args)
+            .and_then(|v| v.as_str());
         
-        let merchant_id = args.get("merchant_id")
-            .or_else(|| args.get("merchant"))
+        // The merchant in the path decides where the call is forwarded, so a tool
+        // call must not claim to pay someone else
+        let merchant_id = match (path_merchant, arg_merchant) {
+            (Some(path), Some(arg)) if path != arg => {
+                return Err(format!("merchant_id {} does not match MCP endpoint merchant {}", arg, path));
+            }
+            (Some(merchant), _) | (None, Some(merchant)) => merchant.to_string(),
+            (None, None) => return Err("Missing merchant_id in MCP call".to_string()),
+        };
+        
+        let currency = ArgumentMapping::lookup(&mapping.currency, args)
             .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing merchant_id in MCP call"))?
-            .to_string();
+            .or(tool.currency.as_deref())
+            .map(|c| c.to_uppercase());
+        
+        // Without an amount every limit check would skip, so a payment tool must carry one
+        let amount = match (ArgumentMapping::lookup(&mapping.amount, args), &currency) {
+            (Some(value), Some(currency)) => {
+                Money::parse_json(value, currency).map_err(|_| format!("Invalid amount {} for {}", value, currency))?
+            }
+            (Some(_), None) => return Err("Missing currency in MCP call".to_string()),
+            (None, _) => return Err("Missing amount in MCP call".to_string()),
+        };
+        if !amount.is_positive() {
+            return Err(format!("Amount must be positive, got {}", amount));
+        }
         
-        let nonce = args.get("nonce")
+        let nonce = ArgumentMapping::lookup(&mapping.nonce, args)
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
             .unwrap_or_else(|| {
@@ -93,79 +120,516 @@ impl ProtocolInterceptor for MCPInterceptor {
                 uuid::Uuid::new_v4().to_string()
             });
         
-        let transaction_id = mcp_call.id
-            .as_ref()
+        let transaction_id = message.get("id")
             .and_then(|v| v.as_str())
             .unwrap_or(&nonce)
             .to_string();
         
-        info!("Agent: {}, Merchant: {}, Amount: {:?}", agent_id, merchant_id, amount);
+        let payment_method = ArgumentMapping::lookup(&mapping.payment_method, args)
+            .and_then(|v| v.as_str());
         
-        Ok(SecurityContext {
+        info!("Agent: {}, Merchant: {}, Amount: {}", agent_id, merchant_id, amount);
+        
+        Ok(Some(SecurityContext {
             agent_id,
             agent_owner: None,
             foundational_model: Some("OpenAI".to_string()), // MCP commonly used by OpenAI
             protocol: Protocol::MCP,
             transaction_id,
-            amount,
-            currency: "USD".to_string(),
+            currency: amount.currency.clone(),
+            amount: Some(amount),
             merchant_id,
             merchant_name: None,
             timestamp: Utc::now(),
             user_id: None,
-            session_id: None,
-            ip_address: parts.headers.get("x-forwarded-for")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string()),
-            user_agent: parts.headers.get("user-agent")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string()),
-            payment_method_type: args.get("payment_method")
-                .and_then(|v| v.as_str())
-                .map(|_| PaymentMethodType::Card),
-            payment_token: args.get("payment_method")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            session_id: request.header(SESSION_HEADER).map(|s| s.to_string()),
+            ip_address: request.header("x-forwarded-for").map(|s| s.to_string()),
+            user_agent: request.header("user-agent").map(|s| s.to_string()),
+            payment_method_type: payment_method.map(|_| PaymentMethodType::Card),
+            payment_token: payment_method.map(|s| s.to_string()),
             signature: None,
             nonce,
             risk_score: None,
-            metadata: args.clone(),
-            raw_request: serde_json::to_value(&mcp_call)?,
+            metadata: args.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<HashMap<_, _>>(),
+            raw_request: message.clone(),
+        }))
+    }
+    
+    /// Merchant whose MCP server receives the request: the path wins, then the
+    /// merchant that issued the session, then the tool call
+    fn route_merchant(&self, request: &BufferedRequest, contexts: &[SecurityContext]) -> Result<String> {
+        merchant_from_path(request.path())
+            .map(|m| m.to_string())
+            .or_else(|| self.session_merchant(request))
+            .or_else(|| contexts.first().map(|c| c.merchant_id.clone()))
+            .ok_or_else(|| anyhow!("MCP request has no merchant to route to; use /mcp/{{merchant_id}}"))
+    }
+    
+    fn session_merchant(&self, request: &BufferedRequest) -> Option<String> {
+        let session_id = request.header(SESSION_HEADER)?;
+        let mut sessions = self.sessions.write().unwrap();
+        let (last_seen, merchant_id) = sessions.get_mut(session_id)?;
+        *last_seen = Instant::now();
+        Some(merchant_id.clone())
+    }
+    
+    /// The first call that would be forwarded to a merchant other than the one it was verified for.
+    ///
+    /// Without a merchant in the path the session's merchant decides the route,
+    /// so a tool call naming another merchant must not ride on its verification.
+    fn misrouted<'a>(&self, request: &BufferedRequest, contexts: &'a [SecurityContext]) -> Option<(&'a SecurityContext, String)> {
+        let route = self.route_merchant(request, contexts).ok()?;
+        let context = contexts.iter().find(|c| c.merchant_id != route)?;
+        warn!("MCP call for merchant {} would be forwarded to {}", context.merchant_id, route);
+        Some((context, format!("merchant_id {} does not match MCP session merchant {}", context.merchant_id, route)))
+    }
+    
+    /// Track the session the merchant issued (or ended) so later requests stay pinned to it
+    fn track_session(&self, merchant_id: &str, request: &BufferedRequest, response: &reqwest::Response) {
+        let mut sessions = self.sessions.write().unwrap();
+        
+        if request.parts.method == Method::DELETE {
+            if let Some(session_id) = request.header(SESSION_HEADER) {
+                sessions.remove(session_id);
+            }
+            return;
+        }
+        
+        if let Some(session_id) = response.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            sessions.retain(|_, (last_seen, _)| last_seen.elapsed() < SESSION_IDLE_TTL);
+            sessions.insert(session_id.to_string(), (Instant::now(), merchant_id.to_string()));
+        }
+    }
+    
+    async fn send_upstream(&self, merchant_id: &str, request: &BufferedRequest, body: Bytes) -> Result<reqwest::Response> {
+        let upstream = self.upstreams.resolve(merchant_id).await?;
+        let merchant_url = upstream.mcp_url();
+        
+        info!("Forwarding to: {} (timeout {:?})", merchant_url, upstream.timeout);
+        
+        let response = upstream.client
+            .request(request.parts.method.clone(), &merchant_url)
+            .headers(upstream.outbound_headers(&request.parts.headers))
+            .body(body)
+            .send()
+            .await?;
+        
+        self.track_session(merchant_id, request, &response);
+        
+        Ok(response)
+    }
+}
+
+/// `/mcp/{merchant_id}/...` routes to that merchant's MCP server
+fn merchant_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/mcp/")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|segment| uuid::Uuid::parse_str(segment).is_ok())
+}
+
+/// A request we refuse before verification, pre-rendered as a JSON-RPC response
+#[derive(Debug)]
+struct JsonRpcRejection(Value);
+
+impl std::fmt::Display for JsonRpcRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for JsonRpcRejection {}
+
+fn jsonrpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn jsonrpc_decline(context: &SecurityContext, verification: &VerificationResult) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": context.raw_request.get("id").cloned().unwrap_or(Value::Null),
+        "error": {
+            "code": if verification.decision == Decision::Review { JSONRPC_PAYMENT_REVIEW } else { JSONRPC_PAYMENT_DECLINED },
+            "message": verification.reason.clone().unwrap_or_else(|| "Payment declined".to_string()),
+            "data": {
+                "decision": verification.decision,
+                "decline_code": verification.decline_code,
+                "limit": verification.limit,
+                "risk_score": verification.risk_score,
+                "risk_factors": verification.risk_factors,
+                "checks": verification.checks,
+            },
+        },
+    })
+}
+
+/// Notifications have no `id` and must never receive a response
+fn is_notification(message: &Value) -> bool {
+    message.get("id").is_none()
+}
+
+/// Reject a whole batch because element `index` is invalid; each request still gets its own id
+fn batch_rejection(messages: &[Value], index: usize, reason: &str) -> JsonRpcRejection {
+    warn!("Rejecting MCP batch: element {} invalid: {}", index, reason);
+    let errors: Vec<Value> = messages.iter()
+        .enumerate()
+        .filter(|(_, m)| !is_notification(m))
+        .map(|(i, m)| {
+            let id = m.get("id").cloned().unwrap_or(Value::Null);
+            if i == index {
+                jsonrpc_error(id, JSONRPC_INVALID_PARAMS, reason)
+            } else {
+                jsonrpc_error(id, JSONRPC_INVALID_REQUEST, &format!("Batch rejected: element {} is invalid", index))
+            }
         })
+        .collect();
+    JsonRpcRejection(Value::Array(errors))
+}
+
+/// Convert the merchant response, relaying SSE streams as they arrive
+async fn into_axum_response(response: reqwest::Response, mut watch: ConfirmationWatch) -> Result<Response<Body>> {
+    let builder = response_builder(&response);
+    
+    if mcp_stream::is_event_stream(response.headers()) {
+        return Ok(builder.body(mcp_stream::relay_event_stream(response, watch, Vec::new()))?);
+    }
+    
+    let body_bytes = response.bytes().await?;
+    if let Ok(message) = serde_json::from_slice::<Value>(&body_bytes) {
+        watch.inspect(&message);
+    }
+    
+    Ok(builder.body(Body::from(body_bytes))?)
+}
+
+#[async_trait::async_trait]
+impl ProtocolInterceptor for MCPInterceptor {
+    fn protocol_name(&self) -> &str {
+        "MCP"
+    }
+    
+    fn can_handle(&self, request: &Request<Body>) -> bool {
+        // Streamable HTTP: GET opens a server stream and DELETE ends a session,
+        // neither with a JSON body
+        if request.uri().path().starts_with("/mcp") || request.headers().contains_key(SESSION_HEADER) {
+            return true;
+        }
+        
+        // Detect MCP by checking for MCP-specific headers or path
+        if let Some(content_type) = request.headers().get("content-type") {
+            if content_type.to_str().unwrap_or("").contains("application/json") {
+                // Check if path suggests MCP
+                let path = request.uri().path();
+                return path.contains("/mcp") || path.contains("/tools/call");
+            }
+        }
+        
+        // Also check for MCP-specific header
+        request.headers().get("x-mcp-version").is_some()
+    }
+    
+    async fn extract_security_contexts(
+        &self,
+        request: &BufferedRequest,
+    ) -> Result<Vec<SecurityContext>> {
+        info!("=== MCP Interceptor: Extracting Security Context ===");
+        
+        // GET (server stream) and DELETE (end session) carry no JSON-RPC payload
+        if request.body.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let payload: Value = serde_json::from_slice(&request.body).map_err(|e| {
+            JsonRpcRejection(jsonrpc_error(Value::Null, JSONRPC_INVALID_REQUEST, &format!("Parse error: {}", e)))
+        })?;
+        let path_merchant = merchant_from_path(request.path());
+        
+        let Value::Array(messages) = &payload else {
+            let id = payload.get("id").cloned().unwrap_or(Value::Null);
+            let context = self.payment_context(&payload, path_merchant, request)
+                .map_err(|message| JsonRpcRejection(jsonrpc_error(id.clone(), JSONRPC_INVALID_PARAMS, &message)))?;
+            let contexts: Vec<_> = context.into_iter().collect();
+            if let Some((_, reason)) = self.misrouted(request, &contexts) {
+                return Err(JsonRpcRejection(jsonrpc_error(id, JSONRPC_INVALID_PARAMS, &reason)).into());
+            }
+            return Ok(contexts);
+        };
+        
+        if messages.is_empty() {
+            return Err(JsonRpcRejection(jsonrpc_error(Value::Null, JSONRPC_INVALID_REQUEST, "Empty batch")).into());
+        }
+        
+        // Verify each element; one malformed payment call rejects the whole batch,
+        // but every request in it still gets an error carrying its own id
+        let mut contexts = Vec::new();
+        for (index, message) in messages.iter().enumerate() {
+            match self.payment_context(message, path_merchant, request) {
+                Ok(context) => contexts.extend(context),
+                Err(reason) => return Err(batch_rejection(messages, index, &reason).into()),
+            }
+        }
+        
+        // The whole batch goes to one merchant, so every call in it must pay that merchant
+        if let Some((context, reason)) = self.misrouted(request, &contexts) {
+            let index = messages.iter().position(|m| *m == context.raw_request).unwrap_or_default();
+            return Err(batch_rejection(messages, index, &reason).into());
+        }
+        
+        Ok(contexts)
     }
     
     async fn forward_request(
         &self,
-        request: Request<Body>,
-        context: &SecurityContext,
+        request: &BufferedRequest,
+        contexts: &[SecurityContext],
     ) -> Result<Response<Body>> {
         info!("=== MCP Interceptor: Forwarding Request ===");
         
-        // Forward the original MCP request to the merchant's MCP server
-        let merchant_url = format!("https://{}/mcp/tools/call", context.merchant_id);
+        // Forward the original MCP request to the merchant's registered MCP server
+        let merchant_id = self.route_merchant(request, contexts)?;
+        let response = self.send_upstream(&merchant_id, request, request.body.clone()).await?;
+        let watch = ConfirmationWatch::new(contexts, self.confirmations.clone());
+        
+        into_axum_response(response, watch).await
+    }
+    
+    async fn respond_to_declines(
+        &self,
+        request: &BufferedRequest,
+        contexts: &[SecurityContext],
+        declined: &[(SecurityContext, VerificationResult)],
+    ) -> Result<Response<Body>> {
+        let payload: Value = serde_json::from_slice(&request.body)?;
+        let Value::Array(messages) = payload else {
+            let (context, verification) = &declined[0];
+            return Ok(self.decline_response(context, verification));
+        };
+        
+        // Forward the approved remainder of the batch and splice declines into the reply
+        let remaining: Vec<Value> = messages.into_iter()
+            .filter(|m| !declined.iter().any(|(c, _)| &c.raw_request == m))
+            .collect();
         
-        info!("Forwarding to: {}", merchant_url);
+        let declines: Vec<Value> = declined.iter()
+            .filter(|(c, _)| !is_notification(&c.raw_request))
+            .map(|(c, v)| jsonrpc_decline(c, v))
+            .collect();
         
-        let (parts, body) = request.into_parts();
-        let bytes = axum::body::to_bytes(body, usize::MAX).await?;
+        let mut replies: Vec<Value> = Vec::new();
+        if !remaining.is_empty() {
+            let merchant_id = self.route_merchant(request, contexts)?;
+            let body = Bytes::from(serde_json::to_vec(&remaining)?);
+            let response = self.send_upstream(&merchant_id, request, body).await?;
+            let mut watch = ConfirmationWatch::new(contexts, self.confirmations.clone());
+            
+            if !response.status().is_success() {
+                return into_axum_response(response, watch).await;
+            }
+            
+            // A streamed reply gets the declines appended as extra events
+            if mcp_stream::is_event_stream(response.headers()) {
+                let builder = response_builder(&response);
+                return Ok(builder.body(mcp_stream::relay_event_stream(response, watch, declines))?);
+            }
+            
+            let bytes = response.bytes().await?;
+            if !bytes.is_empty() {
+                let reply = serde_json::from_slice::<Value>(&bytes)?;
+                watch.inspect(&reply);
+                match reply {
+                    Value::Array(upstream_replies) => replies.extend(upstream_replies),
+                    reply => replies.push(reply),
+                }
+            }
+        }
         
-        let response = self.client
-            .post(&merchant_url)
-            .headers(parts.headers)
-            .body(bytes.to_vec())
-            .send()
-            .await?;
+        replies.extend(declines);
+        
+        if replies.is_empty() {
+            // Only notifications were sent, which never get a response body
+            return Ok(StatusCode::ACCEPTED.into_response());
+        }
         
-        // Convert reqwest response to axum response
-        let status = response.status();
-        let headers = response.headers().clone();
-        let body_bytes = response.bytes().await?;
+        Ok((StatusCode::OK, Json(Value::Array(replies))).into_response())
+    }
+    
+    fn decline_response(
+        &self,
+        context: &SecurityContext,
+        verification: &VerificationResult,
+    ) -> Response<Body> {
+        warn!("MCP tool call declined: {:?}", verification.reason);
         
-        let mut builder = Response::builder().status(status);
-        for (key, value) in headers.iter() {
-            builder = builder.header(key, value);
+        if is_notification(&context.raw_request) {
+            return StatusCode::ACCEPTED.into_response();
         }
         
-        Ok(builder.body(Body::from(body_bytes))?)
+        // JSON-RPC errors travel inside a 200 response so MCP clients can correlate them by id
+        (StatusCode::OK, Json(jsonrpc_decline(context, verification))).into_response()
+    }
+    
+    fn error_response(&self, status: StatusCode, message: &str) -> Response<Body> {
+        (status, Json(jsonrpc_error(Value::Null, JSONRPC_INVALID_REQUEST, message))).into_response()
+    }
+    
+    fn extraction_error_response(&self, error: &anyhow::Error) -> Response<Body> {
+        match error.downcast_ref::<JsonRpcRejection>() {
+            Some(JsonRpcRejection(body)) => (StatusCode::OK, Json(body.clone())).into_response(),
+            None => self.error_response(StatusCode::BAD_REQUEST, &error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERCHANT: &str = "6f1c2b9e-0d4a-4c1e-9a53-2f7e1d0b8c11";
+
+    fn interceptor() -> MCPInterceptor {
+        // Extraction never reaches the merchant or the gateway
+        let pool = sqlx::PgPool::connect_lazy("postgresql://localhost/unused").unwrap();
+        MCPInterceptor {
+            upstreams: Arc::new(UpstreamRegistry::new(pool)),
+            tools: PaymentToolConfig::default(),
+            sessions: RwLock::new(HashMap::new()),
+            confirmations: mpsc::unbounded_channel().0,
+        }
+    }
+
+    fn request(body: &Value) -> BufferedRequest {
+        let (parts, _) = Request::post(format!("/mcp/{}", MERCHANT))
+            .header("content-type", "application/json")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+        BufferedRequest { parts, body: Bytes::from(serde_json::to_vec(body).unwrap()) }
+    }
+
+    fn purchase(id: Option<Value>, amount: Value) -> Value {
+        let mut message = json!({
+            "jsonrpc": "2.0",
+            "method": "tools/call",
+            "params": {
+                "name": "complete_purchase",
+                "arguments": { "agent_id": "agent_test", "amount": amount, "currency": "USD" },
+            },
+        });
+        if let Some(id) = id {
+            message["id"] = id;
+        }
+        message
+    }
+
+    async fn json_body(response: Response<Body>) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn declined() -> VerificationResult {
+        VerificationResult::declined(security_gateway::DeclineCode::DailyLimit, "Would exceed daily limit".to_string())
+    }
+
+    /// The JSON-RPC body of an extraction failure
+    async fn rejection(body: Value) -> Value {
+        let interceptor = interceptor();
+        let error = interceptor.extract_security_contexts(&request(&body)).await.unwrap_err();
+        let response = interceptor.extraction_error_response(&error);
+        assert_eq!(response.status(), StatusCode::OK);
+        json_body(response).await
+    }
+
+    #[tokio::test]
+    async fn zero_and_negative_amounts_are_invalid_params() {
+        for amount in [json!("-25.00"), json!(0), json!(-0.01)] {
+            let reply = rejection(purchase(Some(json!(3)), amount.clone())).await;
+            assert_eq!(reply["id"], 3, "amount {}", amount);
+            assert_eq!(reply["error"]["code"], JSONRPC_INVALID_PARAMS, "amount {}", amount);
+        }
+
+        let contexts = interceptor().extract_security_contexts(&request(&purchase(Some(json!(3)), json!("0.01")))).await.unwrap();
+        assert_eq!(contexts[0].amount, Some(Money::new(1, "USD").unwrap()));
+    }
+    
+    #[tokio::test]
+    async fn only_payment_tool_calls_are_verified() {
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} },
+            { "jsonrpc": "2.0", "method": "notifications/initialized" },
+            { "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "search_products", "arguments": {} } },
+            purchase(Some(json!("buy-1")), json!("12.50")),
+            purchase(None, json!(3)),
+        ]);
+
+        let contexts = interceptor().extract_security_contexts(&request(&batch)).await.unwrap();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].raw_request, batch[3]);
+        assert_eq!(contexts[0].transaction_id, "buy-1");
+        assert_eq!(contexts[0].merchant_id, MERCHANT);
+        assert_eq!(contexts[1].raw_request, batch[4]);
+        assert!(is_notification(&contexts[1].raw_request));
+    }
+    
+    #[tokio::test]
+    async fn invalid_batch_element_rejects_each_request_under_its_own_id() {
+        let reply = rejection(json!([
+            purchase(Some(json!(1)), json!("5.00")),
+            purchase(None, json!("5.00")),
+            purchase(Some(json!("bad")), json!("abc")),
+        ])).await;
+
+        // The notification gets no reply; the rest keep their ids
+        let replies = reply.as_array().unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[0]["error"]["code"], JSONRPC_INVALID_REQUEST);
+        assert_eq!(replies[1]["id"], "bad");
+        assert_eq!(replies[1]["error"]["code"], JSONRPC_INVALID_PARAMS);
+
+        assert_eq!(rejection(json!([])).await["error"]["code"], JSONRPC_INVALID_REQUEST);
+        assert_eq!(rejection(json!({ "jsonrpc": "2.0", "id": 9, "method": "tools/call", "params": {
+            "name": "complete_purchase", "arguments": { "agent_id": "agent_test", "currency": "USD" },
+        } })).await["id"], 9);
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn declines_answer_each_call_by_id_and_skip_notifications() {
+        let interceptor = interceptor();
+        let batch = json!([
+            purchase(Some(json!(1)), json!("5.00")),
+            purchase(Some(json!("two")), json!("6.00")),
+            purchase(None, json!("7.00")),
+        ]);
+        let calls = request(&batch);
+        let contexts = interceptor.extract_security_contexts(&calls).await.unwrap();
+        let review = VerificationResult { decision: Decision::Review, ..declined() };
+        let declines = vec![
+            (contexts[0].clone(), declined()),
+            (contexts[1].clone(), review),
+            (contexts[2].clone(), declined()),
+        ];
+
+        // Nothing approved is left to forward, so the merchant is never called
+        let response = interceptor.respond_to_declines(&calls, &contexts, &declines).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let replies = json_body(response).await;
+        assert_eq!(replies.as_array().unwrap().len(), 2);
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[0]["error"]["code"], JSONRPC_PAYMENT_DECLINED);
+        assert_eq!(replies[0]["error"]["data"]["decline_code"], "DAILY_LIMIT");
+        assert_eq!(replies[1]["id"], "two");
+        assert_eq!(replies[1]["error"]["code"], JSONRPC_PAYMENT_REVIEW);
+
+        assert_eq!(interceptor.decline_response(&contexts[2], &declined()).status(), StatusCode::ACCEPTED);
+
+        // A batch of declined notifications gets no body at all
+        let notifications = request(&json!([purchase(None, json!("7.00"))]));
+        let contexts = interceptor.extract_security_contexts(&notifications).await.unwrap();
+        let declines = vec![(contexts[0].clone(), declined())];
+        let response = interceptor.respond_to_declines(&notifications, &contexts, &declines).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+}
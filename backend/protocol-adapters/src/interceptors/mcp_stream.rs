@@ -0,0 +1,286 @@
+use axum::body::{Body, Bytes};
+use axum::http::HeaderMap;
+use futures_util::{stream, StreamExt};
+use security_gateway::SecurityGateway;
+use security_gateway::SecurityContext;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// A merchant tool result answering one of the payment calls we verified
+#[derive(Debug)]
+pub struct PaymentConfirmation {
+    pub context: SecurityContext,
+    pub result: Value,
+}
+
+/// Spawn the task that writes observed confirmations back to the transaction log.
+///
+/// Recording happens off the response path so a slow database never stalls a stream.
+pub fn spawn_confirmation_recorder(gateway: Arc<SecurityGateway>) -> mpsc::UnboundedSender<PaymentConfirmation> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PaymentConfirmation>();
+
+    tokio::spawn(async move {
+        while let Some(confirmation) = receiver.recv().await {
+            if let Err(e) = gateway.record_payment_confirmation(&confirmation.context, &confirmation.result).await {
+                error!("Failed to record payment confirmation: {}", e);
+            }
+        }
+    });
+
+    sender
+}
+
+/// Watches upstream JSON-RPC messages for results to verified payment calls
+pub struct ConfirmationWatch {
+    pending: Vec<SecurityContext>,
+    sink: mpsc::UnboundedSender<PaymentConfirmation>,
+}
+
+impl ConfirmationWatch {
+    pub fn new(contexts: &[SecurityContext], sink: mpsc::UnboundedSender<PaymentConfirmation>) -> Self {
+        // Notifications never receive a result, so only calls with an id can be confirmed
+        let pending = contexts.iter()
+            .filter(|c| c.raw_request.get("id").is_some())
+            .cloned()
+            .collect();
+
+        Self { pending, sink }
+    }
+
+    /// Inspect one JSON-RPC message (or batch) sent back by the merchant
+    pub fn inspect(&mut self, message: &Value) {
+        if let Value::Array(batch) = message {
+            batch.iter().for_each(|m| self.inspect(m));
+            return;
+        }
+
+        let (Some(id), Some(result)) = (message.get("id"), message.get("result")) else {
+            return;
+        };
+
+        let Some(index) = self.pending.iter().position(|c| c.raw_request.get("id") == Some(id)) else {
+            return;
+        };
+        let context = self.pending.swap_remove(index);
+
+        // MCP reports tool failures as results flagged with isError
+        if result.get("isError").and_then(|v| v.as_bool()).unwrap_or(false) {
+            warn!("Payment tool call {} failed at merchant", id);
+            return;
+        }
+
+        info!("💳 Payment confirmation observed for call {}", id);
+        let result = result.get("structuredContent").cloned().unwrap_or_else(|| result.clone());
+        if self.sink.send(PaymentConfirmation { context, result }).is_err() {
+            error!("Payment confirmation recorder is not running");
+        }
+    }
+}
+
+/// Incremental parser for `text/event-stream` bodies; yields each event's `data`
+#[derive(Default)]
+struct EventScanner {
+    line: Vec<u8>,
+    data: String,
+}
+
+impl EventScanner {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+
+            let line = String::from_utf8_lossy(&self.line).trim_end_matches('\r').to_string();
+            self.line.clear();
+
+            if line.is_empty() {
+                // A blank line dispatches the event
+                if !self.data.is_empty() {
+                    events.push(std::mem::take(&mut self.data));
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+
+        events
+    }
+}
+
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers.get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// Frame a JSON-RPC message as one SSE event
+pub fn sse_event(message: &Value) -> Bytes {
+    Bytes::from(format!("event: message\ndata: {}\n\n", message))
+}
+
+/// Relay an upstream SSE stream chunk by chunk, inspecting events as they pass.
+///
+/// `trailer` messages (declined batch elements) are sent as extra events once the
+/// merchant's stream ends.
+pub fn relay_event_stream(response: reqwest::Response, mut watch: ConfirmationWatch, trailer: Vec<Value>) -> Body {
+    let mut scanner = EventScanner::default();
+
+    let upstream = response.bytes_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            for data in scanner.feed(bytes) {
+                if let Ok(message) = serde_json::from_str::<Value>(&data) {
+                    watch.inspect(&message);
+                }
+            }
+        }
+        chunk
+    });
+
+    let trailer = stream::iter(trailer.into_iter().map(|m| Ok::<_, reqwest::Error>(sse_event(&m))));
+
+    Body::from_stream(upstream.chain(trailer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header;
+    use axum::response::IntoResponse;
+    use axum::routing::post;
+    use axum::Router;
+    use http_body_util::BodyExt;
+    use security_gateway::Protocol;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio::sync::{oneshot, Mutex};
+
+    fn payment_context(id: Value) -> SecurityContext {
+        SecurityContext {
+            agent_id: "agent_test".to_string(),
+            agent_owner: None,
+            foundational_model: None,
+            protocol: Protocol::MCP,
+            transaction_id: "tx_test".to_string(),
+            amount: Some(security_gateway::Money::new(4200, "USD").unwrap()),
+            currency: "USD".to_string(),
+            merchant_id: uuid::Uuid::new_v4().to_string(),
+            merchant_name: None,
+            timestamp: chrono::Utc::now(),
+            user_id: None,
+            session_id: Some("session-1".to_string()),
+            ip_address: None,
+            user_agent: None,
+            payment_method_type: None,
+            payment_token: None,
+            signature: None,
+            nonce: "nonce_test".to_string(),
+            risk_score: None,
+            metadata: Default::default(),
+            raw_request: json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": "complete_purchase" },
+            }),
+        }
+    }
+
+    /// Stub MCP server: sends a progress notification, then waits for `release`
+    /// before sending the tool result, so the test can observe incremental relay.
+    async fn stub_mcp_server(release: oneshot::Receiver<()>) -> String {
+        let release = Arc::new(Mutex::new(Some(release)));
+
+        let app = Router::new().route("/mcp", post(move || {
+            let release = release.clone();
+            async move {
+                let release = release.lock().await.take().expect("single call");
+                let events = stream::once(async {
+                    Ok::<_, std::io::Error>(sse_event(&json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/progress",
+                        "params": { "progress": 1, "total": 2 },
+                    })))
+                })
+                .chain(stream::once(async move {
+                    release.await.ok();
+                    // Split the result event across chunks like a real network would
+                    Ok(Bytes::from(
+                        "event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"isError\":false,\r\n",
+                    ))
+                }))
+                .chain(stream::once(async {
+                    Ok(Bytes::from(
+                        "data: \"structuredContent\":{\"order_id\":\"ord_123\",\"status\":\"paid\"}}}\r\n\r\n",
+                    ))
+                }));
+
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream"), (header::HeaderName::from_static("mcp-session-id"), "session-1")],
+                    Body::from_stream(events),
+                )
+                    .into_response()
+            }
+        }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}/mcp", addr)
+    }
+
+    #[tokio::test]
+    async fn relays_sse_incrementally_and_records_confirmation() {
+        let (release_tx, release_rx) = oneshot::channel();
+        let url = stub_mcp_server(release_rx).await;
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("accept", "application/json, text/event-stream")
+            .body(r#"{"jsonrpc":"2.0","id":7,"method":"tools/call"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert!(is_event_stream(response.headers()));
+        assert_eq!(response.headers()["mcp-session-id"], "session-1");
+
+        let (sink, mut confirmations) = mpsc::unbounded_channel();
+        let watch = ConfirmationWatch::new(&[payment_context(json!(7))], sink);
+        let declined = json!({ "jsonrpc": "2.0", "id": 8, "error": { "code": -32001, "message": "declined" } });
+        let mut body = relay_event_stream(response, watch, vec![declined]);
+
+        // The progress event reaches the agent while the merchant is still working
+        let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert!(String::from_utf8_lossy(&first).contains("notifications/progress"));
+        assert!(confirmations.try_recv().is_err());
+
+        release_tx.send(()).unwrap();
+        let rest = body.collect().await.unwrap().to_bytes();
+        let rest = String::from_utf8_lossy(&rest);
+        assert!(rest.contains("ord_123"));
+        assert!(rest.ends_with("data: {\"error\":{\"code\":-32001,\"message\":\"declined\"},\"id\":8,\"jsonrpc\":\"2.0\"}\n\n"));
+
+        let confirmation = confirmations.try_recv().expect("confirmation recorded");
+        assert_eq!(confirmation.context.nonce, "nonce_test");
+        assert_eq!(confirmation.result, json!({ "order_id": "ord_123", "status": "paid" }));
+    }
+
+    #[test]
+    fn ignores_failed_and_unrelated_results() {
+        let (sink, mut confirmations) = mpsc::unbounded_channel();
+        let mut watch = ConfirmationWatch::new(&[payment_context(json!("a"))], sink);
+
+        watch.inspect(&json!({ "jsonrpc": "2.0", "id": "other", "result": {} }));
+        watch.inspect(&json!([{ "jsonrpc": "2.0", "id": "a", "result": { "isError": true } }]));
+        assert!(confirmations.try_recv().is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::info;
+
+/// Tool names treated as payment-relevant when no config file is provided
+const DEFAULT_PAYMENT_TOOLS: &[&str] = &[
+    "complete_purchase",
+    "create_payment",
+    "make_payment",
+    "purchase",
+    "checkout",
+];
+
+/// Which MCP tools move money, and which of their arguments carry the security context.
+///
+/// Loaded from the JSON file named by `MCP_PAYMENT_TOOLS_FILE`, e.g.
+/// `{"tools": [{"name": "buy_*", "arguments": {"amount": ["total", "amount"]}}]}`.
+/// A trailing `*` in a tool name matches by prefix.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentToolConfig {
+    pub tools: Vec<PaymentTool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentTool {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: ArgumentMapping,
+    /// Currency for calls whose arguments carry an amount but no currency
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+/// Candidate argument names for each field; the first one present wins
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ArgumentMapping {
+    pub agent_id: Vec<String>,
+    pub merchant_id: Vec<String>,
+    pub amount: Vec<String>,
+    pub currency: Vec<String>,
+    pub nonce: Vec<String>,
+    pub payment_method: Vec<String>,
+}
+
+impl Default for ArgumentMapping {
+    fn default() -> Self {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect();
+        Self {
+            agent_id: names(&["agent_id"]),
+            merchant_id: names(&["merchant_id", "merchant"]),
+            amount: names(&["amount"]),
+            currency: names(&["currency"]),
+            nonce: names(&["nonce"]),
+            payment_method: names(&["payment_method"]),
+        }
+    }
+}
+
+impl ArgumentMapping {
+    /// Look up the first candidate argument that is present
+    pub fn lookup<'a>(names: &[String], arguments: &'a Map<String, Value>) -> Option<&'a Value> {
+        names.iter().find_map(|name| arguments.get(name))
+    }
+}
+
+impl Default for PaymentToolConfig {
+    fn default() -> Self {
+        Self {
+            tools: DEFAULT_PAYMENT_TOOLS
+                .iter()
+                .map(|name| PaymentTool {
+                    name: name.to_string(),
+                    arguments: ArgumentMapping::default(),
+                    currency: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PaymentToolConfig {
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var("MCP_PAYMENT_TOOLS_FILE") else {
+            return Ok(Self::default());
+        };
+
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read MCP payment tool config {}", path))?;
+        let config: Self = serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid MCP payment tool config {}", path))?;
+
+        info!("Loaded {} MCP payment tool mappings from {}", config.tools.len(), path);
+        Ok(config)
+    }
+
+    /// Find the mapping for a tool, or `None` if the tool is not payment-relevant
+    pub fn find(&self, tool_name: &str) -> Option<&PaymentTool> {
+        self.tools.iter().find(|tool| match tool.name.strip_suffix('*') {
+            Some(prefix) => tool_name.starts_with(prefix),
+            None => tool.name == tool_name,
+        })
+    }
+}
@@ -1,7 +1,16 @@
+pub mod buffered;
 pub mod trait_;
 pub mod mcp;
+pub mod mcp_stream;
+pub mod mcp_tools;
 pub mod acp;
+pub mod checkout_sessions;
+pub mod upstream;
 
+pub use buffered::BufferedRequest;
 pub use trait_::ProtocolInterceptor;
 pub use mcp::MCPInterceptor;
-pub use acp::ACPInterceptor;
\ No newline at end of file
+pub use mcp_tools::PaymentToolConfig;
+pub use acp::ACPInterceptor;
+pub use checkout_sessions::CheckoutSessionStore;
+pub use upstream::UpstreamRegistry;
\ No newline at end of file
@@ -1,27 +1,60 @@
 use anyhow::Result;
-use axum::http::{Request, Response};
+use axum::http::{Request, Response, StatusCode};
 use axum::body::Body;
-use security_gateway::SecurityContext;
+use security_gateway::{SecurityContext, VerificationResult};
+
+use super::buffered::BufferedRequest;
 
 /// Trait that all protocol interceptors must implement
 #[async_trait::async_trait]  // This is correct
 pub trait ProtocolInterceptor: Send + Sync {
     /// Name of the protocol
     fn protocol_name(&self) -> &str;
-    
+
     /// Check if this interceptor can handle the request
     fn can_handle(&self, request: &Request<Body>) -> bool;
-    
-    /// Extract security context from the request
-    async fn extract_security_context(
+
+    /// Extract one security context per payment-relevant call in the buffered request.
+    ///
+    /// An empty list means the request carries nothing to verify and is passed through.
+    async fn extract_security_contexts(
         &self,
-        request: Request<Body>,
-    ) -> Result<SecurityContext>;
-    
-    /// Forward the request to the merchant/service
+        request: &BufferedRequest,
+    ) -> Result<Vec<SecurityContext>>;
+
+    /// Forward the same buffered request to the merchant/service
     async fn forward_request(
         &self,
-        request: Request<Body>,
-        context: &SecurityContext,
+        request: &BufferedRequest,
+        contexts: &[SecurityContext],
     ) -> Result<Response<Body>>;
-}
\ No newline at end of file
+
+    /// Respond when at least one verified call was declined.
+    ///
+    /// By default the whole request is declined. Interceptors that can split a
+    /// request (MCP batches) override this to forward the approved remainder.
+    async fn respond_to_declines(
+        &self,
+        _request: &BufferedRequest,
+        _contexts: &[SecurityContext],
+        declined: &[(SecurityContext, VerificationResult)],
+    ) -> Result<Response<Body>> {
+        let (context, verification) = &declined[0];
+        Ok(self.decline_response(context, verification))
+    }
+
+    /// Build the protocol-native response returned when the gateway declines a call
+    fn decline_response(
+        &self,
+        context: &SecurityContext,
+        verification: &VerificationResult,
+    ) -> Response<Body>;
+
+    /// Build a protocol-native error for requests that never reached verification
+    fn error_response(&self, status: StatusCode, message: &str) -> Response<Body>;
+
+    /// Build the error returned when `extract_security_contexts` fails
+    fn extraction_error_response(&self, error: &anyhow::Error) -> Response<Body> {
+        self.error_response(StatusCode::BAD_REQUEST, &error.to_string())
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::{anyhow, Context, Result};
+use axum::http::{HeaderMap, HeaderName};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+const DEFAULT_TIMEOUT_MS: i32 = 10_000;
+const DEFAULT_MCP_ENDPOINT: &str = "/mcp";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Headers forwarded when a merchant has not configured its own allow-list
+const DEFAULT_ALLOWED_HEADERS: &[&str] = &[
+    "accept",
+    "accept-language",
+    "content-type",
+    "user-agent",
+    "idempotency-key",
+    "mcp-session-id",
+    "mcp-protocol-version",
+    "last-event-id",
+];
+
+/// Headers that never leave the gateway, even if a merchant allow-list names them
+const BLOCKED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "host",
+    "content-length",
+    "x-api-key",
+    "x-forwarded-for",
+    "x-real-ip",
+];
+
+/// Hop-by-hop headers describing how a merchant framed its response
+const FRAMING_HEADERS: &[&str] = &["content-length", "transfer-encoding", "connection"];
+
+/// Prefix reserved for headers added by our own infrastructure
+const INTERNAL_HEADER_PREFIX: &str = "x-internal-";
+
+/// Where and how to reach one merchant's agent-facing endpoints
+pub struct MerchantUpstream {
+    pub base_url: String,
+    pub mcp_endpoint: String,
+    pub acp_base_url: String,
+    pub timeout: Duration,
+    pub allowed_headers: Vec<HeaderName>,
+    pub client: reqwest::Client,
+}
+
+impl MerchantUpstream {
+    pub fn mcp_url(&self) -> String {
+        format!("{}{}", self.base_url, self.mcp_endpoint)
+    }
+
+    /// ACP paths arrive as `/acp/{merchant_id}/...`; everything after the merchant id
+    /// is appended to the merchant's checkout base URL.
+    pub fn acp_url(&self, path: &str, query: Option<&str>) -> String {
+        match query {
+            Some(query) => format!("{}{}?{}", self.acp_base_url, path, query),
+            None => format!("{}{}", self.acp_base_url, path),
+        }
+    }
+
+    /// Copy only allow-listed headers; our credentials and internal headers are always dropped
+    pub fn outbound_headers(&self, incoming: &HeaderMap) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for name in &self.allowed_headers {
+            if is_blocked(name.as_str()) {
+                continue;
+            }
+            for value in incoming.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        headers
+    }
+}
+
+/// Headers fit to keep with a parked request: credentials and internal headers are dropped
+pub fn storable_headers(incoming: &HeaderMap) -> HeaderMap {
+    incoming.iter()
+        .filter(|(name, _)| !is_blocked(name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Status and headers of a merchant response; framing headers are left to our server,
+/// since the body is re-framed on the way back to the agent
+pub fn response_builder(response: &reqwest::Response) -> axum::http::response::Builder {
+    let mut builder = axum::http::Response::builder().status(response.status());
+    for (key, value) in response.headers().iter() {
+        if !FRAMING_HEADERS.contains(&key.as_str()) {
+            builder = builder.header(key, value);
+        }
+    }
+    builder
+}
+
+fn is_blocked(name: &str) -> bool {
+    BLOCKED_HEADERS.contains(&name) || name.starts_with(INTERNAL_HEADER_PREFIX)
+}
+
+/// The merchant's timeout, or the default; never zero
+fn upstream_timeout(configured_ms: Option<i32>) -> Duration {
+    let timeout_ms = configured_ms.unwrap_or(DEFAULT_TIMEOUT_MS).max(1);
+    Duration::from_millis(timeout_ms as u64)
+}
+
+/// The merchant's header allow-list, or the default one; names that are not valid headers are dropped
+fn allowed_headers(configured: Option<Vec<String>>) -> Vec<HeaderName> {
+    configured
+        .unwrap_or_else(|| DEFAULT_ALLOWED_HEADERS.iter().map(|h| h.to_string()).collect())
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.trim().to_lowercase().as_bytes()).ok())
+        .collect()
+}
+
+/// Normalise a merchant `domain` (with or without scheme) into a base URL
+fn base_url_from_domain(domain: &str) -> String {
+    let domain = domain.trim().trim_end_matches('/');
+    if domain.starts_with("http://") || domain.starts_with("https://") {
+        domain.to_string()
+    } else {
+        format!("https://{}", domain)
+    }
+}
+
+/// Resolves merchant ids to upstream endpoints using the `merchants` table
+pub struct UpstreamRegistry {
+    pool: PgPool,
+    cache: RwLock<HashMap<String, (Instant, Arc<MerchantUpstream>)>>,
+}
+
+impl UpstreamRegistry {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn resolve(&self, merchant_id: &str) -> Result<Arc<MerchantUpstream>> {
+        if let Some((loaded_at, upstream)) = self.cache.read().unwrap().get(merchant_id) {
+            if loaded_at.elapsed() < CACHE_TTL {
+                return Ok(upstream.clone());
+            }
+        }
+
+        let upstream = Arc::new(self.load(merchant_id).await?);
+        self.cache
+            .write()
+            .unwrap()
+            .insert(merchant_id.to_string(), (Instant::now(), upstream.clone()));
+
+        Ok(upstream)
+    }
+
+    async fn load(&self, merchant_id: &str) -> Result<MerchantUpstream> {
+        let merchant_uuid = Uuid::parse_str(merchant_id)
+            .map_err(|_| anyhow!("Invalid merchant id: {}", merchant_id))?;
+
+        let row = sqlx::query(
+            "SELECT domain, checkout_url_pattern, upstream_base_url, mcp_endpoint,
+                    upstream_timeout_ms, upstream_allowed_headers, upstream_tls_verify,
+                    upstream_ca_cert
+             FROM merchants WHERE id = $1"
+        )
+        .bind(merchant_uuid)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Unknown merchant: {}", merchant_id))?;
+
+        let base_url = row.get::<Option<String>, _>("upstream_base_url")
+            .unwrap_or_else(|| row.get("domain"));
+        let base_url = base_url_from_domain(&base_url);
+
+        // checkout_url_pattern may be absolute or a path on the merchant's domain
+        let acp_base_url = match row.get::<Option<String>, _>("checkout_url_pattern") {
+            Some(pattern) if pattern.starts_with("http://") || pattern.starts_with("https://") => {
+                pattern.trim_end_matches('/').to_string()
+            }
+            Some(pattern) if pattern.starts_with('/') => {
+                format!("{}{}", base_url, pattern.trim_end_matches('/'))
+            }
+            _ => base_url.clone(),
+        };
+
+        let mcp_endpoint = row.get::<Option<String>, _>("mcp_endpoint")
+            .unwrap_or_else(|| DEFAULT_MCP_ENDPOINT.to_string());
+
+        let timeout = upstream_timeout(row.get("upstream_timeout_ms"));
+        let allowed_headers = allowed_headers(row.get("upstream_allowed_headers"));
+
+        let tls_verify = row.get::<Option<bool>, _>("upstream_tls_verify").unwrap_or(true);
+        // The timeout bounds each read rather than the whole exchange, so long-lived
+        // MCP event streams survive while a stalled merchant still fails fast
+        let mut builder = reqwest::Client::builder()
+            .read_timeout(timeout)
+            .connect_timeout(CONNECT_TIMEOUT.min(timeout))
+            .danger_accept_invalid_certs(!tls_verify);
+
+        if let Some(pem) = row.get::<Option<String>, _>("upstream_ca_cert") {
+            let cert = reqwest::Certificate::from_pem(pem.as_bytes())
+                .context("Invalid upstream_ca_cert")?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        info!("🔗 Resolved upstream for merchant {}: {}", merchant_id, base_url);
+
+        Ok(MerchantUpstream {
+            base_url,
+            mcp_endpoint,
+            acp_base_url,
+            timeout,
+            allowed_headers,
+            client: builder.build()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn upstream(allowed: Option<Vec<String>>) -> MerchantUpstream {
+        MerchantUpstream {
+            base_url: "https://merchant.example.com".to_string(),
+            mcp_endpoint: DEFAULT_MCP_ENDPOINT.to_string(),
+            acp_base_url: "https://merchant.example.com".to_string(),
+            timeout: upstream_timeout(None),
+            allowed_headers: allowed_headers(allowed),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn incoming(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers.iter()
+            .map(|(name, value)| (HeaderName::from_static(name), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn default_allow_list_forwards_protocol_headers_only() {
+        let headers = incoming(&[
+            ("content-type", "application/json"),
+            ("mcp-session-id", "session-1"),
+            ("authorization", "Bearer agent-secret"),
+            ("x-custom", "1"),
+        ]);
+
+        let outbound = upstream(None).outbound_headers(&headers);
+        assert_eq!(outbound.len(), 2);
+        assert_eq!(outbound["content-type"], "application/json");
+        assert_eq!(outbound["mcp-session-id"], "session-1");
+    }
+
+    #[test]
+    fn merchant_allow_list_replaces_the_default_but_never_passes_blocked_headers() {
+        let allowed = [" X-Merchant-Tenant ", "Authorization", "cookie", "x-internal-trace", "bad header"]
+            .map(String::from)
+            .to_vec();
+        let headers = incoming(&[
+            ("x-merchant-tenant", "acme"),
+            ("authorization", "Bearer agent-secret"),
+            ("cookie", "session=1"),
+            ("x-internal-trace", "abc"),
+            ("content-type", "application/json"),
+        ]);
+
+        let outbound = upstream(Some(allowed)).outbound_headers(&headers);
+        assert_eq!(outbound.len(), 1);
+        assert_eq!(outbound["x-merchant-tenant"], "acme");
+    }
+
+    #[test]
+    fn repeated_headers_are_all_forwarded() {
+        let mut headers = incoming(&[("accept", "application/json")]);
+        headers.append("accept", HeaderValue::from_static("text/event-stream"));
+
+        let outbound = upstream(None).outbound_headers(&headers);
+        assert_eq!(outbound.get_all("accept").iter().count(), 2);
+    }
+
+    #[test]
+    fn timeout_falls_back_to_the_default_and_is_never_zero() {
+        assert_eq!(upstream_timeout(None), Duration::from_millis(DEFAULT_TIMEOUT_MS as u64));
+        assert_eq!(upstream_timeout(Some(2_500)), Duration::from_millis(2_500));
+        assert_eq!(upstream_timeout(Some(0)), Duration::from_millis(1));
+        assert_eq!(upstream_timeout(Some(-5)), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn merchant_framing_headers_are_not_relayed() {
+        let merchant = axum::http::Response::builder()
+            .status(201)
+            .header("content-type", "application/json")
+            .header("content-length", "999")
+            .header("transfer-encoding", "chunked")
+            .header("connection", "keep-alive")
+            .header("idempotency-key", "abc")
+            .body("{}")
+            .unwrap();
+
+        let relayed = response_builder(&reqwest::Response::from(merchant)).body(()).unwrap();
+        assert_eq!(relayed.status(), 201);
+        assert_eq!(relayed.headers().len(), 2);
+        assert_eq!(relayed.headers()["content-type"], "application/json");
+        assert_eq!(relayed.headers()["idempotency-key"], "abc");
+    }
+}
@@ -7,18 +7,31 @@ use axum::{
 };
 use axum::body::Body;
 use axum::http::Request;
-use security_gateway::{Database, SecurityGateway};
+use security_gateway::fx::{RateProvider, StaticRateProvider};
+use security_gateway::ledger;
+use security_gateway::reservations::ReservationStore;
+use security_gateway::{Database, GatewayConfig, SecurityGateway};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Duration;
+use tracing::{error, info};
 use tower_http::cors::CorsLayer;
 
 mod api;
 mod auth;
+mod interceptors;
+mod parked;
+mod proxy;
+
+use interceptors::{ACPInterceptor, CheckoutSessionStore, MCPInterceptor, PaymentToolConfig, ProtocolInterceptor, UpstreamRegistry};
 
 pub struct AppState {
     pub gateway: Arc<SecurityGateway>,
+    pub interceptors: Vec<Arc<dyn ProtocolInterceptor>>,
+    pub proxy_config: proxy::ProxyConfig,
     pub db: Arc<Database>,
+    pub reservations: ReservationStore,
+    pub rates: Arc<dyn RateProvider>,
 }
 
 #[tokio::main]
@@ -27,12 +40,57 @@ async fn main() -> anyhow::Result<()> {
     
     info!("🚀 Starting Protocol Adapters Service with API...");
     
-    let gateway = Arc::new(SecurityGateway::new().await?);
+    let config = GatewayConfig::from_env();
+    info!("🛡️ Gateway config: {:?}", config);
+    let rates: Arc<dyn RateProvider> = Arc::new(StaticRateProvider::from_env()?);
+    let gateway = Arc::new(SecurityGateway::with_config(config).await?.with_rate_provider(rates.clone()));
     let db = Arc::new(Database::connect().await?);
     
+    // Merchant upstream endpoints come from the merchants table
+    let upstreams = Arc::new(UpstreamRegistry::new(db.pool.clone()));
+    
+    // Order matters: the first interceptor whose can_handle matches owns the request
+    let interceptors: Vec<Arc<dyn ProtocolInterceptor>> = vec![
+        Arc::new(MCPInterceptor::new(upstreams.clone(), PaymentToolConfig::from_env()?, gateway.clone())),
+        Arc::new(ACPInterceptor::new(upstreams, CheckoutSessionStore::new(db.pool.clone()))),
+    ];
+    
+    // Lapsed spend holds stop counting on their own; the sweep marks them released
+    let reservations = ReservationStore::new(db.pool.clone());
+    let sweeper = reservations.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweeper.release_expired().await {
+                error!("Failed to release expired spend holds: {}", e);
+            }
+        }
+    });
+    
+    // Derived balances are checked against the ledger on a schedule
+    let reconcile_interval = std::env::var("LEDGER_RECONCILE_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(3600);
+    let reconcile_pool = db.pool.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(reconcile_interval));
+        loop {
+            interval.tick().await;
+            if let Err(e) = ledger::reconcile(&reconcile_pool).await {
+                error!("Ledger reconciliation failed: {}", e);
+            }
+        }
+    });
+    
     let state = Arc::new(AppState {
         gateway,
+        interceptors,
+        proxy_config: proxy::ProxyConfig::from_env(),
         db,
+        reservations,
+        rates,
     });
     
     let app = Router::new()
@@ -47,11 +105,17 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/v1/agents/register", post(api::create_agent))
         .route("/api/v1/agents/:id/transactions", get(api::get_agent_transactions))
         .route("/api/v1/agents/:id", get(api::get_agent))
+        .route("/api/v1/agents/:id/wallet/topup", post(api::topup_wallet))
+        .route("/api/v1/agents/:id/wallet/withdraw", post(api::withdraw_wallet))
+        .route("/api/v1/agents/:id/wallet/history", get(api::get_wallet_history))
+        .route("/api/v1/agents/:id/category-rules", get(api::get_category_rules).put(api::update_category_rules))
+        .route("/api/v1/agents/:id/payment-tokens", post(api::register_payment_token))
         .route("/api/v1/agents/:id", delete(api::delete_agent))
         .route("/api/v1/agents", get(api::list_agents))
         
         .route("/api/v1/merchants", get(api::list_merchants))
         .route("/api/v1/merchants/:id/approve", post(api::approve_merchant))
+        .route("/api/v1/merchants/:id/controls", get(api::get_merchant_controls).put(api::update_merchant_controls))
         .route("/api/v1/merchants/:merchant_id/transactions", get(api::get_merchant_transactions))
         
         .route("/api/v1/merchants/:merchant_id/agents/:agent_id/block", post(api::block_agent_simple))
@@ -61,12 +125,31 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/v1/transactions", post(api::create_transaction))
         .route("/api/v1/transactions/:id/complete", post(api::complete_transaction))
         .route("/api/v1/transactions/:id/deny", post(api::deny_transaction))
+        .route("/api/v1/transactions/:id/review", post(api::review_transaction))
+        .route("/api/v1/reviews", get(api::list_pending_reviews))
+        .route("/api/v1/risk-thresholds", get(api::get_risk_thresholds).put(api::update_risk_thresholds))
+        .route("/api/v1/tiers", get(api::list_tier_profiles))
+        .route("/api/v1/policies", get(api::list_policies).post(api::create_policy))
+        .route("/api/v1/policies/dry-run", post(api::dry_run_policy))
+        .route("/api/v1/policies/:id", put(api::update_policy).delete(api::delete_policy))
         
         .route("/api/v1/admin/block-requests", get(api::list_block_requests))
         .route("/api/v1/admin/block-requests/:id/approve", post(api::approve_block_request))
         .route("/api/v1/admin/block-requests/:id/deny", post(api::deny_block_request))
         .route("/api/v1/admin/blocks-ledger", get(api::get_all_blocks_ledger))
         .route("/api/v1/admin/agents", get(api::list_all_agents_admin))
+        .route("/api/v1/admin/ledger/drift", get(api::list_ledger_drift))
+        .route("/api/v1/admin/ledger/reconcile", post(api::reconcile_ledger))
+        .route("/api/v1/admin/wallet-operations", get(api::list_pending_wallet_operations))
+        .route("/api/v1/admin/wallet-operations/:id/approve", post(api::approve_wallet_operation))
+        .route("/api/v1/admin/wallet-operations/:id/reject", post(api::reject_wallet_operation))
+        .route("/api/v1/admin/fraud-patterns", get(api::list_fraud_patterns))
+        .route("/api/v1/admin/fraud-patterns/:id/resolve", post(api::resolve_fraud_pattern))
+        .route("/api/v1/admin/blacklist", get(api::list_blacklist).post(api::add_to_blacklist))
+        .route("/api/v1/admin/blacklist/audit", get(api::get_blacklist_audit))
+        .route("/api/v1/admin/blacklist/:agent_id", delete(api::remove_from_blacklist))
+        .route("/api/v1/admin/tiers/:tier", put(api::update_tier_profile))
+        .route("/api/v1/admin/agents/:id/tier", get(api::get_agent_tier).post(api::change_agent_tier))
 
         .route("/api/v1/teams", post(api::create_team))
         .route("/api/v1/teams", get(api::list_teams))
@@ -79,6 +162,9 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/v1/network/graph", get(api::get_network_graph))
         .route("/api/v1/evaluate", post(api::evaluate_agents))
         
+        // Everything else is agent protocol traffic (MCP, ACP) routed through the gateway
+        .fallback(proxy::proxy_request)
+        
         .layer(middleware::from_fn_with_state(state.clone(), request_middleware))
         .layer(CorsLayer::very_permissive())
         .with_state(state);
@@ -0,0 +1,447 @@
+//! Requests parked for review.
+//!
+//! A payment the gateway parks in `pending_review` has already been answered,
+//! so the request is kept with its transaction. Once the owner or merchant
+//! approves, it is sent to the merchant as it arrived. If the merchant cannot
+//! be reached or refuses it, the transaction goes back to `approved` and the
+//! agent's next request with the same nonce goes out in its place.
+
+use anyhow::{anyhow, Result};
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode};
+use security_gateway::{balance, ledger, reservations};
+use security_gateway::{DeclineCode, SecurityContext};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::interceptors::{upstream, BufferedRequest};
+use crate::AppState;
+
+/// What is stored in `transactions.parked_request`
+#[derive(Debug, Serialize, Deserialize)]
+struct ParkedRequest {
+    method: String,
+    uri: String,
+    /// Without credentials; the merchant's allow-list still applies when it is sent
+    headers: Vec<(String, String)>,
+    /// The parked call; its `raw_request` is the body that is sent
+    context: SecurityContext,
+}
+
+impl ParkedRequest {
+    fn capture(request: &BufferedRequest, context: &SecurityContext) -> Self {
+        let headers = upstream::storable_headers(&request.parts.headers)
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        Self {
+            method: request.parts.method.to_string(),
+            uri: request.parts.uri.to_string(),
+            headers,
+            context: context.clone(),
+        }
+    }
+
+    fn rebuild(&self) -> Result<BufferedRequest> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.append(HeaderName::from_bytes(name.as_bytes())?, HeaderValue::from_str(value)?);
+        }
+
+        let mut request = Request::builder()
+            .method(Method::from_bytes(self.method.as_bytes())?)
+            .uri(&self.uri)
+            .body(Body::empty())?;
+        *request.headers_mut() = headers;
+
+        let (parts, _) = request.into_parts();
+        Ok(BufferedRequest { parts, body: Bytes::from(serde_json::to_vec(&self.context.raw_request)?) })
+    }
+}
+
+/// How a release attempt ended
+pub enum Release {
+    /// The merchant accepted the payment; the transaction is completed
+    Sent(Response<Body>),
+    /// The merchant refused it or was unreachable; the transaction is approved again
+    Failed(Response<Body>),
+    /// Its agent or shared payment token can no longer pay for it; the transaction is declined
+    Declined(DeclineCode),
+    /// Not approved, or another release of it is already under way
+    Unavailable,
+}
+
+/// Keep the request behind a call the gateway parked for review
+pub async fn park(state: &AppState, request: &BufferedRequest, context: &SecurityContext) -> Result<()> {
+    let parked = serde_json::to_value(ParkedRequest::capture(request, context))?;
+    sqlx::query(
+        "UPDATE transactions SET parked_request = $3
+         WHERE agent_id = $1 AND nonce = $2 AND status = 'pending_review'"
+    )
+    .bind(&context.agent_id)
+    .bind(&context.nonce)
+    .bind(parked)
+    .execute(&state.db.pool)
+    .await?;
+
+    info!("⏸️ Parked {} request from agent {} for review", context.protocol, context.agent_id);
+    Ok(())
+}
+
+/// The approved transaction an agent's request repeats, if any.
+///
+/// A repeat carries the parked call's nonce; its body may differ, so it is not compared.
+pub async fn find_approved(state: &AppState, context: &SecurityContext) -> Result<Option<Uuid>> {
+    let id = sqlx::query_scalar(
+        "SELECT id FROM transactions
+         WHERE status = 'approved' AND agent_id = $1 AND nonce = $2 AND merchant_id::text = $3
+           AND parked_request IS NOT NULL"
+    )
+    .bind(&context.agent_id)
+    .bind(&context.nonce)
+    .bind(&context.merchant_id)
+    .fetch_optional(&state.db.pool)
+    .await?;
+
+    Ok(id)
+}
+
+/// Send an approved transaction's request to the merchant.
+///
+/// `retry` is the agent's repeat of the parked request; it goes out in place of
+/// the stored copy so fresh session headers reach the merchant. The transaction
+/// is claimed as `sending` while the merchant is called, so a payment is only
+/// ever sent once; a refused or failed send puts it back to `approved`.
+pub async fn release(state: &AppState, transaction_id: Uuid, retry: Option<&BufferedRequest>) -> Result<Release> {
+    let Some(parked) = sqlx::query_scalar::<_, serde_json::Value>(
+        "UPDATE transactions SET status = 'sending'
+         WHERE id = $1 AND status = 'approved' AND parked_request IS NOT NULL
+         RETURNING parked_request"
+    )
+    .bind(transaction_id)
+    .fetch_optional(&state.db.pool)
+    .await? else {
+        return Ok(Release::Unavailable);
+    };
+
+    match send(state, transaction_id, parked, retry).await {
+        Ok(Release::Sent(response)) => {
+            // The merchant has the payment; if this fails the row stays `sending` so it is not sent again
+            if let Err(e) = complete(state, transaction_id).await {
+                error!("❌ Transaction {} was sent but could not be completed: {}", transaction_id, e);
+                return Err(e);
+            }
+            Ok(Release::Sent(response))
+        }
+        Ok(Release::Failed(response)) => {
+            reopen(state, transaction_id).await?;
+            Ok(Release::Failed(response))
+        }
+        Ok(release) => Ok(release),
+        Err(e) => {
+            reopen(state, transaction_id).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Send a claimed transaction; `Sent` means the merchant accepted it
+async fn send(state: &AppState, transaction_id: Uuid, parked: serde_json::Value, retry: Option<&BufferedRequest>) -> Result<Release> {
+    let parked: ParkedRequest = serde_json::from_value(parked)?;
+    let context = &parked.context;
+
+    // The agent may have been suspended or blacklisted while the payment waited
+    let standing = state.gateway.recheck_agent(context).await?;
+    if let Some(failure) = standing.first_failure() {
+        let code = failure.code.unwrap_or(DeclineCode::CheckFailed);
+        warn!("❌ Agent {} can no longer pay approved transaction {}: {}", context.agent_id, transaction_id, code);
+        return decline(state, transaction_id, code).await;
+    }
+
+    if !state.gateway.charge_approved_payment(context).await? {
+        warn!("❌ Shared payment token can no longer pay for approved transaction {}", transaction_id);
+        return decline(state, transaction_id, DeclineCode::PaymentTokenInvalid).await;
+    }
+
+    let stored;
+    let request = match retry {
+        Some(request) => request,
+        None => {
+            stored = parked.rebuild()?;
+            &stored
+        }
+    };
+    let matcher = Request::builder()
+        .method(request.parts.method.clone())
+        .uri(request.parts.uri.clone())
+        .body(Body::empty())?;
+    let interceptor = state.interceptors.iter()
+        .find(|i| i.can_handle(&matcher))
+        .ok_or_else(|| anyhow!("No interceptor for parked request {}", parked.uri))?;
+
+    match interceptor.forward_request(request, std::slice::from_ref(context)).await {
+        Ok(response) if response.status().is_success() => {
+            info!("✅ Approved transaction {} sent to merchant {}", transaction_id, context.merchant_id);
+            Ok(Release::Sent(response))
+        }
+        Ok(response) => {
+            warn!("Merchant refused approved transaction {}: {}", transaction_id, response.status());
+            Ok(Release::Failed(response))
+        }
+        Err(e) => {
+            error!("Failed to send approved transaction {}: {}", transaction_id, e);
+            Ok(Release::Failed(interceptor.error_response(StatusCode::BAD_GATEWAY, "Merchant unreachable")))
+        }
+    }
+}
+
+/// Complete a transaction the merchant accepted, debiting the wallet and posting it
+async fn complete(state: &AppState, transaction_id: Uuid) -> Result<()> {
+    let mut tx = state.db.pool.begin().await?;
+    let completed = sqlx::query(
+        "UPDATE transactions SET status = 'completed', completed_at = NOW()
+         WHERE id = $1 AND status = 'sending'
+         RETURNING agent_id, merchant_id, COALESCE(ledger_amount, amount) AS amount, balance_held"
+    )
+    .bind(transaction_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    // The merchant has the payment, so a short balance is reported rather than undoing it
+    let agent_id: String = completed.get("agent_id");
+    let amount = completed.get("amount");
+    if balance::capture(&mut tx, &agent_id, amount, completed.get("balance_held")).await? {
+        ledger::record_completion(&mut tx, &agent_id, completed.get("merchant_id"), amount, transaction_id).await?;
+    } else {
+        error!("❌ Balance of agent {} cannot cover sent transaction {}", agent_id, transaction_id);
+    }
+    reservations::settle_transaction(&mut tx, transaction_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Put a claimed transaction back to `approved` so it can be sent again
+async fn reopen(state: &AppState, transaction_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE transactions SET status = 'approved' WHERE id = $1 AND status = 'sending'")
+        .bind(transaction_id)
+        .execute(&state.db.pool)
+        .await?;
+    Ok(())
+}
+
+/// Decline a claimed transaction that can no longer be paid, giving back its holds
+async fn decline(state: &AppState, transaction_id: Uuid, code: DeclineCode) -> Result<Release> {
+    let mut tx = state.db.pool.begin().await?;
+    let declined = sqlx::query(
+        "UPDATE transactions SET status = 'declined', decline_code = $2, completed_at = NOW()
+         WHERE id = $1 AND status = 'sending'
+         RETURNING agent_id, COALESCE(ledger_amount, amount) AS amount, balance_held"
+    )
+    .bind(transaction_id)
+    .bind(code.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
+    if declined.get::<bool, _>("balance_held") {
+        balance::release(&mut tx, &declined.get::<String, _>("agent_id"), declined.get("amount")).await?;
+    }
+    tx.commit().await?;
+
+    if let Err(e) = state.reservations.release_transaction(transaction_id).await {
+        error!("Failed to release spend hold: {}", e);
+    }
+    Ok(Release::Declined(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptors::{ACPInterceptor, CheckoutSessionStore, ProtocolInterceptor, UpstreamRegistry};
+    use crate::proxy::ProxyConfig;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use chrono::Utc;
+    use security_gateway::fx::StaticRateProvider;
+    use security_gateway::reservations::ReservationStore;
+    use security_gateway::{Database, GatewayConfig, Money, Protocol, SecurityGateway};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// Stub ACP merchant that is down for the first completion and accepts the next
+    async fn flaky_acp_merchant() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+        let app = Router::new().route("/checkout_sessions/:id/complete", post(move || {
+            let seen = seen.clone();
+            async move {
+                if seen.fetch_add(1, Ordering::SeqCst) == 0 {
+                    (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "type": "processing_error" })))
+                } else {
+                    (StatusCode::OK, Json(json!({ "id": "cs_parked", "status": "completed" })))
+                }
+            }
+        }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}", addr), calls)
+    }
+
+    fn approved_context(agent_id: &str, merchant_id: Uuid) -> SecurityContext {
+        SecurityContext {
+            agent_id: agent_id.to_string(),
+            agent_owner: None,
+            foundational_model: None,
+            protocol: Protocol::ACP,
+            transaction_id: "cs_parked".to_string(),
+            currency: "USD".to_string(),
+            amount: Some(Money::parse("45.00", "USD").unwrap()),
+            merchant_id: merchant_id.to_string(),
+            merchant_name: None,
+            timestamp: Utc::now(),
+            user_id: None,
+            session_id: Some("cs_parked".to_string()),
+            ip_address: None,
+            user_agent: None,
+            payment_method_type: None,
+            payment_token: None,
+            signature: None,
+            nonce: Uuid::new_v4().to_string(),
+            risk_score: None,
+            metadata: Default::default(),
+            raw_request: json!({ "agent_id": agent_id }),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at DATABASE_URL"]
+    async fn approved_payment_stays_approved_until_the_merchant_accepts_it() {
+        std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+        let db = Arc::new(Database::connect().await.expect("connect to DATABASE_URL"));
+        let pool = db.pool.clone();
+        let (merchant_url, calls) = flaky_acp_merchant().await;
+
+        let merchant_id = Uuid::new_v4();
+        let agent_id = format!("test-parked-{}", merchant_id);
+        sqlx::query(
+            "INSERT INTO merchants (id, email, password_hash, merchant_name, domain, upstream_base_url)
+             VALUES ($1, $2, 'x', 'Parked Test', 'parked-test.example.com', $3)"
+        )
+        .bind(merchant_id)
+        .bind(format!("{}@parked-test.example.com", merchant_id))
+        .bind(&merchant_url)
+        .execute(&pool)
+        .await
+        .expect("insert test merchant");
+        sqlx::query(
+            "INSERT INTO agents (id, owner_company, owner_email, protocol)
+             VALUES ($1, 'Parked Test', 'parked-test@example.com', 'ACP')"
+        )
+        .bind(&agent_id)
+        .execute(&pool)
+        .await
+        .expect("insert test agent");
+
+        let context = approved_context(&agent_id, merchant_id);
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/acp/{}/checkout_sessions/cs_parked/complete", merchant_id))
+            .header("content-type", "application/json")
+            .header("authorization", "Bearer agent-secret")
+            .body(Body::empty())
+            .unwrap();
+        let (parts, _) = request.into_parts();
+        let parked = ParkedRequest::capture(&BufferedRequest { parts, body: Bytes::new() }, &context);
+        assert!(parked.headers.iter().all(|(name, _)| name != "authorization"));
+
+        let transaction_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO transactions (agent_id, merchant_id, protocol, amount, currency, status, nonce, parked_request)
+             VALUES ($1, $2, 'ACP', 45.00, 'USD', 'approved', $3, $4)
+             RETURNING id"
+        )
+        .bind(&agent_id)
+        .bind(merchant_id)
+        .bind(&context.nonce)
+        .bind(serde_json::to_value(&parked).unwrap())
+        .fetch_one(&pool)
+        .await
+        .expect("insert approved transaction");
+
+        let upstreams = Arc::new(UpstreamRegistry::new(pool.clone()));
+        let interceptors: Vec<Arc<dyn ProtocolInterceptor>> = vec![
+            Arc::new(ACPInterceptor::new(upstreams, CheckoutSessionStore::new(pool.clone()))),
+        ];
+        let state = AppState {
+            gateway: Arc::new(SecurityGateway::with_config(GatewayConfig::default()).await.unwrap()),
+            interceptors,
+            proxy_config: ProxyConfig::from_env(),
+            db: db.clone(),
+            reservations: ReservationStore::new(pool.clone()),
+            rates: Arc::new(StaticRateProvider::empty()),
+        };
+        let status = |pool: sqlx::PgPool| async move {
+            sqlx::query_scalar::<_, String>("SELECT status FROM transactions WHERE id = $1")
+                .bind(transaction_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        assert!(matches!(release(&state, transaction_id, None).await.unwrap(), Release::Failed(_)));
+        assert_eq!(status(pool.clone()).await, "approved");
+
+        // Claimed while it is being sent, so a concurrent release or retry leaves it alone
+        let set_status = |status: &'static str| {
+            sqlx::query("UPDATE transactions SET status = $2 WHERE id = $1")
+                .bind(transaction_id)
+                .bind(status)
+                .execute(&pool)
+        };
+        set_status("sending").await.unwrap();
+        assert!(matches!(release(&state, transaction_id, None).await.unwrap(), Release::Unavailable));
+        assert_eq!(find_approved(&state, &context).await.unwrap(), None);
+        set_status("approved").await.unwrap();
+
+        // The agent's repeat of the request finds the approved transaction by its nonce and gets it through
+        let repeat = SecurityContext { raw_request: json!({ "agent_id": agent_id, "retry": true }), ..context.clone() };
+        assert_eq!(find_approved(&state, &repeat).await.unwrap(), Some(transaction_id));
+        let other = SecurityContext { nonce: Uuid::new_v4().to_string(), ..context.clone() };
+        assert_eq!(find_approved(&state, &other).await.unwrap(), None);
+        assert!(matches!(release(&state, transaction_id, None).await.unwrap(), Release::Sent(_)));
+        assert_eq!(status(pool.clone()).await, "completed");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Sent once only
+        assert!(matches!(release(&state, transaction_id, None).await.unwrap(), Release::Unavailable));
+
+        // An agent suspended while its payment waited is declined without calling the merchant
+        let later = approved_context(&agent_id, merchant_id);
+        let later_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO transactions (agent_id, merchant_id, protocol, amount, currency, status, nonce, parked_request)
+             VALUES ($1, $2, 'ACP', 45.00, 'USD', 'approved', $3, $4)
+             RETURNING id"
+        )
+        .bind(&agent_id)
+        .bind(merchant_id)
+        .bind(&later.nonce)
+        .bind(serde_json::to_value(ParkedRequest { context: later.clone(), ..parked }).unwrap())
+        .fetch_one(&pool)
+        .await
+        .expect("insert second approved transaction");
+        sqlx::query("UPDATE agents SET status = 'suspended' WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+        assert!(matches!(
+            release(&state, later_id, None).await.unwrap(),
+            Release::Declined(DeclineCode::AgentInactive)
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        sqlx::query("DELETE FROM transactions WHERE id IN ($1, $2)").bind(transaction_id).bind(later_id).execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM merchants WHERE id = $1").bind(merchant_id).execute(&pool).await.unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::interceptors::BufferedRequest;
+use crate::parked::{self, Release};
+use crate::AppState;
+use security_gateway::Decision;
+
+/// Default cap on buffered agent request bodies (1 MiB)
+const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Settings for the inline proxy path
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    pub max_body_bytes: usize,
+}
+
+impl ProxyConfig {
+    /// Read settings from the environment (`PROXY_MAX_BODY_BYTES`)
+    pub fn from_env() -> Self {
+        let max_body_bytes = std::env::var("PROXY_MAX_BODY_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+
+        Self { max_body_bytes }
+    }
+}
+
+/// Inline proxy for agent traffic.
+///
+/// Every request that does not match an API route lands here. The first
+/// interceptor that recognises the protocol extracts a `SecurityContext` per
+/// payment call, the gateway verifies and logs each one, and only approved
+/// calls are forwarded to the merchant. Calls parked for review are kept and
+/// sent once approved (see `parked`).
+pub async fn proxy_request(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+) -> Response {
+    let Some(interceptor) = state.interceptors.iter().find(|i| i.can_handle(&request)).cloned() else {
+        warn!("No interceptor for {} {}", request.method(), request.uri().path());
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    info!("🛡️ {} request: {} {}", interceptor.protocol_name(), request.method(), request.uri().path());
+
+    // Buffer once; extraction and forwarding both read from the same bytes
+    let request = match BufferedRequest::from_request(request, state.proxy_config.max_body_bytes).await {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Rejected request body: {}", e);
+            return interceptor.error_response(e.status(), &e.to_string());
+        }
+    };
+
+    let contexts = match interceptor.extract_security_contexts(&request).await {
+        Ok(contexts) => contexts,
+        Err(e) => {
+            warn!("Failed to extract security context: {}", e);
+            return interceptor.extraction_error_response(&e);
+        }
+    };
+
+    if contexts.is_empty() {
+        info!("No payment-relevant calls; passing through");
+    }
+
+    // A repeat of a payment approved after review goes out without being verified again
+    if let [context] = contexts.as_slice() {
+        let approved = match parked::find_approved(&state, context).await {
+            Ok(approved) => approved,
+            Err(e) => {
+                error!("Failed to look up approved transactions: {}", e);
+                return interceptor.error_response(StatusCode::INTERNAL_SERVER_ERROR, "Verification unavailable");
+            }
+        };
+        if let Some(transaction_id) = approved {
+            info!("🔁 Retrying approved transaction {}", transaction_id);
+            match parked::release(&state, transaction_id, Some(&request)).await {
+                Ok(Release::Sent(response)) | Ok(Release::Failed(response)) => return response,
+                Ok(Release::Declined(code)) => {
+                    return interceptor.error_response(StatusCode::PAYMENT_REQUIRED, &format!("Approved payment declined: {}", code));
+                }
+                Ok(Release::Unavailable) => {
+                    return interceptor.error_response(StatusCode::CONFLICT, "Approved payment is already being sent");
+                }
+                Err(e) => {
+                    error!("Failed to release approved transaction: {}", e);
+                    return interceptor.error_response(StatusCode::INTERNAL_SERVER_ERROR, "Could not send approved payment");
+                }
+            }
+        }
+    }
+
+    let mut declined = Vec::new();
+    for context in &contexts {
+        let verification = match state.gateway.verify(context).await {
+            Ok(verification) => verification,
+            Err(e) => {
+                error!("Security gateway verification failed: {}", e);
+                return interceptor.error_response(StatusCode::INTERNAL_SERVER_ERROR, "Verification unavailable");
+            }
+        };
+
+        if let Err(e) = state.gateway.log_transaction(context, &verification).await {
+            error!("Failed to log transaction: {}", e);
+            return interceptor.error_response(StatusCode::INTERNAL_SERVER_ERROR, "Could not record transaction");
+        }
+
+        // Kept so it can be sent once the review approves it
+        if verification.decision == Decision::Review {
+            if let Err(e) = parked::park(&state, &request, context).await {
+                error!("Failed to park request: {}", e);
+                return interceptor.error_response(StatusCode::INTERNAL_SERVER_ERROR, "Could not record transaction");
+            }
+        }
+
+        if !verification.approved {
+            declined.push((context.clone(), verification));
+        }
+    }
+
+    let result = if declined.is_empty() {
+        interceptor.forward_request(&request, &contexts).await
+    } else {
+        interceptor.respond_to_declines(&request, &contexts, &declined).await
+    };
+
+    match result {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to forward request to merchant: {}", e);
+            interceptor.error_response(StatusCode::BAD_GATEWAY, "Merchant unreachable")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptors::{ACPInterceptor, CheckoutSessionStore, ProtocolInterceptor, UpstreamRegistry};
+    use axum::body::Bytes;
+    use futures_util::stream;
+
+    const LIMIT: usize = 16;
+
+    fn acp_interceptor() -> ACPInterceptor {
+        // Rejected bodies never reach the database
+        let pool = sqlx::PgPool::connect_lazy("postgresql://localhost/unused").unwrap();
+        ACPInterceptor::new(Arc::new(UpstreamRegistry::new(pool.clone())), CheckoutSessionStore::new(pool))
+    }
+
+    async fn buffer(request: Request<Body>) -> Result<BufferedRequest, Response> {
+        let config = ProxyConfig { max_body_bytes: LIMIT };
+        BufferedRequest::from_request(request, config.max_body_bytes)
+            .await
+            .map_err(|e| acp_interceptor().error_response(e.status(), &e.to_string()))
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_with_413() {
+        let declared = Request::post("/acp/checkout_sessions")
+            .header("content-length", (LIMIT + 1).to_string())
+            .body(Body::from(vec![b'x'; LIMIT + 1]))
+            .unwrap();
+        assert_eq!(buffer(declared).await.err().unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // No Content-Length: the stream is cut off once it crosses the limit
+        let chunks = vec![Ok::<_, std::io::Error>(Bytes::from(vec![b'x'; LIMIT])), Ok(Bytes::from_static(b"x"))];
+        let streamed = Request::post("/acp/checkout_sessions")
+            .body(Body::from_stream(stream::iter(chunks)))
+            .unwrap();
+        assert_eq!(buffer(streamed).await.err().unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn bodies_within_the_limit_are_buffered() {
+        let request = Request::post("/acp/checkout_sessions")
+            .body(Body::from(vec![b'x'; LIMIT]))
+            .unwrap();
+        let buffered = buffer(request).await.ok().unwrap();
+        assert_eq!(buffered.body.len(), LIMIT);
+    }
+}
@@ -0,0 +1,96 @@
+//! Agent balance holds and debits.
+//!
+//! Every change is a single conditional UPDATE, so the row lock Postgres takes
+//! for it serialises concurrent requests and a short balance makes the update
+//! match nothing instead of going negative. Available balance is
+//! `remaining_balance - held_balance`.
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+
+/// Hold `amount` for a pending transaction; false if the available balance is short
+pub async fn hold(conn: &mut PgConnection, agent_id: &str, amount: Decimal) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE agents
+         SET held_balance = held_balance + $2
+         WHERE id = $1 AND remaining_balance - held_balance >= $2"
+    )
+    .bind(agent_id)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Debit a completed transaction and count it in the agent's volume.
+///
+/// `held` says whether the amount was held at creation; transactions created
+/// before holds existed are debited straight from the balance. False if the
+/// balance cannot cover the debit.
+pub async fn capture(conn: &mut PgConnection, agent_id: &str, amount: Decimal, held: bool) -> Result<bool> {
+    let released = if held { amount } else { Decimal::ZERO };
+    let result = sqlx::query(
+        "UPDATE agents
+         SET remaining_balance = remaining_balance - $2,
+             held_balance = held_balance - $3,
+             total_volume = total_volume + $2,
+             transaction_count = transaction_count + 1
+         WHERE id = $1 AND remaining_balance >= $2 AND held_balance >= $3"
+    )
+    .bind(agent_id)
+    .bind(amount)
+    .bind(released)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Return a denied transaction's hold to the available balance
+pub async fn release(conn: &mut PgConnection, agent_id: &str, amount: Decimal) -> Result<()> {
+    sqlx::query(
+        "UPDATE agents
+         SET held_balance = GREATEST(held_balance - $2, 0)
+         WHERE id = $1"
+    )
+    .bind(agent_id)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Add funds to an agent wallet
+pub async fn fund(conn: &mut PgConnection, agent_id: &str, amount: Decimal) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE agents
+         SET balance = balance + $2,
+             remaining_balance = remaining_balance + $2
+         WHERE id = $1"
+    )
+    .bind(agent_id)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Take funds out of an agent wallet; false if they are spent or held
+pub async fn withdraw(conn: &mut PgConnection, agent_id: &str, amount: Decimal) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE agents
+         SET balance = balance - $2,
+             remaining_balance = remaining_balance - $2
+         WHERE id = $1 AND remaining_balance - held_balance >= $2"
+    )
+    .bind(agent_id)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
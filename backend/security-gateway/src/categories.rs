@@ -0,0 +1,229 @@
+//! Merchant and line-item category restrictions.
+//!
+//! Owners give each agent rules over merchant category codes (MCC) and
+//! line-item categories. `deny` and `review` rules catch the listed category;
+//! `allow` rules turn their scope into an allow-list, so any other category is
+//! denied and anything without a category goes to review.
+
+use crate::models::SecurityContext;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A purchased item as far as category rules care
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LineItem {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl LineItem {
+    /// A string names the item; objects are `{id, name, category}` or ACP line items with a nested `item`
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(name) => Some(Self { name: Some(name.clone()), ..Self::default() }),
+            Value::Object(_) => {
+                let text = |paths: &[&str]| {
+                    paths.iter()
+                        .find_map(|path| value.pointer(path).and_then(Value::as_str))
+                        .map(str::to_string)
+                };
+                Some(Self {
+                    id: text(&["/id", "/item/id"]),
+                    name: text(&["/name", "/title", "/item/name", "/item/title"]),
+                    category: text(&["/category", "/item/category"]).map(|c| normalize(&c)),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn label(&self, index: usize) -> String {
+        self.name.clone()
+            .or_else(|| self.id.clone())
+            .unwrap_or_else(|| format!("item {}", index + 1))
+    }
+}
+
+/// Items in a JSON array; anything else holds none
+pub fn line_items(value: &Value) -> Vec<LineItem> {
+    value.as_array()
+        .map(|items| items.iter().filter_map(LineItem::from_value).collect())
+        .unwrap_or_default()
+}
+
+/// Line items the protocol interceptor attached to the request
+pub fn context_items(ctx: &SecurityContext) -> Vec<LineItem> {
+    ["line_items", "items"]
+        .iter()
+        .find_map(|key| ctx.metadata.get(*key))
+        .map(line_items)
+        .unwrap_or_default()
+}
+
+/// Categories are compared case-insensitively
+pub fn normalize(category: &str) -> String {
+    category.trim().to_ascii_lowercase()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategoryScope {
+    /// The merchant's MCC
+    Merchant,
+    Item,
+}
+
+impl CategoryScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CategoryScope::Merchant => "merchant",
+            CategoryScope::Item => "item",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategoryEffect {
+    Allow,
+    Review,
+    Deny,
+}
+
+impl CategoryEffect {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CategoryEffect::Allow => "allow",
+            CategoryEffect::Review => "review",
+            CategoryEffect::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub scope: CategoryScope,
+    /// A four-digit MCC for merchants, a category name for items
+    pub category: String,
+    pub effect: CategoryEffect,
+}
+
+impl CategoryRule {
+    /// Build a rule from stored text columns
+    pub fn from_stored(scope: &str, category: &str, effect: &str) -> Result<Self> {
+        let scope = match scope {
+            "merchant" => CategoryScope::Merchant,
+            "item" => CategoryScope::Item,
+            other => bail!("Unknown category scope '{}'", other),
+        };
+        let effect = match effect {
+            "allow" => CategoryEffect::Allow,
+            "review" => CategoryEffect::Review,
+            "deny" => CategoryEffect::Deny,
+            other => bail!("Unknown category effect '{}'", other),
+        };
+        Ok(Self { scope, category: category.to_string(), effect })
+    }
+
+    /// Normalized copy, or an error naming what is wrong
+    pub fn validated(&self) -> Result<Self> {
+        let category = normalize(&self.category);
+        match self.scope {
+            CategoryScope::Merchant if !is_mcc(&category) => {
+                bail!("Merchant rules need a four-digit MCC, got '{}'", self.category)
+            }
+            CategoryScope::Item if category.is_empty() || category.len() > 50 => {
+                bail!("Item categories must be 1-50 characters")
+            }
+            _ => Ok(Self { category, ..self.clone() }),
+        }
+    }
+}
+
+pub fn is_mcc(code: &str) -> bool {
+    code.len() == 4 && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The merchant or a line item that breaks the agent's category rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestrictedItem {
+    /// Position in the request's line items; `None` when the merchant's MCC is restricted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// `review` or `deny`
+    pub effect: CategoryEffect,
+    pub reason: String,
+}
+
+/// Everything that breaks a rule, merchant first
+pub fn evaluate(rules: &[CategoryRule], merchant_mcc: Option<&str>, items: &[LineItem]) -> Vec<RestrictedItem> {
+    let mut restricted = Vec::new();
+
+    if let Some((effect, reason)) = judge(rules, CategoryScope::Merchant, merchant_mcc) {
+        restricted.push(RestrictedItem {
+            index: None,
+            id: None,
+            name: "merchant".to_string(),
+            category: merchant_mcc.map(str::to_string),
+            effect,
+            reason,
+        });
+    }
+    for (index, item) in items.iter().enumerate() {
+        if let Some((effect, reason)) = judge(rules, CategoryScope::Item, item.category.as_deref()) {
+            restricted.push(RestrictedItem {
+                index: Some(index),
+                id: item.id.clone(),
+                name: item.label(index),
+                category: item.category.clone(),
+                effect,
+                reason,
+            });
+        }
+    }
+    restricted
+}
+
+/// One line per restricted item, e.g. "Vintage port (alcohol): alcohol is denied"
+pub fn describe(restricted: &[RestrictedItem]) -> String {
+    restricted.iter()
+        .map(|r| match &r.category {
+            Some(category) => format!("{} ({}): {}", r.name, category, r.reason),
+            None => format!("{}: {}", r.name, r.reason),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn judge(rules: &[CategoryRule], scope: CategoryScope, category: Option<&str>) -> Option<(CategoryEffect, String)> {
+    let scoped = || rules.iter().filter(move |r| r.scope == scope);
+
+    if let Some(category) = category {
+        let strongest = scoped()
+            .filter(|r| r.effect != CategoryEffect::Allow && r.category == category)
+            .map(|r| r.effect)
+            .max();
+        if let Some(effect) = strongest {
+            let verdict = if effect == CategoryEffect::Deny { "denied" } else { "held for review" };
+            return Some((effect, format!("{} is {}", category, verdict)));
+        }
+    }
+
+    let mut allowed = scoped().filter(|r| r.effect == CategoryEffect::Allow).peekable();
+    allowed.peek()?;
+    match category {
+        Some(category) if allowed.any(|r| r.category == category) => None,
+        Some(category) => Some((CategoryEffect::Deny, format!("{} is not an allowed category", category))),
+        None => Some((CategoryEffect::Review, "no category, and only listed categories are allowed".to_string())),
+    }
+}
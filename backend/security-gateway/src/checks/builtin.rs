@@ -0,0 +1,720 @@
+use super::{CheckInput, CheckOutcome, SecurityCheck};
+use crate::balance;
+use crate::categories::{self, CategoryEffect};
+use crate::config::DEFAULT_VELOCITY_LIMIT_PER_MINUTE;
+use crate::fraud;
+use crate::ledger::LEDGER_CURRENCY;
+use crate::models::*;
+use crate::policy::{self, Effect, PolicyInput};
+use crate::reservations::{hold_ttl_from_env, HoldOutcome, HoldRequest};
+use anyhow::Result;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use tracing::{info, warn};
+
+/// Limits and usage are plain decimals in the owner's base currency
+fn in_currency(amount: Decimal, currency: &str) -> Money {
+    Money::from_stored(amount, currency)
+}
+
+/// Loads the agent for every later check
+pub struct AgentExistsCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for AgentExistsCheck {
+    fn name(&self) -> &'static str {
+        "agent_exists"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        match input.db.get_agent(&input.ctx.agent_id).await {
+            Ok(agent) => {
+                input.agent = Some(agent);
+                Ok(CheckOutcome::pass())
+            }
+            Err(e) => {
+                warn!("Agent not found: {}", e);
+                Ok(CheckOutcome::fail(DeclineCode::AgentNotFound, format!("Agent not found: {}", input.ctx.agent_id)))
+            }
+        }
+    }
+}
+
+pub struct AgentActiveCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for AgentActiveCheck {
+    fn name(&self) -> &'static str {
+        "agent_active"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        
+        if agent.status == "active" {
+            Ok(CheckOutcome::pass())
+        } else {
+            Ok(CheckOutcome::fail(DeclineCode::AgentInactive, format!("Agent is {}", agent.status)))
+        }
+    }
+}
+
+/// Applies the agent's tier profile: allowed protocols, velocity ceiling and manual review.
+///
+/// The tier's spending maxima are already folded into the agent's limits when it is loaded.
+pub struct TierCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for TierCheck {
+    fn name(&self) -> &'static str {
+        "tier_profile"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(tier) = input.agent.as_ref().and_then(|a| a.tier.clone()) else {
+            return Ok(CheckOutcome::skip("Agent unknown or without a tier"));
+        };
+        let Some(profile) = input.db.get_tier_profile(&tier).await? else {
+            return Ok(CheckOutcome::skip(format!("No profile for tier {}", tier)));
+        };
+        
+        let protocol = &input.ctx.protocol;
+        let outcome = if !profile.allows_protocol(protocol) {
+            CheckOutcome::fail(DeclineCode::TierRestricted, format!("Tier {} may not pay over {}", tier, protocol))
+        } else if profile.requires_manual_review {
+            CheckOutcome::review(format!("Tier {} payments need manual review", tier))
+        } else {
+            CheckOutcome::pass_with(format!("Tier {}", tier))
+        };
+        input.tier = Some(profile);
+        Ok(outcome)
+    }
+}
+
+/// Replay attack prevention
+pub struct NonceCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for NonceCheck {
+    fn name(&self) -> &'static str {
+        "nonce_fresh"
+    }
+    
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        if input.db.check_and_store_nonce(&input.ctx.agent_id, &input.ctx.nonce).await? {
+            Ok(CheckOutcome::pass())
+        } else {
+            Ok(CheckOutcome::fail(DeclineCode::Replay, "Nonce already used - replay attack detected"))
+        }
+    }
+}
+
+pub struct PerTransactionLimitCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for PerTransactionLimitCheck {
+    fn name(&self) -> &'static str {
+        "within_per_tx_limit"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        // Amount not known yet (e.g., ACP create-checkout)
+        let Some(base_amount) = input.base_amount() else {
+            return Ok(CheckOutcome::skip("No amount"));
+        };
+        let (amount, currency) = (base_amount.to_decimal(), base_amount.currency.clone());
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        
+        let per_tx_limit = agent.spending_limit_per_tx;
+        let limit = LimitDetail::new(per_tx_limit, Decimal::ZERO, amount, Some(currency.clone()));
+        
+        let outcome = if amount <= per_tx_limit {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::PerTxLimit, format!(
+                "Amount {} exceeds per-transaction limit {}",
+                in_currency(amount, &currency), in_currency(per_tx_limit, &currency)
+            ))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+pub struct DailyLimitCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for DailyLimitCheck {
+    fn name(&self) -> &'static str {
+        "within_daily_limit"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(base_amount) = input.base_amount() else {
+            return Ok(CheckOutcome::skip("No amount"));
+        };
+        let (amount, currency) = (base_amount.to_decimal(), base_amount.currency.clone());
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        
+        let daily_spent = input.db.get_daily_spending(&input.ctx.agent_id, agent.timezone()).await?;
+        let daily_limit = agent.spending_limit_daily;
+        let limit = LimitDetail::new(daily_limit, daily_spent, amount, Some(currency.clone()));
+        
+        let outcome = if daily_spent + amount <= daily_limit {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::DailyLimit, format!(
+                "Would exceed daily limit {} (spent or held: {}, remaining: {})",
+                in_currency(daily_limit, &currency), in_currency(daily_spent, &currency), in_currency(limit.remaining, &currency)
+            ))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+pub struct MonthlyLimitCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for MonthlyLimitCheck {
+    fn name(&self) -> &'static str {
+        "within_monthly_limit"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(base_amount) = input.base_amount() else {
+            return Ok(CheckOutcome::skip("No amount"));
+        };
+        let (amount, currency) = (base_amount.to_decimal(), base_amount.currency.clone());
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        let Some(monthly_limit) = agent.spending_limit_monthly else {
+            return Ok(CheckOutcome::skip("No monthly limit set"));
+        };
+        
+        let window = agent.monthly_window();
+        let monthly_spent = input.db.get_monthly_spending(&input.ctx.agent_id, agent.timezone(), window).await?;
+        let limit = LimitDetail::new(monthly_limit, monthly_spent, amount, Some(currency.clone()));
+        
+        let outcome = if monthly_spent + amount <= monthly_limit {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::MonthlyLimit, format!(
+                "Would exceed {} limit {} (spent or held: {}, remaining: {})",
+                window.label(), in_currency(monthly_limit, &currency), in_currency(monthly_spent, &currency), in_currency(limit.remaining, &currency)
+            ))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+/// The merchant's cap on any single agent payment, from its controls.
+///
+/// The cap is in the ledger currency; other amounts are converted to compare.
+pub struct MerchantMaxAmountCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for MerchantMaxAmountCheck {
+    fn name(&self) -> &'static str {
+        "merchant_max_amount"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let max = input.db.get_merchant_controls(&input.ctx.merchant_id).await?
+            .and_then(|controls| controls.max_agent_tx_amount);
+        let Some(max) = max else {
+            return Ok(CheckOutcome::skip("Merchant sets no maximum"));
+        };
+        let amount = match input.ledger_amount().await {
+            Ok(Some(amount)) => amount,
+            Ok(None) => return Ok(CheckOutcome::skip("No amount to compare against the merchant maximum")),
+            Err(unconvertible) => return Ok(unconvertible),
+        };
+        
+        let requested = amount.to_decimal();
+        let limit = LimitDetail::new(max, Decimal::ZERO, requested, Some(LEDGER_CURRENCY.to_string()));
+        let outcome = if requested <= max {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::MerchantLimit, format!(
+                "Amount {} exceeds merchant maximum {}",
+                amount, in_currency(max, LEDGER_CURRENCY)
+            ))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+/// The agent's wallet (held in the ledger currency) must cover the amount,
+/// converted at the gateway's rates when it is in another currency.
+///
+/// Holds already placed for pending transactions are not available.
+pub struct BalanceCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for BalanceCheck {
+    fn name(&self) -> &'static str {
+        "balance_sufficient"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        let amount = match input.ledger_amount().await {
+            Ok(Some(amount)) => amount,
+            Ok(None) => return Ok(CheckOutcome::skip("No amount to compare against the wallet")),
+            Err(unconvertible) => return Ok(unconvertible),
+        };
+        
+        let requested = amount.to_decimal();
+        let available = agent.available_balance();
+        let limit = LimitDetail::new(available, Decimal::ZERO, requested, Some(LEDGER_CURRENCY.to_string()));
+        
+        let outcome = if requested <= available {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::InsufficientFunds, format!(
+                "Insufficient balance: {} available, {} requested",
+                in_currency(available, LEDGER_CURRENCY), amount
+            ))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+/// Platform-wide bans, applied before any merchant's own rules
+pub struct BlacklistCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for BlacklistCheck {
+    fn name(&self) -> &'static str {
+        "agent_not_blacklisted"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        match input.db.blacklist_reason(&input.ctx.agent_id).await? {
+            Some(reason) => Ok(CheckOutcome::fail(DeclineCode::AgentBlacklisted, format!("Agent is blacklisted: {}", reason))),
+            None => Ok(CheckOutcome::pass()),
+        }
+    }
+}
+
+/// The merchant has not blocked the agent and, if it keeps an allow-list, lists it
+pub struct MerchantAllowedCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for MerchantAllowedCheck {
+    fn name(&self) -> &'static str {
+        "merchant_allowed"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let ctx = input.ctx;
+        let access = input.db.merchant_access(&ctx.merchant_id, &ctx.agent_id).await?;
+        
+        if let Some(reason) = &access.blocked_reason {
+            Ok(CheckOutcome::fail(DeclineCode::AgentBlocked, format!("Agent blocked by merchant {}: {}", ctx.merchant_id, reason)))
+        } else if !access.allows(&ctx.agent_id) {
+            Ok(CheckOutcome::fail(DeclineCode::MerchantNotAllowed, format!("Merchant {} only accepts agents on its allow-list", ctx.merchant_id)))
+        } else {
+            Ok(CheckOutcome::pass())
+        }
+    }
+}
+
+pub struct VelocityCheck {
+    pub limit_per_minute: i64,
+}
+
+impl Default for VelocityCheck {
+    fn default() -> Self {
+        Self { limit_per_minute: DEFAULT_VELOCITY_LIMIT_PER_MINUTE }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecurityCheck for VelocityCheck {
+    fn name(&self) -> &'static str {
+        "velocity_check_passed"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let limit_per_minute = match &input.tier {
+            Some(tier) => tier.velocity_limit(self.limit_per_minute),
+            None => self.limit_per_minute,
+        };
+        let recent_tx_count = input.db.count_recent_transactions(&input.ctx.agent_id, 60).await?;
+        let limit = LimitDetail::new(limit_per_minute.into(), recent_tx_count.into(), Decimal::ONE, None);
+        
+        let outcome = if recent_tx_count < limit_per_minute {
+            CheckOutcome::pass()
+        } else {
+            CheckOutcome::fail(DeclineCode::Velocity, format!("Too many transactions: {} in last minute", recent_tx_count))
+        };
+        Ok(outcome.with_limit(limit))
+    }
+}
+
+/// Runs the fraud detectors over recent activity plus this request and records what they find.
+///
+/// Fails while the agent has unresolved high or critical findings, whether
+/// raised now or earlier.
+pub struct PatternCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for PatternCheck {
+    fn name(&self) -> &'static str {
+        "pattern_normal"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        
+        let store = input.db.fraud();
+        let now = Utc::now();
+        let mut activity = store.recent_activity(&input.ctx.agent_id, now - chrono::Duration::hours(fraud::DETECTION_WINDOW_HOURS)).await?;
+        activity.push(fraud::ActivityRecord {
+            merchant_id: input.ctx.merchant_id.clone(),
+            amount: input.base_amount().map(Money::to_decimal),
+            created_at: now,
+        });
+        
+        let findings = fraud::detect(&activity, Some(agent.spending_limit_per_tx), now);
+        store.record(&input.ctx.agent_id, &findings).await?;
+        
+        let blocking = store.blocking_patterns(&input.ctx.agent_id).await?;
+        if blocking.is_empty() {
+            Ok(CheckOutcome::pass())
+        } else {
+            Ok(CheckOutcome::fail(DeclineCode::SuspiciousPattern, format!(
+                "Unresolved suspicious activity: {}",
+                blocking.join(", ")
+            )))
+        }
+    }
+}
+
+/// Evaluates the owner's spending policies for the agent and its teams
+pub struct PolicyCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for PolicyCheck {
+    fn name(&self) -> &'static str {
+        "policy_rules"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(agent) = &input.agent else {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        };
+        let policies = input.db.get_policies(&input.ctx.agent_id).await?;
+        if policies.is_empty() {
+            return Ok(CheckOutcome::skip("No spending policies"));
+        }
+        
+        let policy_input = PolicyInput {
+            agent_id: &input.ctx.agent_id,
+            merchant_id: &input.ctx.merchant_id,
+            amount: input.base_amount(),
+            tier: agent.tier.as_deref(),
+            timezone: agent.timezone(),
+            now: Utc::now(),
+        };
+        let evaluation = policy::evaluate(&policies, input.db, &policy_input).await?;
+        
+        let Some(rule) = evaluation.decisive() else {
+            return Ok(CheckOutcome::pass());
+        };
+        let detail = format!("Rule '{}' in policy '{}': {}", rule.rule, rule.policy, rule.reasons.join(", "));
+        match rule.effect {
+            Effect::Deny => Ok(CheckOutcome::fail(DeclineCode::PolicyViolation, detail)),
+            Effect::Review => Ok(CheckOutcome::review(detail)),
+        }
+    }
+}
+
+/// The owner's merchant-MCC and line-item category rules for the agent
+pub struct CategoryCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for CategoryCheck {
+    fn name(&self) -> &'static str {
+        "item_categories"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let ctx = input.ctx;
+        let rules = input.db.get_category_rules(&ctx.agent_id).await?;
+        if rules.is_empty() {
+            return Ok(CheckOutcome::skip("No category rules"));
+        }
+        
+        let mcc = input.db.merchant_mcc(&ctx.merchant_id).await?;
+        let items = categories::context_items(ctx);
+        let restricted = categories::evaluate(&rules, mcc.as_deref(), &items);
+        
+        let Some(effect) = restricted.iter().map(|r| r.effect).max() else {
+            return Ok(CheckOutcome::pass_with(format!("{} line items within category rules", items.len())));
+        };
+        let detail = categories::describe(&restricted);
+        let outcome = match effect {
+            CategoryEffect::Deny => CheckOutcome::fail(DeclineCode::CategoryRestricted, detail),
+            _ => CheckOutcome::review(detail),
+        };
+        Ok(outcome.with_restricted_items(restricted))
+    }
+}
+
+/// The merchant reviews an agent's first payment to it unless it auto-approves agents
+pub struct FirstPaymentReviewCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for FirstPaymentReviewCheck {
+    fn name(&self) -> &'static str {
+        "merchant_first_payment"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let ctx = input.ctx;
+        let reviews = input.db.get_merchant_controls(&ctx.merchant_id).await?
+            .is_some_and(|controls| controls.reviews_first_payment());
+        if !reviews {
+            return Ok(CheckOutcome::skip("Merchant does not review first payments"));
+        }
+        
+        if input.db.merchant_payment_count(&ctx.agent_id, &ctx.merchant_id).await? == 0 {
+            Ok(CheckOutcome::review_by(ReviewParty::Merchant, format!(
+                "First payment from this agent to merchant {} needs the merchant's review",
+                ctx.merchant_id
+            )))
+        } else {
+            Ok(CheckOutcome::pass())
+        }
+    }
+}
+
+/// Scores the payment's risk against the owner's and merchant's thresholds.
+///
+/// Runs before the side-effecting checks, so a risk decline places no hold and
+/// spends no token.
+pub struct RiskCheck {
+    /// Platform thresholds, for owners and merchants who set none
+    pub defaults: RiskThresholds,
+}
+
+#[async_trait::async_trait]
+impl SecurityCheck for RiskCheck {
+    fn name(&self) -> &'static str {
+        "risk_score"
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let ctx = input.ctx;
+        let risk = RiskScorer::assess(input.db, ctx, input.base_amount(), Utc::now()).await?;
+        let thresholds = input.db.get_risk_thresholds(&ctx.agent_id, &ctx.merchant_id, self.defaults).await?;
+        
+        for factor in risk.factors.iter().filter(|f| f.points > 0.0) {
+            info!("  +{:.0} {:?}: {}", factor.points, factor.feature, factor.detail);
+        }
+        let outcome = match thresholds.decide(risk.score) {
+            Decision::Approve => CheckOutcome::pass_with(format!("Risk score {:.0}", risk.score)),
+            Decision::Review => {
+                // Scores past the merchant's own alert threshold are the merchant's to review
+                let merchant_alert = input.db.get_merchant_controls(&ctx.merchant_id).await?
+                    .and_then(|controls| controls.fraud_alert_threshold)
+                    .is_some_and(|threshold| risk.score >= f64::from(threshold));
+                let party = if merchant_alert { ReviewParty::Merchant } else { ReviewParty::Owner };
+                CheckOutcome::review_by(party, format!(
+                    "Risk score {:.0} is at or above the review threshold {:.0}",
+                    risk.score, thresholds.review
+                ))
+            }
+            Decision::Decline => CheckOutcome::fail(DeclineCode::RiskTooHigh, format!(
+                "Risk score {:.0} is at or above the decline threshold {:.0}",
+                risk.score, thresholds.decline
+            )),
+        };
+        Ok(outcome.with_risk(risk))
+    }
+}
+
+/// Places a hold against the daily and monthly budgets.
+///
+/// The limit checks above read usage without locking; this re-checks under the
+/// agent row lock so concurrent requests cannot jointly overshoot. The hold is
+/// settled or released when the transaction is logged.
+pub struct SpendReservationCheck {
+    pub ttl: chrono::Duration,
+}
+
+impl Default for SpendReservationCheck {
+    fn default() -> Self {
+        Self { ttl: hold_ttl_from_env() }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecurityCheck for SpendReservationCheck {
+    fn name(&self) -> &'static str {
+        "spend_reserved"
+    }
+    
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let Some(amount) = input.base_amount() else {
+            return Ok(CheckOutcome::skip("No amount"));
+        };
+        if input.agent.is_none() {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        }
+        
+        let request = HoldRequest {
+            agent_id: &input.ctx.agent_id,
+            nonce: &input.ctx.nonce,
+            transaction_id: None,
+            amount,
+            ttl: self.ttl,
+        };
+        match input.db.reservations().hold(&request).await? {
+            HoldOutcome::Held(hold_id) => Ok(CheckOutcome::pass_with(format!("Hold {}", hold_id))),
+            HoldOutcome::Exceeded { code, reason, limit } => Ok(CheckOutcome::fail(code, reason).with_limit(limit)),
+        }
+    }
+}
+
+/// Holds the amount in the agent's wallet, like the REST path does when it
+/// creates a pending transaction.
+///
+/// The balance check above reads the wallet without locking; this re-checks in
+/// one conditional update. The hold is captured when an approved transaction
+/// is logged or its parked request is sent, and released when it is declined.
+pub struct WalletHoldCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for WalletHoldCheck {
+    fn name(&self) -> &'static str {
+        "balance_held"
+    }
+    
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        if input.agent.is_none() {
+            return Ok(CheckOutcome::skip("Agent unknown"));
+        }
+        let amount = match input.ledger_amount().await {
+            Ok(Some(amount)) => amount,
+            Ok(None) => return Ok(CheckOutcome::skip("No amount to hold")),
+            Err(unconvertible) => return Ok(unconvertible),
+        };
+        
+        let mut conn = input.db.pool.acquire().await?;
+        if !balance::hold(&mut conn, &input.ctx.agent_id, amount.to_decimal()).await? {
+            return Ok(CheckOutcome::fail(DeclineCode::InsufficientFunds, format!(
+                "Insufficient balance to hold {}",
+                amount
+            )));
+        }
+        
+        Ok(CheckOutcome::pass_with(format!("Held {}", amount)).with_wallet_hold(amount))
+    }
+}
+
+/// Enforces a Shared Payment Token's allowance and records its use in the ledger.
+///
+/// Only tokens the agent's owner registered are honoured; the allowance comes
+/// from that registration, never from the agent's request. A payment parked
+/// for review is validated here but not charged until it is approved.
+pub struct PaymentTokenCheck;
+
+#[async_trait::async_trait]
+impl SecurityCheck for PaymentTokenCheck {
+    fn name(&self) -> &'static str {
+        "payment_token_valid"
+    }
+    
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+    
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        let ctx = input.ctx;
+        if !matches!(ctx.payment_method_type, Some(PaymentMethodType::SharedPaymentToken)) {
+            return Ok(CheckOutcome::skip("No shared payment token"));
+        }
+        
+        let Some(token) = ctx.payment_token.as_deref() else {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token missing"));
+        };
+        let fingerprint = payment_token_fingerprint(token);
+        
+        let Some(record) = input.db.get_payment_token(&fingerprint).await? else {
+            warn!("Unregistered shared payment token presented by {}", ctx.agent_id);
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token was not registered by its issuer"));
+        };
+        
+        if record.agent_id != ctx.agent_id {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token was delegated to another agent"));
+        }
+        if record.expires_at <= Utc::now() {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, format!("Shared payment token expired at {}", record.expires_at)));
+        }
+        if !record.currency.eq_ignore_ascii_case(&ctx.currency) {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, format!(
+                "Shared payment token is for {}, not {}",
+                record.currency, ctx.currency
+            )));
+        }
+        if record.merchant_id.as_ref().is_some_and(|m| *m != ctx.merchant_id) {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token is bound to another merchant"));
+        }
+        if record.checkout_session_id.is_some() && record.checkout_session_id != ctx.session_id {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token is bound to another checkout session"));
+        }
+        if record.single_use && record.use_count > 0 {
+            warn!("Reused shared payment token presented by {}", ctx.agent_id);
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token already used"));
+        }
+        
+        let Some(money) = &ctx.amount else {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Amount required to charge a shared payment token"));
+        };
+        let amount = money.to_decimal();
+        if record.amount_used + amount > record.max_amount {
+            let limit = LimitDetail::new(record.max_amount, record.amount_used, amount, Some(record.currency.clone()));
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, format!(
+                "Amount {} exceeds shared payment token allowance {} (used: {})",
+                money, in_currency(record.max_amount, &record.currency), in_currency(record.amount_used, &record.currency)
+            )).with_limit(limit));
+        }
+        
+        // Parked payments keep the token until they are approved and sent on
+        if input.under_review {
+            return Ok(CheckOutcome::pass_with("Charged once the review approves the payment"));
+        }
+        
+        // A concurrent request may have spent the token since it was read
+        if !input.db.consume_payment_token(&fingerprint, money, &ctx.nonce).await? {
+            return Ok(CheckOutcome::fail(DeclineCode::PaymentTokenInvalid, "Shared payment token already used"));
+        }
+        
+        Ok(CheckOutcome::pass())
+    }
+}
@@ -0,0 +1,282 @@
+//! Pluggable verification pipeline.
+//!
+//! Each `SecurityCheck` inspects the `SecurityContext` and reports pass, fail or
+//! skip. Checks run in registration order; the gateway's built-in set lives in
+//! `builtin`, and callers can append their own without touching `verify`.
+
+mod builtin;
+
+pub use builtin::*;
+
+use crate::categories::RestrictedItem;
+use crate::config::GatewayConfig;
+use crate::db::{Agent, Database};
+use crate::fx::{self, Conversion, RateProvider};
+use crate::ledger::LEDGER_CURRENCY;
+use crate::models::*;
+use crate::tiers::TierProfile;
+use anyhow::Result;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Result of running one check
+#[derive(Debug, Clone)]
+pub struct CheckOutcome {
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+    pub code: Option<DeclineCode>,
+    pub limit: Option<LimitDetail>,
+    pub restricted_items: Vec<RestrictedItem>,
+    pub risk: Option<RiskAssessment>,
+    pub reviewer: Option<ReviewParty>,
+    pub wallet_hold: Option<Money>,
+}
+
+impl CheckOutcome {
+    fn new(status: CheckStatus, detail: Option<String>, code: Option<DeclineCode>) -> Self {
+        Self { status, detail, code, limit: None, restricted_items: Vec::new(), risk: None, reviewer: None, wallet_hold: None }
+    }
+
+    pub fn pass() -> Self {
+        Self::new(CheckStatus::Pass, None, None)
+    }
+
+    pub fn pass_with(detail: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Pass, Some(detail.into()), None)
+    }
+
+    pub fn fail(code: DeclineCode, detail: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Fail, Some(detail.into()), Some(code))
+    }
+
+    /// Let the payment through only after the agent's owner approves it
+    pub fn review(detail: impl Into<String>) -> Self {
+        Self::review_by(ReviewParty::Owner, detail)
+    }
+
+    /// Let the payment through only after `party` approves it
+    pub fn review_by(party: ReviewParty, detail: impl Into<String>) -> Self {
+        Self { reviewer: Some(party), ..Self::new(CheckStatus::Review, Some(detail.into()), None) }
+    }
+
+    pub fn skip(detail: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Skip, Some(detail.into()), None)
+    }
+
+    /// Attach the limit that was compared against (reported on pass and fail)
+    pub fn with_limit(mut self, limit: LimitDetail) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Attach the merchant or line items that caused a review or decline
+    pub fn with_restricted_items(mut self, items: Vec<RestrictedItem>) -> Self {
+        self.restricted_items = items;
+        self
+    }
+
+    /// Attach the risk assessment the outcome was decided on
+    pub fn with_risk(mut self, risk: RiskAssessment) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Record the wallet amount the check held, for the transaction log to capture or release
+    pub fn with_wallet_hold(mut self, amount: Money) -> Self {
+        self.wallet_hold = Some(amount);
+        self
+    }
+
+    fn into_result(self, name: &str) -> CheckResult {
+        CheckResult {
+            name: name.to_string(),
+            status: self.status,
+            detail: self.detail,
+            code: self.code,
+            limit: self.limit,
+            restricted_items: self.restricted_items,
+            risk: self.risk,
+            reviewer: self.reviewer,
+            wallet_hold: self.wallet_hold,
+        }
+    }
+}
+
+/// Data shared between checks during one verification
+pub struct CheckInput<'a> {
+    pub ctx: &'a SecurityContext,
+    pub db: &'a Database,
+    /// Loaded by the agent lookup check; `None` if the agent is unknown
+    pub agent: Option<Agent>,
+    /// Loaded by the tier check; `None` if the agent's tier has no profile
+    pub tier: Option<TierProfile>,
+    /// The amount in the owner's base currency, converted before the pipeline ran
+    pub conversion: Option<Conversion>,
+    /// For checks that compare against amounts held in another currency
+    pub rates: &'a dyn RateProvider,
+    /// Set once a check has parked the payment for review, so later checks
+    /// can hold off on anything that only an approved payment should do
+    pub under_review: bool,
+}
+
+impl CheckInput<'_> {
+    /// The amount to compare against limits: converted if a conversion was made
+    pub fn base_amount(&self) -> Option<&Money> {
+        match &self.conversion {
+            Some(conversion) => Some(&conversion.base),
+            None => self.ctx.amount.as_ref(),
+        }
+    }
+
+    /// The amount in the ledger currency wallets and merchant caps use, converted if need be.
+    ///
+    /// `Ok(None)` if the request carries no amount; an amount with no rate to
+    /// the ledger currency is a `CURRENCY_UNSUPPORTED` outcome for the check to return.
+    pub async fn ledger_amount(&self) -> std::result::Result<Option<Money>, CheckOutcome> {
+        let Some(amount) = self.ctx.amount.as_ref() else {
+            return Ok(None);
+        };
+        if let Some(known) = [Some(amount), self.base_amount()].into_iter().flatten().find(|a| a.currency == LEDGER_CURRENCY) {
+            return Ok(Some(known.clone()));
+        }
+
+        match fx::to_base(self.rates, amount, LEDGER_CURRENCY).await {
+            Ok(converted) => Ok(Some(converted.base)),
+            Err(e) => {
+                warn!("Cannot convert {} to {}: {}", amount.currency, LEDGER_CURRENCY, e);
+                Err(CheckOutcome::fail(
+                    DeclineCode::CurrencyUnsupported,
+                    format!("Cannot convert {} to {}", amount.currency, LEDGER_CURRENCY),
+                ))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SecurityCheck: Send + Sync {
+    /// Stable identifier used in check reports
+    fn name(&self) -> &'static str;
+
+    /// Checks that write state (nonce store, token ledger) return true so a
+    /// run-all pipeline can skip them once the request is already declined
+    fn has_side_effects(&self) -> bool {
+        false
+    }
+
+    async fn run(&self, input: &mut CheckInput<'_>) -> Result<CheckOutcome>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PipelineMode {
+    /// Stop at the first failing check
+    #[default]
+    ShortCircuit,
+    /// Run every check so the report shows all problems at once
+    RunAll,
+}
+
+impl PipelineMode {
+    /// `GATEWAY_RUN_ALL_CHECKS=true` selects `RunAll`
+    pub fn from_env() -> Self {
+        match std::env::var("GATEWAY_RUN_ALL_CHECKS").as_deref() {
+            Ok("true") | Ok("1") => PipelineMode::RunAll,
+            _ => PipelineMode::ShortCircuit,
+        }
+    }
+}
+
+/// Ordered set of checks run by `SecurityGateway::verify`
+#[derive(Clone, Default)]
+pub struct CheckPipeline {
+    checks: Vec<Arc<dyn SecurityCheck>>,
+    mode: PipelineMode,
+}
+
+impl CheckPipeline {
+    pub fn new(mode: PipelineMode) -> Self {
+        Self { checks: Vec::new(), mode }
+    }
+
+    /// The gateway's built-in checks in their standard order, configured from the environment
+    pub fn standard() -> Self {
+        Self::from_config(&GatewayConfig::from_env())
+    }
+
+    /// The built-in checks with `config`'s limits and mode
+    pub fn from_config(config: &GatewayConfig) -> Self {
+        Self::new(config.mode)
+            .with_check(AgentExistsCheck)
+            .with_check(AgentActiveCheck)
+            .with_check(BlacklistCheck)
+            .with_check(TierCheck)
+            .with_check(NonceCheck)
+            .with_check(PerTransactionLimitCheck)
+            .with_check(MerchantMaxAmountCheck)
+            .with_check(DailyLimitCheck)
+            .with_check(MonthlyLimitCheck)
+            .with_check(BalanceCheck)
+            .with_check(MerchantAllowedCheck)
+            .with_check(VelocityCheck { limit_per_minute: config.velocity_limit_per_minute })
+            .with_check(PatternCheck)
+            .with_check(PolicyCheck)
+            .with_check(CategoryCheck)
+            .with_check(FirstPaymentReviewCheck)
+            // Scored after the rules so a decline never reaches the holds and token ledger
+            .with_check(RiskCheck { defaults: config.risk })
+            // Side-effecting checks last so they only run once everything else passed
+            .with_check(SpendReservationCheck { ttl: config.hold_ttl })
+            .with_check(WalletHoldCheck)
+            .with_check(PaymentTokenCheck)
+    }
+
+    pub fn with_check(mut self, check: impl SecurityCheck + 'static) -> Self {
+        self.register(Arc::new(check));
+        self
+    }
+
+    pub fn with_mode(mut self, mode: PipelineMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Append a check; it runs after everything registered before it
+    pub fn register(&mut self, check: Arc<dyn SecurityCheck>) {
+        self.checks.push(check);
+    }
+
+    pub fn mode(&self) -> PipelineMode {
+        self.mode
+    }
+
+    pub async fn run(
+        &self,
+        ctx: &SecurityContext,
+        db: &Database,
+        rates: &dyn RateProvider,
+        conversion: Option<Conversion>,
+    ) -> Result<CheckReport> {
+        let mut input = CheckInput { ctx, db, agent: None, tier: None, conversion, rates, under_review: false };
+        let mut report = CheckReport::default();
+
+        for check in &self.checks {
+            // Unrun checks are still reported so a decline shows exactly what was evaluated
+            if report.has_failures() && (self.mode == PipelineMode::ShortCircuit || check.has_side_effects()) {
+                report.push(CheckOutcome::skip("Not run after an earlier failure").into_result(check.name()));
+                continue;
+            }
+
+            let outcome = check.run(&mut input).await?;
+            match outcome.status {
+                CheckStatus::Pass => info!("✓ {}", check.name()),
+                CheckStatus::Fail => warn!("✗ {}: {}", check.name(), outcome.detail.as_deref().unwrap_or("failed")),
+                CheckStatus::Review => info!("? {}: {}", check.name(), outcome.detail.as_deref().unwrap_or("review")),
+                CheckStatus::Skip => info!("- {} skipped", check.name()),
+            }
+            input.under_review |= outcome.status == CheckStatus::Review;
+            report.push(outcome.into_result(check.name()));
+        }
+
+        Ok(report)
+    }
+}
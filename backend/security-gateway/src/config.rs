@@ -0,0 +1,46 @@
+//! Gateway tunables, read from the environment with built-in defaults.
+
+use crate::checks::PipelineMode;
+use crate::models::RiskThresholds;
+use crate::reservations::{hold_ttl_from_env, DEFAULT_HOLD_TTL_SECONDS};
+use chrono::Duration;
+
+pub const DEFAULT_VELOCITY_LIMIT_PER_MINUTE: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GatewayConfig {
+    /// Transactions an agent may make per minute
+    pub velocity_limit_per_minute: i64,
+    /// Risk scores that send a payment to review or decline it
+    pub risk: RiskThresholds,
+    /// How long a spend hold lasts if the transaction is never logged
+    pub hold_ttl: Duration,
+    pub mode: PipelineMode,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            velocity_limit_per_minute: DEFAULT_VELOCITY_LIMIT_PER_MINUTE,
+            risk: RiskThresholds::default(),
+            hold_ttl: Duration::seconds(DEFAULT_HOLD_TTL_SECONDS),
+            mode: PipelineMode::default(),
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Defaults overridden by `VELOCITY_LIMIT_PER_MINUTE`, `RISK_REVIEW_THRESHOLD`,
+    /// `RISK_DECLINE_THRESHOLD`, `SPEND_HOLD_TTL_SECONDS` and `GATEWAY_RUN_ALL_CHECKS`
+    pub fn from_env() -> Self {
+        Self {
+            velocity_limit_per_minute: std::env::var("VELOCITY_LIMIT_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_VELOCITY_LIMIT_PER_MINUTE),
+            risk: RiskThresholds::from_env(),
+            hold_ttl: hold_ttl_from_env(),
+            mode: PipelineMode::from_env(),
+        }
+    }
+}
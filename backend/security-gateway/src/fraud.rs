@@ -0,0 +1,279 @@
+//! Fraud pattern detection.
+//!
+//! Detectors look at an agent's recent transactions (plus the one being
+//! verified) for card testing, merchant hopping, structuring just under the
+//! per-transaction limit and repeated round amounts. Findings are stored in
+//! `fraud_patterns` with their evidence; unresolved high-severity findings
+//! fail the `pattern_normal` check until an admin resolves them.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::collections::BTreeSet;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Amounts at or below this (base currency) count as probes in card testing
+const CARD_TEST_MAX_AMOUNT: i64 = 2;
+const CARD_TEST_WINDOW_MINUTES: i64 = 10;
+const MERCHANT_HOPPING_WINDOW_MINUTES: i64 = 60;
+/// Amounts within this fraction below the per-transaction limit count as structuring
+const STRUCTURING_MARGIN_PERCENT: i64 = 5;
+const ROUND_AMOUNT_UNIT: i64 = 100;
+/// How far back detectors look; callers should load at least this much history
+pub const DETECTION_WINDOW_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternType {
+    CardTesting,
+    MerchantHopping,
+    Structuring,
+    RoundAmounts,
+}
+
+impl PatternType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternType::CardTesting => "card_testing",
+            PatternType::MerchantHopping => "merchant_hopping",
+            PatternType::Structuring => "structuring",
+            PatternType::RoundAmounts => "round_amounts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// A detected pattern and the evidence for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub pattern_type: PatternType,
+    pub severity: Severity,
+    pub details: serde_json::Value,
+}
+
+/// One of the agent's transactions, amount in the owner's base currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityRecord {
+    pub merchant_id: String,
+    pub amount: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Run every detector over `activity`, which should include the request being verified
+pub fn detect(activity: &[ActivityRecord], per_tx_limit: Option<Decimal>, now: DateTime<Utc>) -> Vec<Finding> {
+    [
+        card_testing(activity, now),
+        merchant_hopping(activity, now),
+        per_tx_limit.and_then(|limit| structuring(activity, limit, now)),
+        round_amounts(activity, now),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn within(activity: &[ActivityRecord], now: DateTime<Utc>, window: Duration) -> impl Iterator<Item = &ActivityRecord> {
+    activity.iter().filter(move |a| a.created_at > now - window && a.created_at <= now)
+}
+
+/// A burst of tiny payments, typical of checking whether stolen credentials work
+fn card_testing(activity: &[ActivityRecord], now: DateTime<Utc>) -> Option<Finding> {
+    let probes: Vec<Decimal> = within(activity, now, Duration::minutes(CARD_TEST_WINDOW_MINUTES))
+        .filter_map(|a| a.amount)
+        .filter(|amount| *amount > Decimal::ZERO && *amount <= Decimal::from(CARD_TEST_MAX_AMOUNT))
+        .collect();
+
+    let severity = match probes.len() {
+        n if n >= 10 => Severity::Critical,
+        n if n >= 5 => Severity::High,
+        _ => return None,
+    };
+    Some(Finding {
+        pattern_type: PatternType::CardTesting,
+        severity,
+        details: json!({
+            "window_minutes": CARD_TEST_WINDOW_MINUTES,
+            "max_amount": CARD_TEST_MAX_AMOUNT,
+            "count": probes.len(),
+            "amounts": probes,
+        }),
+    })
+}
+
+/// Payments spread across many merchants in a short time
+fn merchant_hopping(activity: &[ActivityRecord], now: DateTime<Utc>) -> Option<Finding> {
+    let merchants: BTreeSet<&str> = within(activity, now, Duration::minutes(MERCHANT_HOPPING_WINDOW_MINUTES))
+        .map(|a| a.merchant_id.as_str())
+        .collect();
+
+    let severity = match merchants.len() {
+        n if n >= 8 => Severity::High,
+        n if n >= 5 => Severity::Medium,
+        _ => return None,
+    };
+    Some(Finding {
+        pattern_type: PatternType::MerchantHopping,
+        severity,
+        details: json!({
+            "window_minutes": MERCHANT_HOPPING_WINDOW_MINUTES,
+            "merchant_count": merchants.len(),
+            "merchants": merchants,
+        }),
+    })
+}
+
+/// Repeated amounts just under the per-transaction limit, splitting a payment to slip past it
+fn structuring(activity: &[ActivityRecord], per_tx_limit: Decimal, now: DateTime<Utc>) -> Option<Finding> {
+    let floor = per_tx_limit * Decimal::from(100 - STRUCTURING_MARGIN_PERCENT) / Decimal::from(100);
+    let near_limit: Vec<Decimal> = within(activity, now, Duration::hours(DETECTION_WINDOW_HOURS))
+        .filter_map(|a| a.amount)
+        .filter(|amount| *amount >= floor && *amount <= per_tx_limit)
+        .collect();
+
+    let severity = match near_limit.len() {
+        n if n >= 3 => Severity::High,
+        2 => Severity::Medium,
+        _ => return None,
+    };
+    Some(Finding {
+        pattern_type: PatternType::Structuring,
+        severity,
+        details: json!({
+            "window_hours": DETECTION_WINDOW_HOURS,
+            "per_tx_limit": per_tx_limit,
+            "margin_percent": STRUCTURING_MARGIN_PERCENT,
+            "amounts": near_limit,
+        }),
+    })
+}
+
+/// Most recent payments are exact round amounts, unusual for real purchases
+fn round_amounts(activity: &[ActivityRecord], now: DateTime<Utc>) -> Option<Finding> {
+    let amounts: Vec<Decimal> = within(activity, now, Duration::hours(DETECTION_WINDOW_HOURS))
+        .filter_map(|a| a.amount)
+        .collect();
+    if amounts.len() < 5 {
+        return None;
+    }
+
+    let unit = Decimal::from(ROUND_AMOUNT_UNIT);
+    let round: Vec<Decimal> = amounts.iter().copied().filter(|a| *a >= unit && (*a % unit).is_zero()).collect();
+    // At least 80% of payments
+    if round.len() * 5 < amounts.len() * 4 {
+        return None;
+    }
+
+    let severity = if round.len() >= 10 { Severity::Medium } else { Severity::Low };
+    Some(Finding {
+        pattern_type: PatternType::RoundAmounts,
+        severity,
+        details: json!({
+            "window_hours": DETECTION_WINDOW_HOURS,
+            "round_count": round.len(),
+            "total_count": amounts.len(),
+            "amounts": round,
+        }),
+    })
+}
+
+/// Persists findings in `fraud_patterns`
+#[derive(Clone)]
+pub struct FraudStore {
+    pool: PgPool,
+}
+
+impl FraudStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The agent's transactions since `since`, including declined attempts
+    pub async fn recent_activity(&self, agent_id: &str, since: DateTime<Utc>) -> Result<Vec<ActivityRecord>> {
+        // transactions.created_at is a UTC TIMESTAMP without zone
+        let rows = sqlx::query(
+            "SELECT merchant_id::text AS merchant_id, COALESCE(base_amount, amount) AS amount, created_at
+             FROM transactions
+             WHERE agent_id = $1 AND created_at >= $2
+             ORDER BY created_at"
+        )
+        .bind(agent_id)
+        .bind(since.naive_utc())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ActivityRecord {
+                merchant_id: row.get("merchant_id"),
+                amount: row.get("amount"),
+                created_at: row.get::<chrono::NaiveDateTime, _>("created_at").and_utc(),
+            })
+            .collect())
+    }
+
+    /// Store findings, skipping any pattern already open for the agent at the same or higher severity
+    pub async fn record(&self, agent_id: &str, findings: &[Finding]) -> Result<Vec<Uuid>> {
+        let mut recorded = Vec::new();
+        for finding in findings {
+            let id: Option<Uuid> = sqlx::query_scalar(
+                "INSERT INTO fraud_patterns (agent_id, pattern_type, severity, details)
+                 SELECT $1, $2, $3, $4
+                 WHERE NOT EXISTS (
+                     SELECT 1 FROM fraud_patterns
+                     WHERE agent_id = $1 AND pattern_type = $2 AND NOT resolved
+                       AND array_position(ARRAY['low', 'medium', 'high', 'critical'], severity)
+                           >= array_position(ARRAY['low', 'medium', 'high', 'critical'], $3)
+                 )
+                 RETURNING id"
+            )
+            .bind(agent_id)
+            .bind(finding.pattern_type.as_str())
+            .bind(finding.severity.as_str())
+            .bind(&finding.details)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            if let Some(id) = id {
+                warn!("🚩 {} {} pattern for agent {}", finding.severity.as_str(), finding.pattern_type.as_str(), agent_id);
+                recorded.push(id);
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// Pattern types with unresolved high or critical findings for the agent
+    pub async fn blocking_patterns(&self, agent_id: &str) -> Result<Vec<String>> {
+        let patterns = sqlx::query_scalar(
+            "SELECT DISTINCT pattern_type FROM fraud_patterns
+             WHERE agent_id = $1 AND NOT resolved AND severity IN ('high', 'critical')
+             ORDER BY pattern_type"
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(patterns)
+    }
+}
@@ -0,0 +1,184 @@
+//! Currency conversion.
+//!
+//! Spending limits are set in the agent owner's base currency. A transaction in
+//! any other currency is converted with a rate from a `RateProvider` before the
+//! limit checks run, and the rate that was used is stored with the transaction.
+
+use crate::models::Money;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Base currency for owners who have not chosen one
+pub const DEFAULT_BASE_CURRENCY: &str = "USD";
+
+/// Decimal places kept on a derived cross rate
+const RATE_SCALE: u32 = 10;
+
+/// The rate used for one conversion, as stored on the transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateSnapshot {
+    pub from: String,
+    pub to: String,
+    /// Units of `to` per unit of `from`
+    pub rate: Decimal,
+    pub as_of: DateTime<Utc>,
+    pub source: String,
+}
+
+impl RateSnapshot {
+    pub fn identity(currency: &str) -> Self {
+        Self {
+            from: currency.to_string(),
+            to: currency.to_string(),
+            rate: Decimal::ONE,
+            as_of: Utc::now(),
+            source: "identity".to_string(),
+        }
+    }
+
+    /// Convert `amount`, rounding half away from zero to the target currency's minor unit
+    pub fn convert(&self, amount: &Money) -> Result<Money> {
+        if amount.currency != self.from {
+            bail!("Rate is for {}, not {}", self.from, amount.currency);
+        }
+        Ok(Money::from_stored(amount.to_decimal() * self.rate, &self.to))
+    }
+}
+
+/// A transaction amount together with its value in the owner's base currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversion {
+    pub original: Money,
+    pub base: Money,
+    pub rate: RateSnapshot,
+}
+
+impl Conversion {
+    pub fn is_identity(&self) -> bool {
+        self.original.currency == self.base.currency
+    }
+}
+
+#[async_trait::async_trait]
+pub trait RateProvider: Send + Sync {
+    /// Rate to convert one unit of `from` into `to`; errors if either currency is unsupported
+    async fn rate(&self, from: &str, to: &str) -> Result<RateSnapshot>;
+}
+
+/// Convert `amount` into `base_currency`
+pub async fn to_base(provider: &dyn RateProvider, amount: &Money, base_currency: &str) -> Result<Conversion> {
+    let base_currency = base_currency.trim().to_ascii_uppercase();
+    let rate = if amount.currency == base_currency {
+        RateSnapshot::identity(&base_currency)
+    } else {
+        provider.rate(&amount.currency, &base_currency).await?
+    };
+    let base = rate.convert(amount)?;
+    Ok(Conversion { original: amount.clone(), base, rate })
+}
+
+/// A fixed rate table, normally loaded from the JSON file named by `FX_RATES_FILE`:
+///
+/// ```json
+/// {"base": "USD", "as_of": "2026-10-01T00:00:00Z", "rates": {"EUR": "0.92", "JPY": "149.5"}}
+/// ```
+///
+/// Each rate is units of that currency per unit of `base`; other pairs are
+/// crossed through `base`.
+pub struct StaticRateProvider {
+    base: String,
+    as_of: DateTime<Utc>,
+    rates: HashMap<String, Decimal>,
+    source: String,
+}
+
+impl StaticRateProvider {
+    /// A table with no rates; only same-currency transactions can be converted
+    pub fn empty() -> Self {
+        Self {
+            base: DEFAULT_BASE_CURRENCY.to_string(),
+            as_of: Utc::now(),
+            rates: HashMap::new(),
+            source: "none".to_string(),
+        }
+    }
+
+    pub fn from_json(raw: &str, source: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct RateFile {
+            base: String,
+            as_of: DateTime<Utc>,
+            rates: HashMap<String, serde_json::Value>,
+        }
+
+        let file: RateFile = serde_json::from_str(raw)?;
+        let mut rates = HashMap::new();
+        for (currency, value) in file.rates {
+            // Parse the literal text so rates never pass through f64
+            let text = match &value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                _ => bail!("Rate for {} must be a number or string", currency),
+            };
+            let rate: Decimal = text.trim().parse().map_err(|_| anyhow!("Invalid rate for {}: {}", currency, text))?;
+            if rate <= Decimal::ZERO {
+                bail!("Rate for {} must be positive", currency);
+            }
+            rates.insert(Money::zero(&currency)?.currency, rate);
+        }
+
+        Ok(Self {
+            base: Money::zero(&file.base)?.currency,
+            as_of: file.as_of,
+            rates,
+            source: source.to_string(),
+        })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path).with_context(|| format!("Reading FX rates from {}", path))?;
+        Self::from_json(&raw, path)
+    }
+
+    /// Load `FX_RATES_FILE`, or an empty table if it is not set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("FX_RATES_FILE") {
+            Ok(path) => Self::from_file(&path),
+            Err(_) => Ok(Self::empty()),
+        }
+    }
+
+    fn per_base(&self, currency: &str) -> Option<Decimal> {
+        if currency == self.base {
+            Some(Decimal::ONE)
+        } else {
+            self.rates.get(currency).copied()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateProvider for StaticRateProvider {
+    async fn rate(&self, from: &str, to: &str) -> Result<RateSnapshot> {
+        let from = from.to_ascii_uppercase();
+        let to = to.to_ascii_uppercase();
+        if from == to {
+            return Ok(RateSnapshot::identity(&from));
+        }
+
+        let (Some(from_rate), Some(to_rate)) = (self.per_base(&from), self.per_base(&to)) else {
+            bail!("No exchange rate from {} to {}", from, to);
+        };
+
+        Ok(RateSnapshot {
+            rate: (to_rate / from_rate).round_dp(RATE_SCALE),
+            from,
+            to,
+            as_of: self.as_of,
+            source: self.source.clone(),
+        })
+    }
+}
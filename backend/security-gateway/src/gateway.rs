@@ -1,17 +1,52 @@
+use crate::checks::{AgentActiveCheck, AgentExistsCheck, BlacklistCheck, CheckPipeline, PipelineMode};
+use crate::config::GatewayConfig;
 use crate::models::*;
 use crate::db::Database;
+use crate::fx::{self, RateProvider, StaticRateProvider};
 use anyhow::Result;
+use std::sync::Arc;
 use tracing::{info, warn};
-use rust_decimal::prelude::ToPrimitive; 
 
+/// Verifies agent payments: agent existence and status, balance, merchant
+/// blocks, per-transaction, daily and monthly limits, velocity, nonce replay,
+/// fraud patterns and risk score.
 pub struct SecurityGateway {
     db: Database,
+    pipeline: CheckPipeline,
+    rates: Arc<dyn RateProvider>,
+    config: GatewayConfig,
 }
 
 impl SecurityGateway {
     pub async fn new() -> Result<Self> {
+        Self::with_config(GatewayConfig::from_env()).await
+    }
+    
+    /// Build a gateway running the standard checks with `config`'s thresholds
+    pub async fn with_config(config: GatewayConfig) -> Result<Self> {
+        let pipeline = CheckPipeline::from_config(&config);
+        Self::build(pipeline, config).await
+    }
+    
+    /// Build a gateway that runs a custom set of checks
+    pub async fn with_pipeline(pipeline: CheckPipeline) -> Result<Self> {
+        Self::build(pipeline, GatewayConfig::from_env()).await
+    }
+    
+    async fn build(pipeline: CheckPipeline, config: GatewayConfig) -> Result<Self> {
         let db = Database::connect().await?;
-        Ok(Self { db })
+        let rates = Arc::new(StaticRateProvider::from_env()?);
+        Ok(Self { db, pipeline, rates, config })
+    }
+    
+    pub fn config(&self) -> &GatewayConfig {
+        &self.config
+    }
+    
+    /// Convert foreign-currency amounts with `rates` instead of the `FX_RATES_FILE` table
+    pub fn with_rate_provider(mut self, rates: Arc<dyn RateProvider>) -> Self {
+        self.rates = rates;
+        self
     }
     
     pub async fn verify(&self, ctx: &SecurityContext) -> Result<VerificationResult> {
@@ -21,94 +56,116 @@ impl SecurityGateway {
         info!("Amount: {:?} {}", ctx.amount, ctx.currency);
         info!("Merchant: {}", ctx.merchant_id);
         
-        let mut checks = SecurityChecks::all_failed();
-        
-        // 1. Check if agent exists
-        let agent = match self.db.get_agent(&ctx.agent_id).await {
-            Ok(agent) => {
-                checks.agent_exists = true;
-                agent
-            }
-            Err(e) => {
-                warn!("Agent not found: {}", e);
-                return Ok(VerificationResult::declined(format!("Agent not found: {}", ctx.agent_id)));
-            }
-        };
-        
-        // 2. Check agent status
-        checks.agent_active = agent.status == "active";
-        if !checks.agent_active {
-            return Ok(VerificationResult::declined(format!("Agent is {}", agent.status)));
-        }
-        
-        // 3. Check nonce (replay attack prevention)
-        checks.nonce_fresh = self.db.check_and_store_nonce(&ctx.agent_id, &ctx.nonce).await?;
-        if !checks.nonce_fresh {
-            return Ok(VerificationResult::declined("Nonce already used - replay attack detected".to_string()));
+        // A negative amount would pass every limit and credit the budgets it is held against
+        if let Some(amount) = ctx.amount.as_ref().filter(|amount| !amount.is_positive()) {
+            warn!("Rejected non-positive amount {} from {}", amount, ctx.agent_id);
+            return Ok(VerificationResult::declined(
+                DeclineCode::InvalidAmount,
+                format!("Amount must be positive, got {}", amount),
+            ));
         }
         
-        // 4. Check spending limits
-        if let Some(amount) = ctx.amount {
-            // Per-transaction limit
-            let per_tx_limit = agent.spending_limit_per_tx.to_f64().unwrap_or(0.0);
-            checks.within_per_tx_limit = amount <= per_tx_limit;
-            if !checks.within_per_tx_limit {
-                return Ok(VerificationResult::declined(
-                    format!("Amount ${:.2} exceeds per-transaction limit ${:.2}", amount, per_tx_limit)
-                ));
-            }
-            
-            // Daily limit
-            let daily_spent = self.db.get_daily_spending(&ctx.agent_id).await?;
-            let daily_limit = agent.spending_limit_daily.to_f64().unwrap_or(0.0);
-            checks.within_daily_limit = (daily_spent + amount) <= daily_limit;
-            if !checks.within_daily_limit {
-                return Ok(VerificationResult::declined(
-                    format!("Would exceed daily limit ${:.2} (spent: ${:.2})", daily_limit, daily_spent)
-                ));
+        // Limits are in the owner's base currency; unknown agents are left to the pipeline
+        let mut conversion = None;
+        if let Some(amount) = &ctx.amount {
+            if let Some(base_currency) = self.db.get_base_currency(&ctx.agent_id).await? {
+                match fx::to_base(self.rates.as_ref(), amount, &base_currency).await {
+                    Ok(converted) => {
+                        if !converted.is_identity() {
+                            info!("💱 {} = {} at {} ({})", converted.original, converted.base, converted.rate.rate, converted.rate.source);
+                        }
+                        conversion = Some(converted);
+                    }
+                    Err(e) => {
+                        warn!("Cannot convert {} to {}: {}", amount.currency, base_currency, e);
+                        return Ok(VerificationResult::declined(
+                            DeclineCode::CurrencyUnsupported,
+                            format!("Cannot convert {} to {}", amount.currency, base_currency),
+                        ));
+                    }
+                }
             }
-            
-            // Monthly limit (placeholder)
-            checks.within_monthly_limit = true;
-        } else {
-            // If amount not known yet (e.g., ACP create-checkout), skip limit checks
-            checks.within_per_tx_limit = true;
-            checks.within_daily_limit = true;
-            checks.within_monthly_limit = true;
         }
         
-        // 5. Check merchant (placeholder - no blacklist yet)
-        checks.merchant_allowed = true;
+        let checks = self.pipeline.run(ctx, &self.db, self.rates.as_ref(), conversion.clone()).await?;
         
-        // 6. Velocity check
-        let recent_tx_count = self.db.count_recent_transactions(&ctx.agent_id, 60).await?;
-        checks.velocity_check_passed = recent_tx_count < 10; // Max 10 tx per minute
-        if !checks.velocity_check_passed {
-            return Ok(VerificationResult::declined(
-                format!("Too many transactions: {} in last minute", recent_tx_count)
-            ));
+        if let Some(failure) = checks.first_failure() {
+            warn!("Declined by {} ({})", failure.name, failure.code.unwrap_or(DeclineCode::CheckFailed));
+            return Ok(VerificationResult { conversion, ..VerificationResult::declined_by(checks) });
         }
         
-        // 7. Pattern check (placeholder)
-        checks.pattern_normal = true;
-        
-        // 8. Calculate risk score
-        let risk_score = RiskScorer::calculate_risk_score(ctx);
+        // Risk is scored by the pipeline's risk check, ahead of the holds and token ledger
+        let review = checks.first_review();
+        let decision = if review.is_some() { Decision::Review } else { Decision::Approve };
+        let reason = review.and_then(|r| r.detail.clone());
+        let reviewer = checks.reviewer();
+        let risk = checks.risk().cloned().unwrap_or(RiskAssessment { score: 0.0, factors: Vec::new() });
+        let restricted_items = checks.restricted_items();
         
-        // All checks passed
         info!("✓ All security checks passed");
-        info!("Risk score: {:.1}/100", risk_score);
+        info!("Risk score: {:.1}/100 → {:?}", risk.score, decision);
         
         Ok(VerificationResult {
-            approved: true,
-            reason: None,
+            approved: decision == Decision::Approve,
+            decision,
+            reason,
             checks,
-            risk_score,
+            risk_score: risk.score,
+            risk_factors: risk.factors,
+            decline_code: None,
+            limit: None,
+            conversion,
+            restricted_items,
+            reviewer,
         })
     }
     
+    /// Check that an approved payment's agent still exists, is active and is not blacklisted.
+    ///
+    /// A parked payment was verified when it arrived; its agent may have been
+    /// suspended or blacklisted while it waited to be sent.
+    pub async fn recheck_agent(&self, ctx: &SecurityContext) -> Result<CheckReport> {
+        let pipeline = CheckPipeline::new(PipelineMode::ShortCircuit)
+            .with_check(AgentExistsCheck)
+            .with_check(AgentActiveCheck)
+            .with_check(BlacklistCheck);
+        pipeline.run(ctx, &self.db, self.rates.as_ref(), None).await
+    }
+    
+    /// Charge the shared payment token of a parked payment once it is approved.
+    ///
+    /// Verification validated the token but left it unspent. Charging again for
+    /// the same nonce is a no-op, so a payment whose forwarding failed can be retried.
+    pub async fn charge_approved_payment(&self, ctx: &SecurityContext) -> Result<bool> {
+        if !matches!(ctx.payment_method_type, Some(PaymentMethodType::SharedPaymentToken)) {
+            return Ok(true);
+        }
+        let (Some(token), Some(amount)) = (ctx.payment_token.as_deref(), ctx.amount.as_ref()) else {
+            return Ok(false);
+        };
+        let fingerprint = payment_token_fingerprint(token);
+        
+        let Some(record) = self.db.get_payment_token(&fingerprint).await? else {
+            return Ok(false);
+        };
+        if record.last_nonce.as_deref() == Some(ctx.nonce.as_str()) {
+            return Ok(true);
+        }
+        if record.expires_at <= chrono::Utc::now() {
+            warn!("Shared payment token for {} expired while under review", ctx.agent_id);
+            return Ok(false);
+        }
+        self.db.consume_payment_token(&fingerprint, amount, &ctx.nonce).await
+    }
+    
     pub async fn log_transaction(&self, ctx: &SecurityContext, verification: &VerificationResult) -> Result<()> {
         self.db.log_transaction(ctx, verification).await?;
         Ok(())
     }
+    
+    /// Record the merchant's confirmation for an approved payment once it is observed
+    pub async fn record_payment_confirmation(&self, ctx: &SecurityContext, confirmation: &serde_json::Value) -> Result<()> {
+        info!("✅ Payment confirmed for agent {} (nonce {})", ctx.agent_id, ctx.nonce);
+        self.db.record_payment_confirmation(ctx, confirmation).await
+    }
 }
\ No newline at end of file
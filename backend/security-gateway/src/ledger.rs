@@ -0,0 +1,405 @@
+//! Append-only double-entry ledger.
+//!
+//! Every movement of money posts one journal whose debits equal its credits.
+//! `agents.remaining_balance` and `merchants.total_revenue` are kept alongside
+//! as derived balances; `reconcile` compares them with the ledger and records
+//! any drift in `ledger_drift`.
+
+use crate::models::Money;
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Currency every account is kept in until multi-currency wallets exist
+pub const LEDGER_CURRENCY: &str = "USD";
+
+/// A stored wallet, revenue or ledger amount, in the ledger currency
+pub fn money(amount: Decimal) -> Money {
+    Money::from_stored(amount, LEDGER_CURRENCY)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountType {
+    /// Funds an agent may spend; owed to the agent's owner
+    AgentWallet,
+    /// Owed to a merchant for completed purchases
+    MerchantReceivable,
+    /// Platform's cut of completed purchases
+    PlatformFees,
+    /// Refunds a merchant has issued (contra-revenue)
+    Refunds,
+    /// Money entering or leaving the platform (top-ups, withdrawals)
+    ExternalFunding,
+}
+
+impl AccountType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::AgentWallet => "agent_wallet",
+            AccountType::MerchantReceivable => "merchant_receivable",
+            AccountType::PlatformFees => "platform_fees",
+            AccountType::Refunds => "refunds",
+            AccountType::ExternalFunding => "external_funding",
+        }
+    }
+
+    /// Liability and revenue accounts grow with credits; the rest with debits
+    fn credit_normal(&self) -> bool {
+        matches!(self, AccountType::AgentWallet | AccountType::MerchantReceivable | AccountType::PlatformFees)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Debit,
+    Credit,
+}
+
+impl Direction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Debit => "debit",
+            Direction::Credit => "credit",
+        }
+    }
+}
+
+/// One side of a journal
+#[derive(Debug, Clone)]
+pub struct Posting {
+    pub account: AccountType,
+    /// Agent id or merchant id; `None` for platform-wide accounts
+    pub owner: Option<String>,
+    pub direction: Direction,
+    pub amount: Decimal,
+}
+
+impl Posting {
+    fn debit(account: AccountType, owner: Option<String>, amount: Decimal) -> Self {
+        Self { account, owner, direction: Direction::Debit, amount }
+    }
+
+    fn credit(account: AccountType, owner: Option<String>, amount: Decimal) -> Self {
+        Self { account, owner, direction: Direction::Credit, amount }
+    }
+}
+
+/// A balanced set of postings recorded together
+#[derive(Debug, Clone)]
+pub struct Journal {
+    /// `topup`, `withdrawal`, `completion`, `refund` or `opening_balance`
+    pub kind: &'static str,
+    /// The transaction, block request or wallet operation that caused it
+    pub reference_id: Option<Uuid>,
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+impl Journal {
+    /// Owner funds an agent wallet
+    pub fn topup(agent_id: &str, amount: Decimal, reference_id: Option<Uuid>) -> Self {
+        Self {
+            kind: "topup",
+            reference_id,
+            description: format!("Top-up of {} to {}", amount, agent_id),
+            postings: vec![
+                Posting::debit(AccountType::ExternalFunding, None, amount),
+                Posting::credit(AccountType::AgentWallet, Some(agent_id.to_string()), amount),
+            ],
+        }
+    }
+
+    /// Owner takes funds back out of an agent wallet
+    pub fn withdrawal(agent_id: &str, amount: Decimal, reference_id: Option<Uuid>) -> Self {
+        Self {
+            kind: "withdrawal",
+            reference_id,
+            description: format!("Withdrawal of {} from {}", amount, agent_id),
+            postings: vec![
+                Posting::debit(AccountType::AgentWallet, Some(agent_id.to_string()), amount),
+                Posting::credit(AccountType::ExternalFunding, None, amount),
+            ],
+        }
+    }
+
+    /// A purchase completes: the agent pays the merchant, less the platform fee
+    pub fn completion(agent_id: &str, merchant_id: Uuid, amount: Decimal, fee: Decimal, transaction_id: Uuid) -> Self {
+        let mut postings = vec![
+            Posting::debit(AccountType::AgentWallet, Some(agent_id.to_string()), amount),
+            Posting::credit(AccountType::MerchantReceivable, Some(merchant_id.to_string()), amount - fee),
+        ];
+        if fee > Decimal::ZERO {
+            postings.push(Posting::credit(AccountType::PlatformFees, None, fee));
+        }
+
+        Self {
+            kind: "completion",
+            reference_id: Some(transaction_id),
+            description: format!("Purchase of {} by {}", amount, agent_id),
+            postings,
+        }
+    }
+
+    /// A merchant refunds an agent
+    pub fn refund(agent_id: &str, merchant_id: Uuid, amount: Decimal, transaction_id: Uuid) -> Self {
+        Self {
+            kind: "refund",
+            reference_id: Some(transaction_id),
+            description: format!("Refund of {} to {}", amount, agent_id),
+            postings: vec![
+                Posting::debit(AccountType::Refunds, Some(merchant_id.to_string()), amount),
+                Posting::credit(AccountType::AgentWallet, Some(agent_id.to_string()), amount),
+            ],
+        }
+    }
+
+    /// Debits must equal credits and no posting may be negative
+    pub fn validate(&self) -> Result<()> {
+        if !self.is_balanced() {
+            bail!("Unbalanced {} journal: {:?}", self.kind, self.postings);
+        }
+        if self.postings.iter().any(|p| p.amount < Decimal::ZERO) {
+            bail!("Negative posting in {} journal", self.kind);
+        }
+        Ok(())
+    }
+
+    fn is_balanced(&self) -> bool {
+        let total = |direction: Direction| -> Decimal {
+            self.postings.iter().filter(|p| p.direction == direction).map(|p| p.amount).sum()
+        };
+        total(Direction::Debit) == total(Direction::Credit)
+    }
+}
+
+/// Platform fee in basis points from `PLATFORM_FEE_BPS` (default none)
+pub fn platform_fee(amount: Decimal) -> Decimal {
+    let bps: u32 = std::env::var("PLATFORM_FEE_BPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    (amount * Decimal::from(bps) / Decimal::from(10_000)).round_dp(2)
+}
+
+/// Record a journal inside the caller's database transaction
+pub async fn post(conn: &mut PgConnection, journal: &Journal) -> Result<Uuid> {
+    journal.validate()?;
+
+    let journal_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO ledger_journals (kind, reference_id, description)
+         VALUES ($1, $2, $3)
+         RETURNING id"
+    )
+    .bind(journal.kind)
+    .bind(journal.reference_id)
+    .bind(&journal.description)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for posting in &journal.postings {
+        let account_id = account_id(conn, posting.account, posting.owner.as_deref()).await?;
+        sqlx::query(
+            "INSERT INTO ledger_entries (journal_id, account_id, direction, amount)
+             VALUES ($1, $2, $3, $4)"
+        )
+        .bind(journal_id)
+        .bind(account_id)
+        .bind(posting.direction.as_str())
+        .bind(posting.amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(journal_id)
+}
+
+/// Credit the merchant's revenue, less the platform fee, and post the completion
+/// journal; call inside the database transaction that marks the purchase completed
+/// and debits the agent
+pub async fn record_completion(
+    conn: &mut PgConnection,
+    agent_id: &str,
+    merchant_id: Uuid,
+    amount: Decimal,
+    transaction_id: Uuid,
+) -> Result<Uuid> {
+    let fee = platform_fee(amount);
+    sqlx::query(
+        "UPDATE merchants 
+         SET total_revenue = COALESCE(total_revenue, 0) + $1
+         WHERE id = $2"
+    )
+    .bind(amount - fee)
+    .bind(merchant_id)
+    .execute(&mut *conn)
+    .await?;
+
+    post(conn, &Journal::completion(agent_id, merchant_id, amount, fee, transaction_id)).await
+}
+
+async fn account_id(conn: &mut PgConnection, account: AccountType, owner: Option<&str>) -> Result<Uuid> {
+    // The no-op update makes RETURNING yield the id of an existing account
+    let id = sqlx::query_scalar(
+        "INSERT INTO ledger_accounts (account_type, owner_id, currency)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (account_type, owner_key, currency) DO UPDATE SET account_type = EXCLUDED.account_type
+         RETURNING id"
+    )
+    .bind(account.as_str())
+    .bind(owner)
+    .bind(LEDGER_CURRENCY)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// Balance of one account in its normal direction
+pub async fn balance(conn: &mut PgConnection, account: AccountType, owner: Option<&str>) -> Result<Decimal> {
+    let (debits, credits): (Decimal, Decimal) = sqlx::query_as(
+        "SELECT
+            COALESCE(SUM(e.amount) FILTER (WHERE e.direction = 'debit'), 0),
+            COALESCE(SUM(e.amount) FILTER (WHERE e.direction = 'credit'), 0)
+         FROM ledger_entries e
+         JOIN ledger_accounts la ON la.id = e.account_id
+         WHERE la.account_type = $1 AND la.owner_key = COALESCE($2, '') AND la.currency = $3"
+    )
+    .bind(account.as_str())
+    .bind(owner)
+    .bind(LEDGER_CURRENCY)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(if account.credit_normal() { credits - debits } else { debits - credits })
+}
+
+/// A derived balance that no longer matches the ledger
+#[derive(Debug, Clone, Serialize)]
+pub struct Drift {
+    /// `agent_balance`, `merchant_revenue` or `journal`
+    pub subject_type: String,
+    pub subject_id: String,
+    /// What the ledger says
+    pub expected: Decimal,
+    /// What the derived column says
+    pub actual: Decimal,
+}
+
+/// Compare derived balances with the ledger and record every mismatch
+pub async fn reconcile(pool: &PgPool) -> Result<Vec<Drift>> {
+    let mut drift = Vec::new();
+
+    let agents = sqlx::query(
+        "SELECT a.id, COALESCE(a.remaining_balance, 0) AS actual,
+                COALESCE(SUM(CASE e.direction WHEN 'credit' THEN e.amount ELSE -e.amount END), 0) AS expected
+         FROM agents a
+         LEFT JOIN ledger_accounts la
+           ON la.account_type = 'agent_wallet' AND la.owner_id = a.id AND la.currency = $1
+         LEFT JOIN ledger_entries e ON e.account_id = la.id
+         GROUP BY a.id, a.remaining_balance
+         HAVING COALESCE(a.remaining_balance, 0)
+             <> COALESCE(SUM(CASE e.direction WHEN 'credit' THEN e.amount ELSE -e.amount END), 0)"
+    )
+    .bind(LEDGER_CURRENCY)
+    .fetch_all(pool)
+    .await?;
+    drift.extend(agents.iter().map(|row| Drift {
+        subject_type: "agent_balance".to_string(),
+        subject_id: row.get("id"),
+        expected: row.get("expected"),
+        actual: row.get("actual"),
+    }));
+
+    // Net revenue: receivable credits less refunds issued
+    let merchants = sqlx::query(
+        "SELECT m.id::text AS id, COALESCE(m.total_revenue, 0) AS actual,
+                COALESCE(SUM(CASE
+                    WHEN la.account_type = 'merchant_receivable' AND e.direction = 'credit' THEN e.amount
+                    WHEN la.account_type = 'merchant_receivable' THEN -e.amount
+                    WHEN e.direction = 'debit' THEN -e.amount
+                    ELSE e.amount
+                END), 0) AS expected
+         FROM merchants m
+         LEFT JOIN ledger_accounts la
+           ON la.account_type IN ('merchant_receivable', 'refunds') AND la.owner_id = m.id::text AND la.currency = $1
+         LEFT JOIN ledger_entries e ON e.account_id = la.id
+         GROUP BY m.id, m.total_revenue"
+    )
+    .bind(LEDGER_CURRENCY)
+    .fetch_all(pool)
+    .await?;
+    drift.extend(merchants.iter()
+        .map(|row| Drift {
+            subject_type: "merchant_revenue".to_string(),
+            subject_id: row.get("id"),
+            expected: row.get("expected"),
+            actual: row.get("actual"),
+        })
+        // Derived revenue is floored at zero when refunds exceed it
+        .filter(|d| d.actual != d.expected.max(Decimal::ZERO)));
+
+    let unbalanced = sqlx::query(
+        "SELECT journal_id::text AS id,
+                SUM(amount) FILTER (WHERE direction = 'debit') AS debits,
+                SUM(amount) FILTER (WHERE direction = 'credit') AS credits
+         FROM ledger_entries
+         GROUP BY journal_id
+         HAVING SUM(CASE direction WHEN 'debit' THEN amount ELSE -amount END) <> 0"
+    )
+    .fetch_all(pool)
+    .await?;
+    drift.extend(unbalanced.iter().map(|row| Drift {
+        subject_type: "journal".to_string(),
+        subject_id: row.get("id"),
+        expected: row.get::<Option<Decimal>, _>("debits").unwrap_or_default(),
+        actual: row.get::<Option<Decimal>, _>("credits").unwrap_or_default(),
+    }));
+
+    for d in &drift {
+        warn!("⚠️ Ledger drift on {} {}: ledger {} vs recorded {}", d.subject_type, d.subject_id, d.expected, d.actual);
+        // Keep one open flag per subject, refreshed with the latest figures
+        let updated = sqlx::query(
+            "UPDATE ledger_drift
+             SET expected = $3, actual = $4
+             WHERE subject_type = $1 AND subject_id = $2 AND resolved_at IS NULL"
+        )
+        .bind(&d.subject_type)
+        .bind(&d.subject_id)
+        .bind(d.expected)
+        .bind(d.actual)
+        .execute(pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            sqlx::query(
+                "INSERT INTO ledger_drift (subject_type, subject_id, expected, actual)
+                 VALUES ($1, $2, $3, $4)"
+            )
+            .bind(&d.subject_type)
+            .bind(&d.subject_id)
+            .bind(d.expected)
+            .bind(d.actual)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    // Anything flagged earlier that now matches has been corrected
+    let still_drifting: Vec<String> = drift.iter().map(|d| format!("{}:{}", d.subject_type, d.subject_id)).collect();
+    sqlx::query(
+        "UPDATE ledger_drift
+         SET resolved_at = NOW()
+         WHERE resolved_at IS NULL AND NOT (subject_type || ':' || subject_id = ANY($1))"
+    )
+    .bind(&still_drifting)
+    .execute(pool)
+    .await?;
+
+    if drift.is_empty() {
+        info!("✅ Ledger reconciled with no drift");
+    }
+    Ok(drift)
+}
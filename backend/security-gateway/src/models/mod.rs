@@ -1,7 +1,13 @@
 pub mod security_context;
 pub mod verification;
 pub mod risk_scoring;
+pub mod payment_token;
+pub mod spending_window;
+pub mod money;
 
 pub use security_context::*;
 pub use verification::*;
-pub use risk_scoring::*;
\ No newline at end of file
+pub use risk_scoring::*;
+pub use payment_token::*;
+pub use spending_window::*;
+pub use money::*;
\ No newline at end of file
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// An amount in a currency's minor units (cents for USD, yen for JPY).
+///
+/// This is the wire format for every amount (`{"minor_units": 1234, "currency": "USD"}`)
+/// and what gets signed, so there is no float rounding between what an agent
+/// signed, what the gateway checked and what was stored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Money {
+    pub minor_units: i64,
+    pub currency: String,
+}
+
+impl Money {
+    /// `currency` must be a three-letter ISO 4217 code; it is upper-cased
+    pub fn new(minor_units: i64, currency: &str) -> Result<Self> {
+        let currency = currency.trim().to_ascii_uppercase();
+        if currency.len() != 3 || !currency.bytes().all(|b| b.is_ascii_alphabetic()) {
+            bail!("Invalid ISO 4217 currency code: {:?}", currency);
+        }
+        Ok(Self { minor_units, currency })
+    }
+
+    /// Convert a major-unit amount, rejecting precision the currency cannot hold
+    pub fn from_decimal(amount: Decimal, currency: &str) -> Result<Self> {
+        let exponent = minor_unit_exponent(currency);
+        let scaled = amount.checked_mul(Decimal::from(10i64.pow(exponent)))
+            .ok_or_else(|| anyhow!("Amount {} is out of range", amount))?;
+        if scaled.fract() != Decimal::ZERO {
+            bail!("{} has more than {} decimal places for {}", amount, exponent, currency);
+        }
+        let minor_units = i64::try_from(scaled).map_err(|_| anyhow!("Amount {} is out of range", amount))?;
+        Self::new(minor_units, currency)
+    }
+
+    /// Parse a major-unit string such as `"12.34"`
+    pub fn parse(amount: &str, currency: &str) -> Result<Self> {
+        let amount: Decimal = amount.trim().parse().map_err(|_| anyhow!("Invalid amount: {:?}", amount))?;
+        Self::from_decimal(amount, currency)
+    }
+
+    /// Parse a major-unit amount sent as a JSON number or string, without going through f64
+    pub fn parse_json(amount: &serde_json::Value, currency: &str) -> Result<Self> {
+        match amount {
+            serde_json::Value::Number(n) => Self::parse(&n.to_string(), currency),
+            serde_json::Value::String(s) => Self::parse(s, currency),
+            other => bail!("Invalid amount: {}", other),
+        }
+    }
+
+    /// Read back an amount from a NUMERIC column.
+    ///
+    /// Columns already hold the currency's precision, so this only rounds
+    /// values written by hand; it never fails, saturating amounts out of range.
+    pub fn from_stored(amount: Decimal, currency: &str) -> Self {
+        let exponent = minor_unit_exponent(currency);
+        let saturated = if amount.is_sign_negative() { i64::MIN } else { i64::MAX };
+        let minor_units = amount.checked_mul(Decimal::from(10i64.pow(exponent)))
+            .map(|scaled| scaled.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero))
+            .and_then(|scaled| i64::try_from(scaled).ok())
+            .unwrap_or(saturated);
+        Self {
+            minor_units,
+            currency: currency.trim().to_ascii_uppercase(),
+        }
+    }
+
+    pub fn zero(currency: &str) -> Result<Self> {
+        Self::new(0, currency)
+    }
+
+    /// The amount in major units, as stored in NUMERIC columns
+    pub fn to_decimal(&self) -> Decimal {
+        Decimal::new(self.minor_units, minor_unit_exponent(&self.currency))
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.minor_units > 0
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.to_decimal(), self.currency)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Wire {
+            minor_units: i64,
+            currency: String,
+        }
+
+        let wire = Wire::deserialize(deserializer)?;
+        Money::new(wire.minor_units, &wire.currency).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Decimal places in a currency's minor unit (ISO 4217); 2 unless listed
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    match currency.to_ascii_uppercase().as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "UYI"
+        | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
@@ -0,0 +1,39 @@
+use super::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Limits a user placed on a delegated payment (ACP Shared Payment Token)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegatedAllowance {
+    pub max_amount: Money,
+    pub merchant_id: Option<String>,
+    pub checkout_session_id: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub single_use: bool,
+}
+
+impl DelegatedAllowance {
+    /// Parse an ACP `allowance` object.
+    ///
+    /// ACP sends `max_amount` in minor units and `reason: "one_time"` for single-use tokens.
+    pub fn from_acp(value: &serde_json::Value) -> Option<Self> {
+        let currency = value.get("currency")?.as_str()?;
+        let max_amount = Money::new(value.get("max_amount")?.as_i64()?, currency).ok()?;
+        let expires_at = value.get("expires_at")?.as_str()?.parse::<DateTime<Utc>>().ok()?;
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        Some(Self {
+            max_amount,
+            merchant_id: text("merchant_id"),
+            checkout_session_id: text("checkout_session_id"),
+            expires_at,
+            single_use: text("reason").is_none_or(|reason| reason == "one_time"),
+        })
+    }
+}
+
+/// Tokens are bearer credentials, so the ledger only ever stores their SHA-256
+pub fn payment_token_fingerprint(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
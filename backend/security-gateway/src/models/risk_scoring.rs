@@ -1,29 +1,266 @@
-use super::SecurityContext;
+use super::{Decision, Money, SecurityContext};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Prior transactions needed before an amount is judged against the agent's own history
+const MIN_HISTORY_FOR_DEVIATION: i64 = 5;
+/// Prior transactions needed before an unusual hour counts as an anomaly
+const MIN_HISTORY_FOR_TIME_OF_DAY: i64 = 20;
+/// How far back amount and time-of-day baselines look
+const BASELINE_DAYS: i64 = 90;
+
+/// What the scorer needs to know about an agent's past; `Database` reads it
+/// from Postgres, tests can supply fixed values.
+#[async_trait::async_trait]
+pub trait RiskHistory: Send + Sync {
+    async fn agent_created_at(&self, agent_id: &str) -> Result<Option<DateTime<Utc>>>;
+
+    /// Transactions of any status since `since`
+    async fn transactions_since(&self, agent_id: &str, since: DateTime<Utc>) -> Result<i64>;
+
+    /// Completed transactions between this agent and merchant
+    async fn merchant_payment_count(&self, agent_id: &str, merchant_id: &str) -> Result<i64>;
+
+    /// Completed amounts since `since`, in the owner's base currency
+    async fn amount_stats(&self, agent_id: &str, since: DateTime<Utc>) -> Result<AmountStats>;
+
+    /// Completed transactions since `since` per UTC hour of day
+    async fn hourly_distribution(&self, agent_id: &str, since: DateTime<Utc>) -> Result<[i64; 24]>;
+
+    /// 0-100; `None` for unknown merchants
+    async fn merchant_trust_score(&self, merchant_id: &str) -> Result<Option<i32>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AmountStats {
+    pub count: i64,
+    pub mean: Decimal,
+    pub stddev: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFeature {
+    AgentAge,
+    HourlyVelocity,
+    DailyVelocity,
+    NewMerchant,
+    AmountDeviation,
+    TimeOfDay,
+    MerchantTrust,
+}
+
+/// One feature's share of the score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskFactor {
+    pub feature: RiskFeature,
+    pub points: f64,
+    pub detail: String,
+}
+
+impl RiskFactor {
+    fn new(feature: RiskFeature, points: f64, detail: impl Into<String>) -> Self {
+        Self { feature, points, detail: detail.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskAssessment {
+    /// 0-100: the sum of factor points, capped
+    pub score: f64,
+    /// Every feature evaluated, including those that added nothing
+    pub factors: Vec<RiskFactor>,
+}
+
+/// Scores at or above `review` are parked for approval; at or above `decline` they are refused
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskThresholds {
+    pub review: f64,
+    pub decline: f64,
+}
+
+impl Default for RiskThresholds {
+    fn default() -> Self {
+        Self { review: 80.0, decline: 95.0 }
+    }
+}
+
+impl RiskThresholds {
+    /// Platform defaults from `RISK_REVIEW_THRESHOLD` and `RISK_DECLINE_THRESHOLD`
+    pub fn from_env() -> Self {
+        let read = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok());
+        let defaults = Self::default();
+        Self {
+            review: read("RISK_REVIEW_THRESHOLD").unwrap_or(defaults.review),
+            decline: read("RISK_DECLINE_THRESHOLD").unwrap_or(defaults.decline),
+        }
+    }
+
+    /// Apply an owner's or merchant's thresholds; the stricter value wins
+    pub fn tighten(self, review: Option<i32>, decline: Option<i32>) -> Self {
+        Self {
+            review: review.map_or(self.review, |r| self.review.min(r.into())),
+            decline: decline.map_or(self.decline, |d| self.decline.min(d.into())),
+        }
+    }
+
+    pub fn decide(&self, score: f64) -> Decision {
+        if score >= self.decline {
+            Decision::Decline
+        } else if score >= self.review {
+            Decision::Review
+        } else {
+            Decision::Approve
+        }
+    }
+}
 
 pub struct RiskScorer;
 
 impl RiskScorer {
-    pub fn calculate_risk_score(ctx: &SecurityContext) -> f64 {
-        let mut score: f64 = 0.0;
-        
-        // Amount-based risk
-        if let Some(amount) = ctx.amount {
-            if amount > 1000.0 {
-                score += 20.0;
-            } else if amount > 500.0 {
-                score += 10.0;
-            }
+    /// Score a request; `amount` is the requested amount in the owner's base currency
+    pub async fn assess(
+        history: &dyn RiskHistory,
+        ctx: &SecurityContext,
+        amount: Option<&Money>,
+        now: DateTime<Utc>,
+    ) -> Result<RiskAssessment> {
+        let agent_id = ctx.agent_id.as_str();
+        let baseline_since = now - Duration::days(BASELINE_DAYS);
+
+        let factors = vec![
+            Self::agent_age(history.agent_created_at(agent_id).await?, now),
+            Self::hourly_velocity(history.transactions_since(agent_id, now - Duration::hours(1)).await?),
+            Self::daily_velocity(history.transactions_since(agent_id, now - Duration::days(1)).await?),
+            Self::new_merchant(history.merchant_payment_count(agent_id, &ctx.merchant_id).await?),
+            match amount {
+                Some(amount) => Self::amount_deviation(amount, history.amount_stats(agent_id, baseline_since).await?),
+                None => RiskFactor::new(RiskFeature::AmountDeviation, 0.0, "No amount"),
+            },
+            Self::time_of_day(now, history.hourly_distribution(agent_id, baseline_since).await?),
+            Self::merchant_trust(history.merchant_trust_score(&ctx.merchant_id).await?),
+        ];
+
+        let score = factors.iter().map(|f| f.points).sum::<f64>().min(100.0);
+        Ok(RiskAssessment { score, factors })
+    }
+
+    fn agent_age(created_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> RiskFactor {
+        let Some(created_at) = created_at else {
+            return RiskFactor::new(RiskFeature::AgentAge, 20.0, "Registration date unknown");
+        };
+        let age = now - created_at;
+        let points = if age < Duration::days(1) {
+            20.0
+        } else if age < Duration::days(7) {
+            10.0
+        } else if age < Duration::days(30) {
+            5.0
+        } else {
+            0.0
+        };
+        RiskFactor::new(RiskFeature::AgentAge, points, format!("Registered {} days ago", age.num_days()))
+    }
+
+    fn hourly_velocity(count: i64) -> RiskFactor {
+        let points = match count {
+            c if c > 20 => 20.0,
+            c if c > 10 => 10.0,
+            c if c > 5 => 5.0,
+            _ => 0.0,
+        };
+        RiskFactor::new(RiskFeature::HourlyVelocity, points, format!("{} transactions in the last hour", count))
+    }
+
+    fn daily_velocity(count: i64) -> RiskFactor {
+        let points = match count {
+            c if c > 100 => 15.0,
+            c if c > 50 => 8.0,
+            _ => 0.0,
+        };
+        RiskFactor::new(RiskFeature::DailyVelocity, points, format!("{} transactions in the last 24 hours", count))
+    }
+
+    fn new_merchant(previous_payments: i64) -> RiskFactor {
+        if previous_payments == 0 {
+            RiskFactor::new(RiskFeature::NewMerchant, 10.0, "First payment to this merchant")
+        } else {
+            RiskFactor::new(RiskFeature::NewMerchant, 0.0, format!("{} previous payments to this merchant", previous_payments))
         }
-        
-        // New agent risk
-        // TODO: Check agent registration date
-        
-        // Velocity risk
-        // TODO: Check recent transaction count
-        
-        // Pattern risk
-        // TODO: Check for suspicious patterns
-        
-        score.min(100.0)
-    }
-}
\ No newline at end of file
+    }
+
+    fn amount_deviation(amount: &Money, stats: AmountStats) -> RiskFactor {
+        let value = amount.to_decimal();
+
+        // Too little history for a baseline: fall back to absolute size
+        if stats.count < MIN_HISTORY_FOR_DEVIATION {
+            let points = if value > Decimal::from(1000) {
+                20.0
+            } else if value > Decimal::from(500) {
+                10.0
+            } else {
+                0.0
+            };
+            return RiskFactor::new(RiskFeature::AmountDeviation, points, format!(
+                "{} with only {} prior transactions",
+                amount, stats.count
+            ));
+        }
+
+        let z = if stats.stddev > Decimal::ZERO {
+            ((value - stats.mean) / stats.stddev).to_f64().unwrap_or(0.0)
+        } else if value > stats.mean {
+            // Every prior amount was identical, so any larger amount is an outlier
+            f64::INFINITY
+        } else {
+            0.0
+        };
+        let points = if z >= 4.0 {
+            25.0
+        } else if z >= 3.0 {
+            15.0
+        } else if z >= 2.0 {
+            8.0
+        } else {
+            0.0
+        };
+        RiskFactor::new(RiskFeature::AmountDeviation, points, format!(
+            "{} is {:.1} standard deviations from the mean {:.2}",
+            amount, z, stats.mean
+        ))
+    }
+
+    fn time_of_day(now: DateTime<Utc>, by_hour: [i64; 24]) -> RiskFactor {
+        let total: i64 = by_hour.iter().sum();
+        let hour = now.hour() as usize;
+        if total < MIN_HISTORY_FOR_TIME_OF_DAY {
+            return RiskFactor::new(RiskFeature::TimeOfDay, 0.0, format!("Only {} prior transactions", total));
+        }
+
+        // The current hour and its neighbours, so activity just before or after counts as usual
+        let nearby = by_hour[(hour + 23) % 24] + by_hour[hour] + by_hour[(hour + 1) % 24];
+        let share = nearby as f64 / total as f64;
+        let points = if share < 0.02 { 10.0 } else { 0.0 };
+        RiskFactor::new(RiskFeature::TimeOfDay, points, format!(
+            "{:.0}% of past transactions were around {:02}:00 UTC",
+            share * 100.0, hour
+        ))
+    }
+
+    fn merchant_trust(trust_score: Option<i32>) -> RiskFactor {
+        let Some(trust) = trust_score else {
+            return RiskFactor::new(RiskFeature::MerchantTrust, 10.0, "Merchant not registered");
+        };
+        let points = if trust < 20 {
+            10.0
+        } else if trust < 50 {
+            5.0
+        } else {
+            0.0
+        };
+        RiskFactor::new(RiskFeature::MerchantTrust, points, format!("Merchant trust score {}", trust))
+    }
+}
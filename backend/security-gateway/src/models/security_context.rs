@@ -1,3 +1,4 @@
+use super::Money;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,7 +14,7 @@ pub struct SecurityContext {
     
     // Transaction Details
     pub transaction_id: String,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: String,
     pub merchant_id: String,
     pub merchant_name: Option<String>,
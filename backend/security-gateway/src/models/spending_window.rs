@@ -0,0 +1,70 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use tracing::warn;
+
+/// How `spending_limit_monthly` is measured for an agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonthlyWindow {
+    /// Since the first of the current month in the owner's timezone
+    #[default]
+    Calendar,
+    /// The last 30 days, ending now
+    Rolling30Days,
+}
+
+impl MonthlyWindow {
+    /// Parse `agents.monthly_limit_window`; unknown values fall back to `Calendar`
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("rolling_30d") => MonthlyWindow::Rolling30Days,
+            _ => MonthlyWindow::Calendar,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MonthlyWindow::Calendar => "monthly",
+            MonthlyWindow::Rolling30Days => "30-day",
+        }
+    }
+}
+
+/// Resolve an owner's IANA timezone name, defaulting to UTC
+pub fn owner_timezone(name: Option<&str>) -> Tz {
+    match name {
+        Some(name) => name.parse().unwrap_or_else(|_| {
+            warn!("Unknown owner timezone {:?}, using UTC", name);
+            Tz::UTC
+        }),
+        None => Tz::UTC,
+    }
+}
+
+/// Start of the owner's current day
+pub fn start_of_day(tz: Tz, now: DateTime<Utc>) -> DateTime<Utc> {
+    local_midnight(tz, now.with_timezone(&tz).date_naive())
+}
+
+/// Start of the window `spending_limit_monthly` is counted over
+pub fn start_of_month_window(tz: Tz, window: MonthlyWindow, now: DateTime<Utc>) -> DateTime<Utc> {
+    match window {
+        MonthlyWindow::Calendar => {
+            let today = now.with_timezone(&tz).date_naive();
+            local_midnight(tz, today.with_day(1).unwrap_or(today))
+        }
+        MonthlyWindow::Rolling30Days => now - Duration::days(30),
+    }
+}
+
+/// First instant of `date` in `tz`.
+///
+/// Where DST skips midnight (e.g. America/Santiago) the day starts at the
+/// first local time that exists; where midnight repeats, the earlier one wins.
+fn local_midnight(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    (0..=120)
+        .step_by(15)
+        .find_map(|minutes| tz.from_local_datetime(&(midnight + Duration::minutes(minutes))).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
@@ -1,72 +1,328 @@
+use super::{Money, RiskAssessment, RiskFactor};
+use crate::categories::RestrictedItem;
+use crate::fx::Conversion;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationResult {
+    /// True only when `decision` is `Approve`
     pub approved: bool,
+    pub decision: Decision,
     pub reason: Option<String>,
-    pub checks: SecurityChecks,
+    pub checks: CheckReport,
     pub risk_score: f64, // 0-100
+    /// Per-feature contributions to `risk_score`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub risk_factors: Vec<RiskFactor>,
+    /// Machine-readable reason for a decline, taken from the first failing check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_code: Option<DeclineCode>,
+    /// Limit that was hit, when the decline is limit-related
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<LimitDetail>,
+    /// The amount in the owner's base currency and the rate used, when there was an amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion: Option<Conversion>,
+    /// The merchant or line items that broke the agent's category rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restricted_items: Vec<RestrictedItem>,
+    /// Who must approve the payment when `decision` is `Review`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer: Option<ReviewParty>,
 }
 
+/// Who may resolve a payment parked for review; an admin always may
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewParty {
+    /// The agent's owner
+    Owner,
+    /// The merchant being paid
+    Merchant,
+}
+
+impl ReviewParty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewParty::Owner => "owner",
+            ReviewParty::Merchant => "merchant",
+        }
+    }
+}
+
+/// Outcome of verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Approve,
+    /// Checks passed but the risk score or a check needs an owner or merchant to approve
+    Review,
+    Decline,
+}
+
+impl Decision {
+    /// Status the transaction is logged with
+    pub fn transaction_status(&self) -> &'static str {
+        match self {
+            Decision::Approve => "completed",
+            Decision::Review => "pending_review",
+            Decision::Decline => "declined",
+        }
+    }
+}
+
+/// Why a transaction was declined; stable for merchants and owners to branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeclineCode {
+    AgentNotFound,
+    AgentInactive,
+    /// On the platform-wide blacklist
+    AgentBlacklisted,
+    Replay,
+    PerTxLimit,
+    DailyLimit,
+    MonthlyLimit,
+    MerchantNotAllowed,
+    /// Above the merchant's maximum for agent payments
+    MerchantLimit,
+    /// The merchant has blocked this agent
+    AgentBlocked,
+    /// The agent's available wallet balance is below the amount
+    InsufficientFunds,
+    Velocity,
+    SuspiciousPattern,
+    PaymentTokenInvalid,
+    /// The amount is zero or negative
+    InvalidAmount,
+    /// No exchange rate from the transaction currency to the owner's base currency
+    CurrencyUnsupported,
+    /// Risk score at or above the decline threshold
+    RiskTooHigh,
+    /// Parked for review and rejected by the owner or merchant
+    ReviewRejected,
+    /// A rule in the owner's spending policy denies the payment
+    PolicyViolation,
+    /// The merchant's MCC or a line item's category is denied for this agent
+    CategoryRestricted,
+    /// The agent's tier does not allow the protocol
+    TierRestricted,
+    /// A custom check failed without a more specific code
+    CheckFailed,
+}
+
+impl DeclineCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeclineCode::AgentNotFound => "AGENT_NOT_FOUND",
+            DeclineCode::AgentInactive => "AGENT_INACTIVE",
+            DeclineCode::AgentBlacklisted => "AGENT_BLACKLISTED",
+            DeclineCode::Replay => "REPLAY",
+            DeclineCode::PerTxLimit => "PER_TX_LIMIT",
+            DeclineCode::DailyLimit => "DAILY_LIMIT",
+            DeclineCode::MonthlyLimit => "MONTHLY_LIMIT",
+            DeclineCode::MerchantNotAllowed => "MERCHANT_NOT_ALLOWED",
+            DeclineCode::MerchantLimit => "MERCHANT_LIMIT",
+            DeclineCode::AgentBlocked => "AGENT_BLOCKED",
+            DeclineCode::InsufficientFunds => "INSUFFICIENT_FUNDS",
+            DeclineCode::Velocity => "VELOCITY",
+            DeclineCode::SuspiciousPattern => "SUSPICIOUS_PATTERN",
+            DeclineCode::PaymentTokenInvalid => "PAYMENT_TOKEN_INVALID",
+            DeclineCode::InvalidAmount => "INVALID_AMOUNT",
+            DeclineCode::CurrencyUnsupported => "CURRENCY_UNSUPPORTED",
+            DeclineCode::RiskTooHigh => "RISK_TOO_HIGH",
+            DeclineCode::ReviewRejected => "REVIEW_REJECTED",
+            DeclineCode::PolicyViolation => "POLICY_VIOLATION",
+            DeclineCode::CategoryRestricted => "CATEGORY_RESTRICTED",
+            DeclineCode::TierRestricted => "TIER_RESTRICTED",
+            DeclineCode::CheckFailed => "CHECK_FAILED",
+        }
+    }
+}
+
+impl std::fmt::Display for DeclineCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The limit a check compared against, so callers need not parse `reason`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitDetail {
+    pub limit: Decimal,
+    /// Amount already counted against the limit (spent, used, or transactions made)
+    pub used: Decimal,
+    pub requested: Decimal,
+    /// Headroom left before the limit: `limit - used`, never negative
+    pub remaining: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+impl LimitDetail {
+    pub fn new(limit: Decimal, used: Decimal, requested: Decimal, currency: Option<String>) -> Self {
+        Self {
+            limit,
+            used,
+            requested,
+            remaining: (limit - used).max(Decimal::ZERO),
+            currency,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Passed, but the payment needs an owner or merchant to approve it
+    Review,
+    /// The check did not apply (e.g. no amount yet) or was not run
+    Skip,
+}
+
+/// Outcome of a single security check
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SecurityChecks {
-    pub agent_exists: bool,
-    pub agent_active: bool,
-    pub within_per_tx_limit: bool,
-    pub within_daily_limit: bool,
-    pub within_monthly_limit: bool,
-    pub merchant_allowed: bool,
-    pub velocity_check_passed: bool,
-    pub pattern_normal: bool,
-    pub nonce_fresh: bool,
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<DeclineCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<LimitDetail>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restricted_items: Vec<RestrictedItem>,
+    /// Set on `Review` results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer: Option<ReviewParty>,
+    /// The risk score check's assessment; reported at the top level of the verification
+    #[serde(skip)]
+    pub risk: Option<RiskAssessment>,
+    /// Wallet amount held by the balance hold check; captured or released when the transaction is logged
+    #[serde(skip)]
+    pub wallet_hold: Option<Money>,
+}
+
+/// Outcomes of every check the gateway ran, in pipeline order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    pub fn push(&mut self, result: CheckResult) {
+        self.results.push(result);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CheckResult> {
+        self.results.iter().find(|r| r.name == name)
+    }
+
+    /// True only if the named check ran and passed
+    pub fn passed(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|r| r.status == CheckStatus::Pass)
+    }
+
+    pub fn first_failure(&self) -> Option<&CheckResult> {
+        self.results.iter().find(|r| r.status == CheckStatus::Fail)
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.first_failure().is_some()
+    }
+
+    pub fn first_review(&self) -> Option<&CheckResult> {
+        self.results.iter().find(|r| r.status == CheckStatus::Review)
+    }
+
+    /// Restricted items reported by any check
+    pub fn restricted_items(&self) -> Vec<RestrictedItem> {
+        self.results.iter().flat_map(|r| r.restricted_items.iter().cloned()).collect()
+    }
+
+    /// Who must approve a parked payment: the merchant if any check asked for
+    /// the merchant's review, otherwise the owner; `None` if nothing asked
+    pub fn reviewer(&self) -> Option<ReviewParty> {
+        let mut reviewers = self.results.iter().filter_map(|r| r.reviewer);
+        let first = reviewers.next()?;
+        Some(if first == ReviewParty::Merchant || reviewers.any(|r| r == ReviewParty::Merchant) {
+            ReviewParty::Merchant
+        } else {
+            ReviewParty::Owner
+        })
+    }
+
+    /// The risk assessment, if the risk score check ran
+    pub fn risk(&self) -> Option<&RiskAssessment> {
+        self.results.iter().find_map(|r| r.risk.as_ref())
+    }
+
+    /// The wallet amount held for the payment, if the balance hold check placed one
+    pub fn wallet_hold(&self) -> Option<&Money> {
+        self.results.iter().find_map(|r| r.wallet_hold.as_ref())
+    }
 }
 
 impl VerificationResult {
-    pub fn declined(reason: String) -> Self {
+    /// Decline carrying the full check report; code, reason and limit come from the first failure.
+    ///
+    /// The risk score is the assessed one if the risk check ran, otherwise 100.
+    pub fn declined_by(checks: CheckReport) -> Self {
+        let failure = checks.first_failure();
+        let reason = failure.and_then(|f| f.detail.clone());
+        let decline_code = failure.map(|f| f.code.unwrap_or(DeclineCode::CheckFailed));
+        let limit = failure.and_then(|f| f.limit.clone());
+        let restricted_items = checks.restricted_items();
+        let risk = checks.risk().cloned();
+        
         Self {
             approved: false,
+            decision: Decision::Decline,
+            reason,
+            checks,
+            risk_score: risk.as_ref().map_or(100.0, |r| r.score),
+            risk_factors: risk.map(|r| r.factors).unwrap_or_default(),
+            decline_code,
+            limit,
+            conversion: None,
+            restricted_items,
+            reviewer: None,
+        }
+    }
+    
+    pub fn declined(code: DeclineCode, reason: String) -> Self {
+        Self {
+            approved: false,
+            decision: Decision::Decline,
             reason: Some(reason),
-            checks: SecurityChecks::all_failed(),
+            checks: CheckReport::default(),
             risk_score: 100.0,
+            risk_factors: Vec::new(),
+            decline_code: Some(code),
+            limit: None,
+            conversion: None,
+            restricted_items: Vec::new(),
+            reviewer: None,
         }
     }
-    
+
     pub fn approved_with_score(score: f64) -> Self {
         Self {
             approved: true,
+            decision: Decision::Approve,
             reason: None,
-            checks: SecurityChecks::all_passed(),
+            checks: CheckReport::default(),
             risk_score: score,
+            risk_factors: Vec::new(),
+            decline_code: None,
+            limit: None,
+            conversion: None,
+            restricted_items: Vec::new(),
+            reviewer: None,
         }
     }
 }
-
-impl SecurityChecks {
-    pub fn all_passed() -> Self {
-        Self {
-            agent_exists: true,
-            agent_active: true,
-            within_per_tx_limit: true,
-            within_daily_limit: true,
-            within_monthly_limit: true,
-            merchant_allowed: true,
-            velocity_check_passed: true,
-            pattern_normal: true,
-            nonce_fresh: true,
-        }
-    }
-    
-    pub fn all_failed() -> Self {
-        Self {
-            agent_exists: false,
-            agent_active: false,
-            within_per_tx_limit: false,
-            within_daily_limit: false,
-            within_monthly_limit: false,
-            merchant_allowed: false,
-            velocity_check_passed: false,
-            pattern_normal: false,
-            nonce_fresh: false,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,359 @@
+//! Declarative spending policies.
+//!
+//! Owners attach policy documents (JSON or YAML) to an agent or a team. A rule
+//! matches a payment when every condition under `when` holds and, if it has a
+//! `limit`, the payment would take spending over it. Matching rules `deny` or
+//! send the payment to `review`; deny wins when both match.
+//!
+//! ```yaml
+//! rules:
+//!   - name: weekly cap at the bookshop
+//!     when: { merchants: ["6f1c..."] }
+//!     limit: { amount: 200, period: week }
+//!     effect: deny
+//!   - name: office hours
+//!     when: { not: { days: [mon, tue, wed, thu, fri], hours: { from: 9, to: 17 } } }
+//!     effect: deny
+//!   - name: large bronze payments
+//!     when: { agent_tiers: [bronze], amount_above: 500 }
+//!     effect: review
+//! ```
+
+use crate::models::Money;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocument {
+    pub rules: Vec<PolicyRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    pub name: String,
+    #[serde(default)]
+    pub when: Conditions,
+    /// Spending cap over a rolling period, counted at the merchants and categories `when` names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<SpendCap>,
+    pub effect: Effect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Review,
+    Deny,
+}
+
+/// All listed conditions must hold; an empty set always holds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Conditions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merchants: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merchant_categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agent_tiers: Vec<String>,
+    /// In the owner's timezone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<HourRange>,
+    /// In the owner's base currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_above: Option<Decimal>,
+    /// Holds when the nested conditions do not all hold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Conditions>>,
+}
+
+/// Local hours `from` (inclusive) to `to` (exclusive); wraps past midnight when `from > to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HourRange {
+    pub from: u32,
+    pub to: u32,
+}
+
+impl HourRange {
+    fn contains(&self, hour: u32) -> bool {
+        if self.from <= self.to {
+            (self.from..self.to).contains(&hour)
+        } else {
+            hour >= self.from || hour < self.to
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendCap {
+    /// In the owner's base currency
+    pub amount: Decimal,
+    pub period: Period,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// Rolling window: 1, 7 or 30 days
+    pub fn duration(&self) -> Duration {
+        match self {
+            Period::Day => Duration::days(1),
+            Period::Week => Duration::days(7),
+            Period::Month => Duration::days(30),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+impl PolicyDocument {
+    /// Parse a JSON or YAML document (JSON is read as YAML)
+    pub fn parse(raw: &str) -> Result<Self> {
+        let document: Self = serde_yaml::from_str(raw)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    pub fn from_json(value: serde_json::Value) -> Result<Self> {
+        let document: Self = serde_json::from_value(value)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                bail!("Every rule needs a name");
+            }
+            if !names.insert(rule.name.as_str()) {
+                bail!("Duplicate rule name '{}'", rule.name);
+            }
+            if rule.limit.is_some_and(|cap| cap.amount <= Decimal::ZERO) {
+                bail!("Rule '{}': limit must be positive", rule.name);
+            }
+            rule.when.validate(&rule.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Conditions {
+    fn validate(&self, rule: &str) -> Result<()> {
+        if let Some(hours) = self.hours {
+            if hours.from > 23 || hours.to > 24 || hours.from == hours.to {
+                bail!("Rule '{}': hours must be distinct values in 0-24", rule);
+            }
+        }
+        if let Some(not) = &self.not {
+            not.validate(rule)?;
+        }
+        Ok(())
+    }
+
+    /// Whether every condition holds, with a line of explanation per condition
+    fn evaluate(&self, facts: &PaymentFacts<'_>) -> (bool, Vec<String>) {
+        let mut holds = true;
+        let mut reasons = Vec::new();
+        let mut check = |ok: bool, reason: String| {
+            holds &= ok;
+            reasons.push(reason);
+        };
+
+        if !self.merchants.is_empty() {
+            let ok = self.merchants.iter().any(|m| m == facts.merchant_id);
+            check(ok, format!("merchant {} {} listed", facts.merchant_id, is_or_not(ok)));
+        }
+        if !self.merchant_categories.is_empty() {
+            match facts.merchant_category {
+                Some(category) => {
+                    let ok = self.merchant_categories.iter().any(|c| c.eq_ignore_ascii_case(category));
+                    check(ok, format!("merchant category {} {} listed", category, is_or_not(ok)));
+                }
+                None => check(false, "merchant has no category".to_string()),
+            }
+        }
+        if !self.agent_tiers.is_empty() {
+            match facts.tier {
+                Some(tier) => {
+                    let ok = self.agent_tiers.iter().any(|t| t.eq_ignore_ascii_case(tier));
+                    check(ok, format!("tier {} {} listed", tier, is_or_not(ok)));
+                }
+                None => check(false, "agent has no tier".to_string()),
+            }
+        }
+        if !self.days.is_empty() {
+            let day = facts.local_time.weekday();
+            let ok = self.days.contains(&day);
+            check(ok, format!("{} {} a listed day", day, is_or_not(ok)));
+        }
+        if let Some(hours) = self.hours {
+            let hour = facts.local_time.hour();
+            let ok = hours.contains(hour);
+            check(ok, format!(
+                "{:02}:{:02} {} within {:02}:00-{:02}:00",
+                hour, facts.local_time.minute(), is_or_not(ok), hours.from, hours.to
+            ));
+        }
+        if let Some(threshold) = self.amount_above {
+            match facts.amount {
+                Some(amount) => {
+                    let ok = amount.to_decimal() > threshold;
+                    check(ok, format!("amount {} {} above {}", amount, is_or_not(ok), threshold));
+                }
+                None => check(false, "no amount".to_string()),
+            }
+        }
+        if let Some(not) = &self.not {
+            let (inner, inner_reasons) = not.evaluate(facts);
+            check(!inner, format!("not ({})", inner_reasons.join(", ")));
+        }
+
+        (holds, reasons)
+    }
+}
+
+fn is_or_not(ok: bool) -> &'static str {
+    if ok { "is" } else { "is not" }
+}
+
+/// Data policies need beyond the payment itself; `Database` reads it from Postgres
+#[async_trait::async_trait]
+pub trait PolicyFacts: Send + Sync {
+    async fn merchant_category(&self, merchant_id: &str) -> Result<Option<String>>;
+
+    /// Spending since `since` in the owner's base currency, at `merchants` and
+    /// `categories` when non-empty; pending and completed payments count
+    async fn policy_spending(
+        &self,
+        agent_id: &str,
+        since: DateTime<Utc>,
+        merchants: &[String],
+        categories: &[String],
+    ) -> Result<Decimal>;
+}
+
+/// The payment a policy is evaluated against
+#[derive(Debug, Clone)]
+pub struct PolicyInput<'a> {
+    pub agent_id: &'a str,
+    pub merchant_id: &'a str,
+    /// In the owner's base currency
+    pub amount: Option<&'a Money>,
+    pub tier: Option<&'a str>,
+    pub timezone: Tz,
+    pub now: DateTime<Utc>,
+}
+
+struct PaymentFacts<'a> {
+    merchant_id: &'a str,
+    merchant_category: Option<&'a str>,
+    tier: Option<&'a str>,
+    local_time: DateTime<Tz>,
+    amount: Option<&'a Money>,
+}
+
+/// How one rule fared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleOutcome {
+    pub policy: String,
+    pub rule: String,
+    pub effect: Effect,
+    pub matched: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyEvaluation {
+    /// Every rule of every policy, in order
+    pub rules: Vec<RuleOutcome>,
+}
+
+impl PolicyEvaluation {
+    /// The first matching rule with the strongest effect
+    pub fn decisive(&self) -> Option<&RuleOutcome> {
+        let strongest = self.rules.iter().filter(|r| r.matched).map(|r| r.effect).max()?;
+        self.rules.iter().find(|r| r.matched && r.effect == strongest)
+    }
+
+    pub fn effect(&self) -> Option<Effect> {
+        self.decisive().map(|r| r.effect)
+    }
+}
+
+/// Evaluate named policies against a payment
+pub async fn evaluate(
+    policies: &[(String, PolicyDocument)],
+    facts: &dyn PolicyFacts,
+    input: &PolicyInput<'_>,
+) -> Result<PolicyEvaluation> {
+    let category = facts.merchant_category(input.merchant_id).await?;
+    let payment = PaymentFacts {
+        merchant_id: input.merchant_id,
+        merchant_category: category.as_deref(),
+        tier: input.tier,
+        local_time: input.now.with_timezone(&input.timezone),
+        amount: input.amount,
+    };
+
+    let mut evaluation = PolicyEvaluation::default();
+    for (policy, document) in policies {
+        for rule in &document.rules {
+            let (mut matched, mut reasons) = rule.when.evaluate(&payment);
+            if let (true, Some(cap)) = (matched, rule.limit) {
+                let (exceeded, reason) = exceeds_cap(facts, input, &rule.when, cap).await?;
+                matched = exceeded;
+                reasons.push(reason);
+            }
+            evaluation.rules.push(RuleOutcome {
+                policy: policy.clone(),
+                rule: rule.name.clone(),
+                effect: rule.effect,
+                matched,
+                reasons,
+            });
+        }
+    }
+    Ok(evaluation)
+}
+
+async fn exceeds_cap(
+    facts: &dyn PolicyFacts,
+    input: &PolicyInput<'_>,
+    scope: &Conditions,
+    cap: SpendCap,
+) -> Result<(bool, String)> {
+    let Some(amount) = input.amount else {
+        return Ok((false, "no amount to count against the limit".to_string()));
+    };
+    let since = input.now - cap.period.duration();
+    let spent = facts.policy_spending(input.agent_id, since, &scope.merchants, &scope.merchant_categories).await?;
+    let total = spent + amount.to_decimal();
+    let exceeded = total > cap.amount;
+    Ok((exceeded, format!(
+        "spent {} + {} {} {} per {}",
+        spent, amount, if exceeded { "exceeds" } else { "is within" }, cap.amount, cap.period.as_str()
+    )))
+}
@@ -0,0 +1,260 @@
+//! Spend reservations.
+//!
+//! Verification places a hold against the agent's daily and monthly budgets;
+//! the hold is settled when the transaction completes and released when it is
+//! denied or its hold expires. Budget usage is completed transactions plus
+//! live holds, so pending payments count before they are confirmed.
+
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How long a hold placed during verification lives if nothing settles it
+pub const DEFAULT_HOLD_TTL_SECONDS: i64 = 15 * 60;
+
+/// How long a transaction parked for review keeps its budget reserved
+pub const REVIEW_HOLD_TTL_HOURS: i64 = 72;
+
+/// Hold TTL from `SPEND_HOLD_TTL_SECONDS`
+pub fn hold_ttl_from_env() -> Duration {
+    let seconds = std::env::var("SPEND_HOLD_TTL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_HOLD_TTL_SECONDS);
+    Duration::seconds(seconds)
+}
+
+/// What to reserve
+#[derive(Debug, Clone)]
+pub struct HoldRequest<'a> {
+    pub agent_id: &'a str,
+    /// Identifies the hold for settlement; the gateway uses the request nonce
+    pub nonce: &'a str,
+    /// Set when the hold belongs to an already-created transaction
+    pub transaction_id: Option<Uuid>,
+    /// In the owner's base currency, like the limits it is held against
+    pub amount: &'a Money,
+    pub ttl: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum HoldOutcome {
+    Held(Uuid),
+    /// The hold would push the agent past a budget; nothing was reserved
+    Exceeded {
+        code: DeclineCode,
+        reason: String,
+        limit: LimitDetail,
+    },
+}
+
+/// Persists holds in `spend_reservations`
+#[derive(Clone)]
+pub struct ReservationStore {
+    pool: PgPool,
+}
+
+impl ReservationStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Reserve `amount` against the daily and monthly budgets.
+    ///
+    /// The agent row is locked for the duration, so concurrent holds for one
+    /// agent are serialised and cannot jointly overshoot a budget.
+    pub async fn hold(&self, request: &HoldRequest<'_>) -> Result<HoldOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        let agent = sqlx::query(
+            "SELECT LEAST(a.spending_limit_daily, tp.max_daily) AS spending_limit_daily,
+                    LEAST(a.spending_limit_monthly, tp.max_monthly) AS spending_limit_monthly,
+                    a.monthly_limit_window, u.timezone AS owner_timezone
+             FROM agents a
+             LEFT JOIN users u ON u.id = a.user_id
+             LEFT JOIN tier_profiles tp ON tp.tier = a.tier
+             WHERE a.id = $1
+             FOR UPDATE OF a"
+        )
+        .bind(request.agent_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow!("Agent not found: {}", request.agent_id))?;
+
+        let tz = owner_timezone(agent.get::<Option<String>, _>("owner_timezone").as_deref());
+        let window = MonthlyWindow::parse(agent.get::<Option<String>, _>("monthly_limit_window").as_deref());
+        let now = Utc::now();
+        let amount = request.amount.to_decimal();
+        let currency = Some(request.amount.currency.clone());
+        let in_currency = |value: Decimal| Money::from_stored(value, &request.amount.currency);
+
+        let daily_limit: Option<Decimal> = agent.get("spending_limit_daily");
+        if let Some(daily_limit) = daily_limit {
+            let used = spending_since(&mut tx, request.agent_id, start_of_day(tz, now)).await?;
+            if used + amount > daily_limit {
+                let limit = LimitDetail::new(daily_limit, used, amount, currency);
+                return Ok(HoldOutcome::Exceeded {
+                    code: DeclineCode::DailyLimit,
+                    reason: format!(
+                        "Would exceed daily limit {} (spent or held: {}, remaining: {})",
+                        in_currency(daily_limit), in_currency(used), in_currency(limit.remaining)
+                    ),
+                    limit,
+                });
+            }
+        }
+
+        let monthly_limit: Option<Decimal> = agent.get("spending_limit_monthly");
+        if let Some(monthly_limit) = monthly_limit {
+            let used = spending_since(&mut tx, request.agent_id, start_of_month_window(tz, window, now)).await?;
+            if used + amount > monthly_limit {
+                let limit = LimitDetail::new(monthly_limit, used, amount, currency);
+                return Ok(HoldOutcome::Exceeded {
+                    code: DeclineCode::MonthlyLimit,
+                    reason: format!(
+                        "Would exceed {} limit {} (spent or held: {}, remaining: {})",
+                        window.label(), in_currency(monthly_limit), in_currency(used), in_currency(limit.remaining)
+                    ),
+                    limit,
+                });
+            }
+        }
+
+        let hold_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO spend_reservations (agent_id, nonce, transaction_id, amount, currency, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id"
+        )
+        .bind(request.agent_id)
+        .bind(request.nonce)
+        .bind(request.transaction_id)
+        .bind(amount)
+        .bind(&request.amount.currency)
+        .bind(now + request.ttl)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        info!("🔒 Held {} for agent {} until {}", request.amount, request.agent_id, now + request.ttl);
+        Ok(HoldOutcome::Held(hold_id))
+    }
+
+    /// Release the hold for a denied or abandoned transaction
+    pub async fn release_transaction(&self, transaction_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE spend_reservations
+             SET status = 'released', resolved_at = NOW()
+             WHERE transaction_id = $1 AND status = 'held'"
+        )
+        .bind(transaction_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark lapsed holds released. They already stop counting at `expires_at`;
+    /// this only keeps the table honest for reporting.
+    pub async fn release_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE spend_reservations
+             SET status = 'released', resolved_at = NOW()
+             WHERE status = 'held' AND expires_at <= NOW()"
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            warn!("⌛ Released {} expired spend holds", result.rows_affected());
+        }
+        Ok(result.rows_affected())
+    }
+}
+
+/// Completed and approved spending plus live holds since `since`, in the owner's base currency.
+///
+/// Approved payments still waiting to reach the merchant count in full; their
+/// holds are settled when the review approves them.
+pub(crate) async fn spending_since(conn: &mut PgConnection, agent_id: &str, since: DateTime<Utc>) -> Result<Decimal> {
+    // transactions.created_at is a UTC TIMESTAMP without zone
+    let total: Decimal = sqlx::query_scalar(
+        "SELECT
+            COALESCE((SELECT SUM(COALESCE(base_amount, amount)) FROM transactions
+                      WHERE agent_id = $1 AND created_at >= $2
+                        AND status IN ('completed', 'approved', 'sending')), 0)
+          + COALESCE((SELECT SUM(amount) FROM spend_reservations
+                      WHERE agent_id = $1 AND created_at >= $3 AND status = 'held' AND expires_at > NOW()), 0)"
+    )
+    .bind(agent_id)
+    .bind(since.naive_utc())
+    .bind(since)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(total)
+}
+
+/// Settle inside the caller's database transaction, alongside marking it completed
+pub async fn settle_transaction(conn: &mut PgConnection, transaction_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE spend_reservations
+         SET status = 'settled', resolved_at = NOW()
+         WHERE transaction_id = $1 AND status = 'held'"
+    )
+    .bind(transaction_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Settle the gateway hold placed under `nonce` and link it to its transaction
+pub(crate) async fn settle_nonce(conn: &mut PgConnection, agent_id: &str, nonce: &str, transaction_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE spend_reservations
+         SET status = 'settled', transaction_id = $3, resolved_at = NOW()
+         WHERE agent_id = $1 AND nonce = $2 AND status = 'held'"
+    )
+    .bind(agent_id)
+    .bind(nonce)
+    .bind(transaction_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Tie a verification hold to a transaction parked for review and keep it for the review window
+pub(crate) async fn park_nonce(conn: &mut PgConnection, agent_id: &str, nonce: &str, transaction_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE spend_reservations
+         SET transaction_id = $3, expires_at = NOW() + make_interval(hours => $4)
+         WHERE agent_id = $1 AND nonce = $2 AND status = 'held'"
+    )
+    .bind(agent_id)
+    .bind(nonce)
+    .bind(transaction_id)
+    .bind(REVIEW_HOLD_TTL_HOURS as i32)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub(crate) async fn release_nonce(conn: &mut PgConnection, agent_id: &str, nonce: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE spend_reservations
+         SET status = 'released', resolved_at = NOW()
+         WHERE agent_id = $1 AND nonce = $2 AND status = 'held'"
+    )
+    .bind(agent_id)
+    .bind(nonce)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
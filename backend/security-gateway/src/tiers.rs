@@ -0,0 +1,131 @@
+//! Agent tiers as limit and capability profiles.
+//!
+//! Each tier (`bronze` to `diamond`) has a profile in `tier_profiles`. Its
+//! maxima cap the agent's own spending limits, its velocity ceiling caps the
+//! gateway's per-minute limit, and it can restrict protocols or send every
+//! payment to review. Admins move agents between tiers; `recommend` suggests
+//! a move from the agent's recent history.
+
+use crate::models::Protocol;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TierProfile {
+    pub tier: String,
+    /// Order from lowest (1) to highest
+    pub rank: i32,
+    /// Caps on the agent's limits, in the owner's base currency; `None` leaves them as set
+    pub max_per_tx: Option<Decimal>,
+    pub max_daily: Option<Decimal>,
+    pub max_monthly: Option<Decimal>,
+    /// Protocols agents in the tier may pay over; `None` or empty allows all
+    pub allowed_protocols: Option<Vec<String>>,
+    /// Never above the gateway-wide velocity limit
+    pub velocity_per_minute: Option<i32>,
+    pub requires_manual_review: bool,
+    /// Completed transactions in the history window needed to be promoted into this tier
+    pub min_completed_transactions: i32,
+}
+
+impl TierProfile {
+    pub fn allows_protocol(&self, protocol: &Protocol) -> bool {
+        let protocol = protocol.to_string();
+        self.allowed_protocols
+            .as_ref()
+            .filter(|allowed| !allowed.is_empty())
+            .is_none_or(|allowed| allowed.iter().any(|p| p.eq_ignore_ascii_case(&protocol)))
+    }
+
+    /// The tighter of the gateway's velocity limit and the tier's ceiling
+    pub fn velocity_limit(&self, gateway_limit: i64) -> i64 {
+        self.velocity_per_minute
+            .map_or(gateway_limit, |ceiling| gateway_limit.min(ceiling.into()))
+    }
+}
+
+/// An agent's recent record, over `HISTORY_DAYS`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierHistory {
+    pub completed: i64,
+    pub declined: i64,
+    /// Unresolved high or critical fraud patterns
+    pub open_fraud_patterns: i64,
+}
+
+pub const HISTORY_DAYS: i64 = 90;
+
+/// Declines above this share of decided payments call for a demotion
+const DEMOTE_DECLINE_RATE: f64 = 0.25;
+/// Promotion needs a clean record: at most this share declined
+const PROMOTE_DECLINE_RATE: f64 = 0.05;
+/// Too few decisions to judge a decline rate
+const MIN_DECISIONS: i64 = 4;
+
+impl TierHistory {
+    pub fn decline_rate(&self) -> f64 {
+        let decided = self.completed + self.declined;
+        if decided == 0 { 0.0 } else { self.declined as f64 / decided as f64 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TierMove {
+    Promote,
+    Demote,
+    Keep,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TierRecommendation {
+    pub action: TierMove,
+    /// The tier to move to, or the current one when keeping
+    pub tier: String,
+    pub reason: String,
+}
+
+/// One step up or down, or none, for an agent in `current` with `history`
+pub fn recommend(profiles: &[TierProfile], current: &str, history: &TierHistory) -> Option<TierRecommendation> {
+    let mut ranked: Vec<_> = profiles.iter().collect();
+    ranked.sort_by_key(|p| p.rank);
+    let position = ranked.iter().position(|p| p.tier == current)?;
+    let lower = position.checked_sub(1).map(|i| ranked[i]);
+    let higher = ranked.get(position + 1);
+    let decided = history.completed + history.declined;
+    let rate = history.decline_rate();
+
+    let keep = |reason: String| TierRecommendation { action: TierMove::Keep, tier: current.to_string(), reason };
+
+    let demote_reason = if history.open_fraud_patterns > 0 {
+        Some(format!("{} unresolved high-severity fraud patterns", history.open_fraud_patterns))
+    } else if decided >= MIN_DECISIONS && rate > DEMOTE_DECLINE_RATE {
+        Some(format!("{:.0}% of {} payments declined", rate * 100.0, decided))
+    } else {
+        None
+    };
+    if let Some(reason) = demote_reason {
+        return Some(match lower {
+            Some(lower) => TierRecommendation { action: TierMove::Demote, tier: lower.tier.clone(), reason },
+            None => keep(format!("{}, already in the lowest tier", reason)),
+        });
+    }
+
+    let Some(higher) = higher else {
+        return Some(keep("Already in the highest tier".to_string()));
+    };
+    if history.completed < higher.min_completed_transactions.into() {
+        return Some(keep(format!(
+            "{} of {} completed payments needed for {}",
+            history.completed, higher.min_completed_transactions, higher.tier
+        )));
+    }
+    if rate > PROMOTE_DECLINE_RATE {
+        return Some(keep(format!("{:.0}% of payments declined", rate * 100.0)));
+    }
+    Some(TierRecommendation {
+        action: TierMove::Promote,
+        tier: higher.tier.clone(),
+        reason: format!("{} completed payments with {:.0}% declined", history.completed, rate * 100.0),
+    })
+}
@@ -0,0 +1,124 @@
+//! Parallel holds and debits against one agent must never overdraw it.
+//!
+//! Needs a Postgres with docs/schema.sql (or docs/balance_holds_schema.sql)
+//! applied.
+
+use rust_decimal::Decimal;
+use security_gateway::balance;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const PARALLEL_REQUESTS: usize = 25;
+
+async fn connect() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+    PgPoolOptions::new()
+        .max_connections(PARALLEL_REQUESTS as u32)
+        .connect(&url)
+        .await
+        .expect("connect to DATABASE_URL")
+}
+
+async fn create_agent(pool: &PgPool, balance: Decimal) -> String {
+    let agent_id = format!("test-balance-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO agents (id, owner_company, owner_email, protocol, balance, remaining_balance, held_balance)
+         VALUES ($1, 'Balance Test', 'balance-test@example.com', 'ACP', $2, $2, 0)"
+    )
+    .bind(&agent_id)
+    .bind(balance)
+    .execute(pool)
+    .await
+    .expect("insert test agent");
+    agent_id
+}
+
+async fn balances(pool: &PgPool, agent_id: &str) -> (Decimal, Decimal) {
+    sqlx::query_as("SELECT remaining_balance, held_balance FROM agents WHERE id = $1")
+        .bind(agent_id)
+        .fetch_one(pool)
+        .await
+        .expect("read balances")
+}
+
+async fn delete_agent(pool: &PgPool, agent_id: &str) {
+    sqlx::query("DELETE FROM agents WHERE id = $1")
+        .bind(agent_id)
+        .execute(pool)
+        .await
+        .expect("delete test agent");
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn parallel_holds_and_debits_never_overdraw() {
+    let pool = connect().await;
+    let agent_id = create_agent(&pool, Decimal::from(100)).await;
+    let amount = Decimal::from(10);
+
+    // Hold then debit, as create_transaction and complete_transaction do
+    let tasks: Vec<_> = (0..PARALLEL_REQUESTS)
+        .map(|_| {
+            let pool = pool.clone();
+            let agent_id = agent_id.clone();
+            tokio::spawn(async move {
+                let mut conn = pool.acquire().await?;
+                if !balance::hold(&mut conn, &agent_id, amount).await? {
+                    return anyhow::Ok(false);
+                }
+                let mut tx = pool.begin().await?;
+                let debited = balance::capture(&mut tx, &agent_id, amount, true).await?;
+                tx.commit().await?;
+                Ok(debited)
+            })
+        })
+        .collect();
+
+    let mut completed = 0;
+    for task in tasks {
+        if task.await.expect("task panicked").expect("database error") {
+            completed += 1;
+        }
+    }
+
+    let (remaining, held) = balances(&pool, &agent_id).await;
+    delete_agent(&pool, &agent_id).await;
+
+    assert_eq!(completed, 10);
+    assert_eq!(remaining, Decimal::ZERO);
+    assert_eq!(held, Decimal::ZERO);
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn parallel_unheld_debits_never_overdraw() {
+    let pool = connect().await;
+    let agent_id = create_agent(&pool, Decimal::from(50)).await;
+    let amount = Decimal::from(10);
+
+    // Transactions created before holds existed are debited directly
+    let tasks: Vec<_> = (0..PARALLEL_REQUESTS)
+        .map(|_| {
+            let pool = pool.clone();
+            let agent_id = agent_id.clone();
+            tokio::spawn(async move {
+                let mut conn = pool.acquire().await?;
+                balance::capture(&mut conn, &agent_id, amount, false).await
+            })
+        })
+        .collect();
+
+    let mut completed = 0;
+    for task in tasks {
+        if task.await.expect("task panicked").expect("database error") {
+            completed += 1;
+        }
+    }
+
+    let (remaining, _) = balances(&pool, &agent_id).await;
+    delete_agent(&pool, &agent_id).await;
+
+    assert_eq!(completed, 5);
+    assert_eq!(remaining, Decimal::ZERO);
+}
@@ -0,0 +1,92 @@
+//! Category rules over merchant MCCs and line items, without Postgres.
+
+use security_gateway::categories::{self, CategoryEffect, CategoryRule, CategoryScope, LineItem};
+use serde_json::json;
+
+fn rule(scope: CategoryScope, category: &str, effect: CategoryEffect) -> CategoryRule {
+    CategoryRule { scope, category: category.to_string(), effect }
+}
+
+fn item(name: &str, category: Option<&str>) -> LineItem {
+    LineItem {
+        id: None,
+        name: Some(name.to_string()),
+        category: category.map(str::to_string),
+    }
+}
+
+#[test]
+fn line_items_come_from_names_objects_and_acp_items() {
+    let items = categories::line_items(&json!([
+        "Paperback",
+        {"name": "Vintage port", "category": "Alcohol"},
+        {"id": "li_1", "item": {"id": "sku_9", "category": "gift_cards"}, "total": 5000},
+        42
+    ]));
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0], item("Paperback", None));
+    assert_eq!(items[1], item("Vintage port", Some("alcohol")));
+    assert_eq!(items[2].id.as_deref(), Some("li_1"));
+    assert_eq!(items[2].category.as_deref(), Some("gift_cards"));
+}
+
+#[test]
+fn denied_and_reviewed_items_are_named() {
+    let rules = vec![
+        rule(CategoryScope::Item, "alcohol", CategoryEffect::Deny),
+        rule(CategoryScope::Item, "gift_cards", CategoryEffect::Review),
+    ];
+    let items = vec![
+        item("Paperback", Some("books")),
+        item("Vintage port", Some("alcohol")),
+        item("Store card", Some("gift_cards")),
+    ];
+
+    let restricted = categories::evaluate(&rules, None, &items);
+    assert_eq!(restricted.len(), 2);
+    assert_eq!((restricted[0].index, restricted[0].effect), (Some(1), CategoryEffect::Deny));
+    assert_eq!((restricted[1].index, restricted[1].effect), (Some(2), CategoryEffect::Review));
+    assert!(categories::describe(&restricted).starts_with("Vintage port (alcohol): alcohol is denied"));
+}
+
+#[test]
+fn allow_list_denies_other_categories_and_reviews_uncategorized() {
+    let rules = vec![rule(CategoryScope::Item, "books", CategoryEffect::Allow)];
+    let items = vec![
+        item("Paperback", Some("books")),
+        item("Headphones", Some("electronics")),
+        item("Mystery box", None),
+    ];
+
+    let effects: Vec<_> = categories::evaluate(&rules, None, &items)
+        .iter()
+        .map(|r| (r.index, r.effect))
+        .collect();
+    assert_eq!(effects, vec![(Some(1), CategoryEffect::Deny), (Some(2), CategoryEffect::Review)]);
+}
+
+#[test]
+fn merchant_rules_match_the_mcc() {
+    let rules = vec![rule(CategoryScope::Merchant, "5921", CategoryEffect::Deny)];
+
+    let restricted = categories::evaluate(&rules, Some("5921"), &[]);
+    assert_eq!(restricted.len(), 1);
+    assert_eq!(restricted[0].index, None);
+    assert!(categories::evaluate(&rules, Some("5942"), &[]).is_empty());
+    assert!(categories::evaluate(&rules, None, &[]).is_empty());
+
+    // Item rules never apply to the merchant
+    let item_rules = vec![rule(CategoryScope::Item, "5921", CategoryEffect::Deny)];
+    assert!(categories::evaluate(&item_rules, Some("5921"), &[]).is_empty());
+}
+
+#[test]
+fn rules_are_validated_and_normalized() {
+    let mcc = rule(CategoryScope::Merchant, "59x1", CategoryEffect::Deny);
+    assert!(mcc.validated().is_err());
+
+    let named = rule(CategoryScope::Item, " Alcohol ", CategoryEffect::Deny).validated().unwrap();
+    assert_eq!(named.category, "alcohol");
+    assert!(rule(CategoryScope::Item, "  ", CategoryEffect::Deny).validated().is_err());
+}
@@ -0,0 +1,69 @@
+//! One `GatewayConfig` carries every gateway tunable, from the environment or
+//! built in code, and the standard pipeline is built from it.
+
+use chrono::Duration;
+use security_gateway::checks::{CheckPipeline, PipelineMode};
+use security_gateway::config::DEFAULT_VELOCITY_LIMIT_PER_MINUTE;
+use security_gateway::reservations::DEFAULT_HOLD_TTL_SECONDS;
+use security_gateway::{GatewayConfig, RiskThresholds};
+
+const VARS: [&str; 5] = [
+    "VELOCITY_LIMIT_PER_MINUTE",
+    "RISK_REVIEW_THRESHOLD",
+    "RISK_DECLINE_THRESHOLD",
+    "SPEND_HOLD_TTL_SECONDS",
+    "GATEWAY_RUN_ALL_CHECKS",
+];
+
+#[test]
+fn defaults_match_the_documented_values() {
+    let config = GatewayConfig::default();
+    assert_eq!(config.velocity_limit_per_minute, DEFAULT_VELOCITY_LIMIT_PER_MINUTE);
+    assert_eq!(config.risk, RiskThresholds { review: 80.0, decline: 95.0 });
+    assert_eq!(config.hold_ttl, Duration::seconds(DEFAULT_HOLD_TTL_SECONDS));
+    assert_eq!(config.mode, PipelineMode::ShortCircuit);
+}
+
+// The only test that touches these variables, so it cannot race another
+#[test]
+fn environment_overrides_each_tunable() {
+    for var in VARS {
+        std::env::remove_var(var);
+    }
+    assert_eq!(GatewayConfig::from_env(), GatewayConfig::default());
+
+    std::env::set_var("VELOCITY_LIMIT_PER_MINUTE", "3");
+    std::env::set_var("RISK_REVIEW_THRESHOLD", "60");
+    std::env::set_var("RISK_DECLINE_THRESHOLD", "90");
+    std::env::set_var("SPEND_HOLD_TTL_SECONDS", "120");
+    std::env::set_var("GATEWAY_RUN_ALL_CHECKS", "true");
+    let config = GatewayConfig::from_env();
+    assert_eq!(
+        config,
+        GatewayConfig {
+            velocity_limit_per_minute: 3,
+            risk: RiskThresholds { review: 60.0, decline: 90.0 },
+            hold_ttl: Duration::seconds(120),
+            mode: PipelineMode::RunAll,
+        }
+    );
+    assert_eq!(CheckPipeline::standard().mode(), PipelineMode::RunAll);
+
+    // Unparseable values fall back to the defaults
+    std::env::set_var("VELOCITY_LIMIT_PER_MINUTE", "lots");
+    std::env::set_var("SPEND_HOLD_TTL_SECONDS", "soon");
+    let config = GatewayConfig::from_env();
+    assert_eq!(config.velocity_limit_per_minute, DEFAULT_VELOCITY_LIMIT_PER_MINUTE);
+    assert_eq!(config.hold_ttl, Duration::seconds(DEFAULT_HOLD_TTL_SECONDS));
+
+    for var in VARS {
+        std::env::remove_var(var);
+    }
+}
+
+#[test]
+fn pipeline_takes_its_mode_from_the_config() {
+    let run_all = GatewayConfig { mode: PipelineMode::RunAll, ..GatewayConfig::default() };
+    assert_eq!(CheckPipeline::from_config(&run_all).mode(), PipelineMode::RunAll);
+    assert_eq!(CheckPipeline::from_config(&GatewayConfig::default()).mode(), PipelineMode::ShortCircuit);
+}
@@ -0,0 +1,70 @@
+//! A decline keeps the checks that passed and reports the first failure's
+//! code, reason and limit.
+
+use rust_decimal::Decimal;
+use security_gateway::{CheckReport, CheckResult, CheckStatus, Decision, DeclineCode, LimitDetail, VerificationResult};
+
+fn result(name: &str, status: CheckStatus) -> CheckResult {
+    CheckResult {
+        name: name.to_string(),
+        status,
+        detail: None,
+        code: None,
+        limit: None,
+        restricted_items: Vec::new(),
+        reviewer: None,
+        risk: None,
+        wallet_hold: None,
+    }
+}
+
+#[test]
+fn decline_reports_the_first_failure_and_keeps_passed_checks() {
+    let per_tx = CheckResult {
+        detail: Some("Amount 150.00 USD exceeds per-transaction limit 100.00 USD".to_string()),
+        code: Some(DeclineCode::PerTxLimit),
+        limit: Some(LimitDetail::new(Decimal::from(100), Decimal::ZERO, Decimal::from(150), Some("USD".to_string()))),
+        ..result("per_tx_limit", CheckStatus::Fail)
+    };
+    let daily = CheckResult {
+        code: Some(DeclineCode::DailyLimit),
+        ..result("daily_limit", CheckStatus::Fail)
+    };
+    let checks = CheckReport {
+        results: vec![
+            result("agent_exists", CheckStatus::Pass),
+            result("agent_active", CheckStatus::Pass),
+            per_tx,
+            daily,
+            result("nonce_unique", CheckStatus::Skip),
+        ],
+    };
+
+    let declined = VerificationResult::declined_by(checks);
+    assert!(!declined.approved);
+    assert_eq!(declined.decision, Decision::Decline);
+    assert_eq!(declined.decline_code, Some(DeclineCode::PerTxLimit));
+    assert!(declined.reason.unwrap().contains("per-transaction limit"));
+    assert!(declined.checks.passed("agent_exists"));
+    assert!(declined.checks.passed("agent_active"));
+
+    let limit = declined.limit.unwrap();
+    assert_eq!(limit.limit, Decimal::from(100));
+    assert_eq!(limit.requested, Decimal::from(150));
+    assert_eq!(limit.remaining, Decimal::from(100));
+
+    let json = serde_json::to_value(&declined.checks).unwrap();
+    assert_eq!(json[2]["code"], "PER_TX_LIMIT");
+    assert_eq!(json[0]["status"], "pass");
+}
+
+#[test]
+fn failures_without_a_code_fall_back_to_check_failed() {
+    let checks = CheckReport {
+        results: vec![result("agent_exists", CheckStatus::Pass), result("custom", CheckStatus::Fail)],
+    };
+
+    let declined = VerificationResult::declined_by(checks);
+    assert_eq!(declined.decline_code, Some(DeclineCode::CheckFailed));
+    assert!(declined.limit.is_none());
+}
@@ -0,0 +1,72 @@
+//! Fraud detectors run over synthetic activity, without Postgres.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use security_gateway::fraud::{self, ActivityRecord, PatternType, Severity};
+use std::str::FromStr;
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap()
+}
+
+fn record(merchant: &str, amount: &str, minutes_ago: i64) -> ActivityRecord {
+    ActivityRecord {
+        merchant_id: merchant.to_string(),
+        amount: Some(Decimal::from_str(amount).unwrap()),
+        created_at: now() - Duration::minutes(minutes_ago),
+    }
+}
+
+fn severity_of(findings: &[fraud::Finding], pattern: PatternType) -> Option<Severity> {
+    findings.iter().find(|f| f.pattern_type == pattern).map(|f| f.severity)
+}
+
+#[test]
+fn ordinary_activity_raises_nothing() {
+    let activity = vec![
+        record("m1", "42.10", 300),
+        record("m2", "17.99", 120),
+        record("m1", "63.45", 5),
+    ];
+    assert!(fraud::detect(&activity, Some(Decimal::from(100)), now()).is_empty());
+}
+
+#[test]
+fn burst_of_tiny_payments_is_card_testing() {
+    let probes: Vec<_> = (0..6).map(|i| record("m1", "0.50", i)).collect();
+    let findings = fraud::detect(&probes, None, now());
+    assert_eq!(severity_of(&findings, PatternType::CardTesting), Some(Severity::High));
+
+    // The same probes spread over an hour are not a burst
+    let spread: Vec<_> = (0..6).map(|i| record("m1", "0.50", i * 12)).collect();
+    assert_eq!(severity_of(&fraud::detect(&spread, None, now()), PatternType::CardTesting), None);
+}
+
+#[test]
+fn many_merchants_within_the_hour_is_hopping() {
+    let activity: Vec<_> = (0..8).map(|i| record(&format!("m{}", i), "25.00", i * 5)).collect();
+    let findings = fraud::detect(&activity, None, now());
+    assert_eq!(severity_of(&findings, PatternType::MerchantHopping), Some(Severity::High));
+}
+
+#[test]
+fn repeated_amounts_just_under_the_limit_are_structuring() {
+    let activity = vec![
+        record("m1", "98.00", 600),
+        record("m2", "99.50", 300),
+        record("m3", "96.00", 1),
+    ];
+    let findings = fraud::detect(&activity, Some(Decimal::from(100)), now());
+    assert_eq!(severity_of(&findings, PatternType::Structuring), Some(Severity::High));
+
+    // Without a per-transaction limit there is nothing to structure around
+    assert_eq!(severity_of(&fraud::detect(&activity, None, now()), PatternType::Structuring), None);
+}
+
+#[test]
+fn mostly_round_amounts_are_flagged_at_low_severity() {
+    let mut activity: Vec<_> = (0..4).map(|i| record("m1", "200.00", i * 60)).collect();
+    activity.push(record("m1", "37.20", 10));
+    let findings = fraud::detect(&activity, None, now());
+    assert_eq!(severity_of(&findings, PatternType::RoundAmounts), Some(Severity::Low));
+}
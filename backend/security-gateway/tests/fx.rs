@@ -0,0 +1,95 @@
+//! Static FX table: direct, inverse and cross rates, conversion rounding, and
+//! the ledger-currency amounts checks compare against.
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use security_gateway::checks::CheckInput;
+use security_gateway::fx::{self, RateProvider, StaticRateProvider};
+use security_gateway::{CheckStatus, Database, DeclineCode, Money, Protocol, SecurityContext};
+use sqlx::postgres::PgPoolOptions;
+use std::str::FromStr;
+
+const RATES: &str = r#"{"base": "USD", "as_of": "2026-10-01T00:00:00Z", "rates": {"EUR": "0.9", "JPY": 150, "GBP": "0.75"}}"#;
+
+fn provider() -> StaticRateProvider {
+    StaticRateProvider::from_json(RATES, "test").unwrap()
+}
+
+#[tokio::test]
+async fn converts_into_the_base_currency() {
+    let rates = provider();
+
+    let conversion = fx::to_base(&rates, &Money::parse("90.00", "EUR").unwrap(), "USD").await.unwrap();
+    assert_eq!(conversion.base, Money::parse("100.00", "USD").unwrap());
+    assert_eq!(conversion.rate.from, "EUR");
+    assert_eq!(conversion.rate.source, "test");
+
+    // EUR -> JPY crosses through USD: 150 / 0.9
+    let yen = fx::to_base(&rates, &Money::parse("9.00", "EUR").unwrap(), "jpy").await.unwrap();
+    assert_eq!(yen.base, Money::new(1500, "JPY").unwrap());
+}
+
+#[tokio::test]
+async fn rounds_to_the_target_minor_unit() {
+    let rates = provider();
+
+    let snapshot = rates.rate("JPY", "USD").await.unwrap();
+    assert_eq!(snapshot.rate, Decimal::from_str("0.0066666667").unwrap());
+    assert_eq!(snapshot.convert(&Money::new(1000, "JPY").unwrap()).unwrap(), Money::parse("6.67", "USD").unwrap());
+}
+
+#[tokio::test]
+async fn same_currency_is_identity_and_unknown_pairs_fail() {
+    let rates = StaticRateProvider::empty();
+
+    let amount = Money::parse("12.34", "CHF").unwrap();
+    let conversion = fx::to_base(&rates, &amount, "CHF").await.unwrap();
+    assert!(conversion.is_identity());
+    assert_eq!(conversion.base, amount);
+
+    assert!(fx::to_base(&rates, &amount, "USD").await.is_err());
+    assert!(provider().rate("CHF", "USD").await.is_err());
+}
+
+fn paying(amount: Money) -> SecurityContext {
+    SecurityContext {
+        agent_id: "agent_test".to_string(),
+        agent_owner: None,
+        foundational_model: None,
+        protocol: Protocol::MCP,
+        transaction_id: "tx_test".to_string(),
+        currency: amount.currency.clone(),
+        amount: Some(amount),
+        merchant_id: "merchant_test".to_string(),
+        merchant_name: None,
+        timestamp: Utc::now(),
+        user_id: None,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+        payment_method_type: None,
+        payment_token: None,
+        signature: None,
+        nonce: "nonce_test".to_string(),
+        risk_score: None,
+        metadata: Default::default(),
+        raw_request: serde_json::Value::Null,
+    }
+}
+
+#[tokio::test]
+async fn checks_compare_foreign_amounts_in_the_ledger_currency() {
+    // Converting never touches the database
+    let db = Database { pool: PgPoolOptions::new().connect_lazy("postgresql://localhost/unused").unwrap() };
+    let rates = provider();
+
+    let ctx = paying(Money::parse("90.00", "EUR").unwrap());
+    let input = CheckInput { ctx: &ctx, db: &db, agent: None, tier: None, conversion: None, rates: &rates, under_review: false };
+    assert_eq!(input.ledger_amount().await.unwrap(), Some(Money::parse("100.00", "USD").unwrap()));
+
+    let ctx = paying(Money::parse("12.34", "CHF").unwrap());
+    let input = CheckInput { ctx: &ctx, db: &db, agent: None, tier: None, conversion: None, rates: &rates, under_review: false };
+    let unconvertible = input.ledger_amount().await.unwrap_err();
+    assert_eq!(unconvertible.status, CheckStatus::Fail);
+    assert_eq!(unconvertible.code, Some(DeclineCode::CurrencyUnsupported));
+}
@@ -0,0 +1,81 @@
+//! Journals must balance before they are posted, and reconciliation flags a
+//! derived balance that no longer matches the ledger.
+//!
+//! The reconciliation test needs a Postgres with docs/schema.sql applied.
+
+use rust_decimal::Decimal;
+use security_gateway::ledger::{self, AccountType, Direction, Journal, Posting};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn posting(account: AccountType, direction: Direction, amount: i64) -> Posting {
+    Posting { account, owner: Some("agent_test".to_string()), direction, amount: Decimal::from(amount) }
+}
+
+fn journal(postings: Vec<Posting>) -> Journal {
+    Journal { kind: "topup", reference_id: None, description: "test".to_string(), postings }
+}
+
+#[test]
+fn built_journals_balance() {
+    let transaction_id = Uuid::new_v4();
+    let merchant_id = Uuid::new_v4();
+    assert!(Journal::topup("agent_test", Decimal::from(50), None).validate().is_ok());
+    assert!(Journal::completion("agent_test", merchant_id, Decimal::from(30), Decimal::new(45, 2), transaction_id).validate().is_ok());
+    assert!(Journal::refund("agent_test", merchant_id, Decimal::from(30), transaction_id).validate().is_ok());
+}
+
+#[test]
+fn unbalanced_and_negative_journals_are_rejected() {
+    let unbalanced = journal(vec![
+        posting(AccountType::ExternalFunding, Direction::Debit, 50),
+        posting(AccountType::AgentWallet, Direction::Credit, 40),
+    ]);
+    assert!(unbalanced.validate().unwrap_err().to_string().contains("Unbalanced"));
+
+    // Balanced, but it would move money backwards
+    let negative = journal(vec![
+        posting(AccountType::ExternalFunding, Direction::Debit, -50),
+        posting(AccountType::AgentWallet, Direction::Credit, -50),
+    ]);
+    assert!(negative.validate().unwrap_err().to_string().contains("Negative"));
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn reconcile_flags_and_then_clears_balance_drift() {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+    let pool = PgPool::connect(&url).await.expect("connect to DATABASE_URL");
+
+    // A wallet credited without a journal
+    let agent_id = format!("test-ledger-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO agents (id, owner_company, owner_email, protocol, balance, remaining_balance)
+         VALUES ($1, 'Ledger Test', 'ledger-test@example.com', 'MCP', 50, 50)"
+    )
+    .bind(&agent_id)
+    .execute(&pool)
+    .await
+    .expect("insert test agent");
+
+    let drift = ledger::reconcile(&pool).await.unwrap();
+    let flagged = drift.iter().find(|d| d.subject_id == agent_id).expect("drift is reported");
+    assert_eq!(flagged.subject_type, "agent_balance");
+    assert_eq!((flagged.expected, flagged.actual), (Decimal::ZERO, Decimal::from(50)));
+
+    let open = || {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM ledger_drift WHERE subject_id = $1 AND resolved_at IS NULL")
+            .bind(&agent_id)
+            .fetch_one(&pool)
+    };
+    assert_eq!(open().await.unwrap(), 1);
+
+    // Posting the missing top-up brings the ledger in line and resolves the flag
+    let mut conn = pool.acquire().await.unwrap();
+    ledger::post(&mut conn, &Journal::topup(&agent_id, Decimal::from(50), None)).await.unwrap();
+    let drift = ledger::reconcile(&pool).await.unwrap();
+    assert!(drift.iter().all(|d| d.subject_id != agent_id));
+    assert_eq!(open().await.unwrap(), 0);
+
+    sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+}
@@ -0,0 +1,15 @@
+//! Merchant blocks and allow-lists.
+
+use security_gateway::db::MerchantAccess;
+
+#[test]
+fn allow_list_restricts_and_block_overrides() {
+    assert!(MerchantAccess::default().allows("agent_a"));
+
+    let listed = MerchantAccess { blocked_reason: None, allowed_agents: Some(vec!["agent_a".to_string()]) };
+    assert!(listed.allows("agent_a"));
+    assert!(!listed.allows("agent_b"));
+
+    let blocked = MerchantAccess { blocked_reason: Some("chargebacks".to_string()), ..listed };
+    assert!(!blocked.allows("agent_a"));
+}
@@ -0,0 +1,66 @@
+//! Amounts convert between major units, minor units and the wire format exactly.
+
+use rust_decimal::Decimal;
+use security_gateway::Money;
+use std::str::FromStr;
+
+#[test]
+fn decimal_round_trips_through_minor_units() {
+    let money = Money::parse("0.29", "usd").unwrap();
+    assert_eq!(money.minor_units, 29);
+    assert_eq!(money.currency, "USD");
+    assert_eq!(money.to_decimal(), Decimal::from_str("0.29").unwrap());
+
+    let yen = Money::parse("1500", "JPY").unwrap();
+    assert_eq!(yen.minor_units, 1500);
+
+    let dinar = Money::parse("1.005", "KWD").unwrap();
+    assert_eq!(dinar.minor_units, 1005);
+}
+
+#[test]
+fn sub_minor_precision_is_rejected() {
+    assert!(Money::parse("10.001", "USD").is_err());
+    assert!(Money::parse("1.5", "JPY").is_err());
+    assert!(Money::new(100, "US").is_err());
+}
+
+#[test]
+fn wire_format_is_minor_units_and_currency() {
+    let money = Money::new(4599, "EUR").unwrap();
+    let json = serde_json::to_value(&money).unwrap();
+    assert_eq!(json, serde_json::json!({"minor_units": 4599, "currency": "EUR"}));
+
+    let parsed: Money = serde_json::from_value(serde_json::json!({"minor_units": 4599, "currency": "eur"})).unwrap();
+    assert_eq!(parsed, money);
+    assert!(serde_json::from_value::<Money>(serde_json::json!({"minor_units": 1, "currency": "EURO"})).is_err());
+}
+
+#[test]
+fn stored_amounts_round_to_the_currency() {
+    let money = Money::from_stored(Decimal::from_str("12.345").unwrap(), "USD");
+    assert_eq!(money.minor_units, 1235);
+    assert_eq!(money.to_string(), "12.35 USD");
+}
+
+#[test]
+fn json_amounts_parse_from_numbers_and_strings() {
+    assert_eq!(Money::parse_json(&serde_json::json!(45.5), "USD").unwrap().minor_units, 4550);
+    assert_eq!(Money::parse_json(&serde_json::json!("45.50"), "USD").unwrap().minor_units, 4550);
+    assert!(Money::parse_json(&serde_json::json!(null), "USD").is_err());
+    assert!(Money::parse_json(&serde_json::json!("45.505"), "USD").is_err());
+}
+
+#[test]
+fn amounts_out_of_range_fail_or_saturate_instead_of_panicking() {
+    const DECIMAL_MAX: &str = "79228162514264337593543950335";
+
+    assert!(Money::parse(DECIMAL_MAX, "USD").is_err());
+    assert!(Money::parse(&format!("-{}", DECIMAL_MAX), "KWD").is_err());
+    // Fits a Decimal once scaled, but not an i64
+    assert!(Money::parse("100000000000000000000", "JPY").is_err());
+
+    let max = Decimal::from_str(DECIMAL_MAX).unwrap();
+    assert_eq!(Money::from_stored(max, "USD").minor_units, i64::MAX);
+    assert_eq!(Money::from_stored(-max, "USD").minor_units, i64::MIN);
+}
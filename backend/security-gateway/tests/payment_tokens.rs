@@ -0,0 +1,130 @@
+//! Shared payment tokens are honoured only as their issuer registered them:
+//! for the agent they were delegated to and up to their allowance.
+//!
+//! Needs a Postgres with docs/schema.sql applied.
+
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use security_gateway::checks::{CheckPipeline, PaymentTokenCheck, PipelineMode};
+use security_gateway::fx::StaticRateProvider;
+use security_gateway::{
+    payment_token_fingerprint, CheckStatus, Database, DeclineCode, DelegatedAllowance, Money,
+    PaymentMethodType, Protocol, SecurityContext,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn connect() -> Database {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+    Database { pool: PgPool::connect(&url).await.expect("connect to DATABASE_URL") }
+}
+
+async fn create_owner(pool: &PgPool) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO users (email, password_hash) VALUES ($1, 'unused') RETURNING id"
+    )
+    .bind(format!("token-test-{}@example.com", Uuid::new_v4()))
+    .fetch_one(pool)
+    .await
+    .expect("insert test user")
+}
+
+async fn create_agent(pool: &PgPool, owner: Uuid) -> String {
+    let agent_id = format!("test-token-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO agents (id, user_id, owner_company, owner_email, protocol)
+         VALUES ($1, $2, 'Token Test', 'token-test@example.com', 'ACP')"
+    )
+    .bind(&agent_id)
+    .bind(owner)
+    .execute(pool)
+    .await
+    .expect("insert test agent");
+    agent_id
+}
+
+fn paying(agent_id: &str, token: &str, amount: &str) -> SecurityContext {
+    let amount = Money::parse(amount, "USD").unwrap();
+    SecurityContext {
+        agent_id: agent_id.to_string(),
+        agent_owner: None,
+        foundational_model: None,
+        protocol: Protocol::ACP,
+        transaction_id: format!("tx_{}", Uuid::new_v4()),
+        currency: amount.currency.clone(),
+        amount: Some(amount),
+        merchant_id: "merchant_test".to_string(),
+        merchant_name: None,
+        timestamp: Utc::now(),
+        user_id: None,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+        payment_method_type: Some(PaymentMethodType::SharedPaymentToken),
+        payment_token: Some(token.to_string()),
+        signature: None,
+        nonce: Uuid::new_v4().to_string(),
+        risk_score: None,
+        metadata: Default::default(),
+        raw_request: serde_json::Value::Null,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn token_is_charged_against_its_registered_allowance() {
+    let db = connect().await;
+    let owner = create_owner(&db.pool).await;
+    let agent_id = create_agent(&db.pool, owner).await;
+    let other_agent = create_agent(&db.pool, owner).await;
+
+    let token = format!("spt_{}", Uuid::new_v4());
+    let fingerprint = payment_token_fingerprint(&token);
+    let allowance = DelegatedAllowance {
+        max_amount: Money::parse("50.00", "USD").unwrap(),
+        merchant_id: None,
+        checkout_session_id: None,
+        expires_at: Utc::now() + Duration::hours(1),
+        single_use: false,
+    };
+    assert!(db.register_payment_token(&fingerprint, &agent_id, &allowance, owner).await.unwrap());
+    // The first registration wins; a later one cannot raise the allowance
+    assert!(!db.register_payment_token(&fingerprint, &agent_id, &allowance, owner).await.unwrap());
+
+    let pipeline = CheckPipeline::new(PipelineMode::ShortCircuit).with_check(PaymentTokenCheck);
+    let rates = StaticRateProvider::empty();
+    let check = |ctx: SecurityContext| {
+        let (db, pipeline, rates) = (&db, &pipeline, &rates);
+        async move {
+            let report = pipeline.run(&ctx, db, rates, None).await.unwrap();
+            report.get("payment_token_valid").cloned().unwrap()
+        }
+    };
+
+    assert_eq!(check(paying(&agent_id, &token, "30.00")).await.status, CheckStatus::Pass);
+
+    let over = check(paying(&agent_id, &token, "30.00")).await;
+    assert_eq!(over.status, CheckStatus::Fail);
+    assert_eq!(over.code, Some(DeclineCode::PaymentTokenInvalid));
+    let limit = over.limit.unwrap();
+    assert_eq!(limit.used, Decimal::from(30));
+    assert_eq!(limit.remaining, Decimal::from(20));
+
+    assert_eq!(check(paying(&agent_id, &token, "20.00")).await.status, CheckStatus::Pass);
+
+    let stolen = check(paying(&other_agent, &token, "1.00")).await;
+    assert_eq!(stolen.status, CheckStatus::Fail);
+    let unregistered = check(paying(&agent_id, "spt_never_registered", "1.00")).await;
+    assert_eq!(unregistered.status, CheckStatus::Fail);
+
+    let used: Decimal = sqlx::query_scalar("SELECT amount_used FROM delegated_payment_tokens WHERE token_fingerprint = $1")
+        .bind(&fingerprint)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(used, Decimal::from(50));
+
+    sqlx::query("DELETE FROM delegated_payment_tokens WHERE token_fingerprint = $1").bind(&fingerprint).execute(&db.pool).await.unwrap();
+    sqlx::query("DELETE FROM agents WHERE user_id = $1").bind(owner).execute(&db.pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(owner).execute(&db.pool).await.unwrap();
+}
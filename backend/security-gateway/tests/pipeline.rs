@@ -0,0 +1,106 @@
+//! Pipeline modes: short-circuit stops at the first failure, run-all keeps
+//! evaluating but never runs a side-effecting check for a declined request.
+
+use anyhow::Result;
+use chrono::Utc;
+use security_gateway::checks::{CheckInput, CheckOutcome, CheckPipeline, PipelineMode, SecurityCheck};
+use security_gateway::fx::StaticRateProvider;
+use security_gateway::{CheckStatus, Database, DeclineCode, Protocol, SecurityContext};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A check with a fixed outcome that counts how often it ran
+struct Fixed {
+    name: &'static str,
+    fails: bool,
+    side_effects: bool,
+    runs: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl SecurityCheck for Fixed {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn has_side_effects(&self) -> bool {
+        self.side_effects
+    }
+
+    async fn run(&self, _input: &mut CheckInput<'_>) -> Result<CheckOutcome> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        Ok(if self.fails {
+            CheckOutcome::fail(DeclineCode::PerTxLimit, "over the limit")
+        } else {
+            CheckOutcome::pass()
+        })
+    }
+}
+
+fn context() -> SecurityContext {
+    SecurityContext {
+        agent_id: "agent_test".to_string(),
+        agent_owner: None,
+        foundational_model: None,
+        protocol: Protocol::MCP,
+        transaction_id: "tx_test".to_string(),
+        currency: "USD".to_string(),
+        amount: None,
+        merchant_id: "merchant_test".to_string(),
+        merchant_name: None,
+        timestamp: Utc::now(),
+        user_id: None,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+        payment_method_type: None,
+        payment_token: None,
+        signature: None,
+        nonce: "nonce_test".to_string(),
+        risk_score: None,
+        metadata: Default::default(),
+        raw_request: serde_json::Value::Null,
+    }
+}
+
+/// Runs failing, plain and side-effecting checks in that order; returns the
+/// report's statuses and how often the two later checks ran
+async fn run(mode: PipelineMode) -> (Vec<(String, CheckStatus)>, usize, usize) {
+    // None of these checks touch the database
+    let db = Database { pool: PgPoolOptions::new().connect_lazy("postgresql://localhost/unused").unwrap() };
+    let (failing, plain, writing) = (Arc::default(), Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+
+    let pipeline = CheckPipeline::new(mode)
+        .with_check(Fixed { name: "limit", fails: true, side_effects: false, runs: failing })
+        .with_check(Fixed { name: "velocity", fails: false, side_effects: false, runs: plain.clone() })
+        .with_check(Fixed { name: "nonce_unique", fails: false, side_effects: true, runs: writing.clone() });
+
+    let report = pipeline.run(&context(), &db, &StaticRateProvider::empty(), None).await.unwrap();
+    let statuses = report.results.iter().map(|r| (r.name.clone(), r.status)).collect();
+    (statuses, plain.load(Ordering::SeqCst), writing.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn short_circuit_stops_at_the_first_failure() {
+    let (statuses, plain, writing) = run(PipelineMode::ShortCircuit).await;
+
+    assert_eq!((plain, writing), (0, 0));
+    assert_eq!(statuses, vec![
+        ("limit".to_string(), CheckStatus::Fail),
+        ("velocity".to_string(), CheckStatus::Skip),
+        ("nonce_unique".to_string(), CheckStatus::Skip),
+    ]);
+}
+
+#[tokio::test]
+async fn run_all_skips_only_side_effecting_checks_after_a_failure() {
+    let (statuses, plain, writing) = run(PipelineMode::RunAll).await;
+
+    assert_eq!((plain, writing), (1, 0));
+    assert_eq!(statuses, vec![
+        ("limit".to_string(), CheckStatus::Fail),
+        ("velocity".to_string(), CheckStatus::Pass),
+        ("nonce_unique".to_string(), CheckStatus::Skip),
+    ]);
+}
@@ -0,0 +1,137 @@
+//! Spending policies evaluated against stub facts, without Postgres.
+
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+use security_gateway::policy::{self, Effect, PolicyDocument, PolicyFacts, PolicyInput};
+use security_gateway::Money;
+
+struct StubFacts {
+    category: Option<&'static str>,
+    spent: Decimal,
+}
+
+#[async_trait::async_trait]
+impl PolicyFacts for StubFacts {
+    async fn merchant_category(&self, _merchant_id: &str) -> Result<Option<String>> {
+        Ok(self.category.map(str::to_string))
+    }
+
+    async fn policy_spending(
+        &self,
+        _agent_id: &str,
+        _since: DateTime<Utc>,
+        _merchants: &[String],
+        _categories: &[String],
+    ) -> Result<Decimal> {
+        Ok(self.spent)
+    }
+}
+
+const NO_HISTORY: StubFacts = StubFacts { category: None, spent: Decimal::ZERO };
+
+const POLICY: &str = r#"
+rules:
+  - name: office hours
+    when: { not: { days: [mon, tue, wed, thu, fri], hours: { from: 9, to: 17 } } }
+    effect: deny
+  - name: large bronze payments
+    when: { agent_tiers: [bronze], amount_above: 500 }
+    effect: review
+  - name: weekly travel cap
+    when: { merchant_categories: [travel] }
+    limit: { amount: 1000, period: week }
+    effect: deny
+"#;
+
+fn input<'a>(amount: &'a Money, tier: Option<&'a str>, now: DateTime<Utc>) -> PolicyInput<'a> {
+    PolicyInput {
+        agent_id: "agent-1",
+        merchant_id: "merchant-1",
+        amount: Some(amount),
+        tier,
+        timezone: chrono_tz::Europe::Paris,
+        now,
+    }
+}
+
+/// Wednesday 14 October 2026 at 12:00 in Paris
+fn weekday_noon() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 14, 10, 0, 0).unwrap()
+}
+
+async fn effect_of(facts: &StubFacts, input: &PolicyInput<'_>) -> Option<Effect> {
+    let policies = vec![("default".to_string(), PolicyDocument::parse(POLICY).unwrap())];
+    policy::evaluate(&policies, facts, input).await.unwrap().effect()
+}
+
+#[test]
+fn yaml_and_json_documents_agree() {
+    let yaml = PolicyDocument::parse(POLICY).unwrap();
+    let json = PolicyDocument::from_json(serde_json::to_value(&yaml).unwrap()).unwrap();
+    assert_eq!(yaml, json);
+    assert_eq!(PolicyDocument::parse(&serde_json::to_string(&yaml).unwrap()).unwrap(), yaml);
+}
+
+#[test]
+fn invalid_documents_are_rejected() {
+    let duplicate = "rules: [{name: a, effect: deny}, {name: a, effect: review}]";
+    assert!(PolicyDocument::parse(duplicate).is_err());
+    assert!(PolicyDocument::parse("rules: [{name: a, effect: block}]").is_err());
+    assert!(PolicyDocument::parse("rules: [{name: a, when: {hours: {from: 9, to: 9}}, effect: deny}]").is_err());
+    assert!(PolicyDocument::parse("rules: [{name: a, limit: {amount: 0, period: day}, effect: deny}]").is_err());
+    assert!(PolicyDocument::parse("rules: [{name: a, when: {weekdays: [mon]}, effect: deny}]").is_err());
+}
+
+#[tokio::test]
+async fn payments_outside_office_hours_are_denied() {
+    let amount = Money::parse("20.00", "EUR").unwrap();
+    assert_eq!(effect_of(&NO_HISTORY, &input(&amount, None, weekday_noon())).await, None);
+
+    // 20:00 in Paris
+    let evening = Utc.with_ymd_and_hms(2026, 10, 14, 18, 0, 0).unwrap();
+    assert_eq!(effect_of(&NO_HISTORY, &input(&amount, None, evening)).await, Some(Effect::Deny));
+
+    // Saturday noon
+    let weekend = Utc.with_ymd_and_hms(2026, 10, 17, 10, 0, 0).unwrap();
+    assert_eq!(effect_of(&NO_HISTORY, &input(&amount, None, weekend)).await, Some(Effect::Deny));
+}
+
+#[tokio::test]
+async fn large_payments_by_bronze_agents_go_to_review() {
+    let large = Money::parse("750.00", "EUR").unwrap();
+    let small = Money::parse("75.00", "EUR").unwrap();
+
+    assert_eq!(effect_of(&NO_HISTORY, &input(&large, Some("bronze"), weekday_noon())).await, Some(Effect::Review));
+    assert_eq!(effect_of(&NO_HISTORY, &input(&small, Some("bronze"), weekday_noon())).await, None);
+    assert_eq!(effect_of(&NO_HISTORY, &input(&large, Some("gold"), weekday_noon())).await, None);
+    assert_eq!(effect_of(&NO_HISTORY, &input(&large, None, weekday_noon())).await, None);
+}
+
+#[tokio::test]
+async fn weekly_cap_counts_earlier_spending() {
+    let amount = Money::parse("300.00", "EUR").unwrap();
+    let travel = |spent: i64| StubFacts { category: Some("travel"), spent: Decimal::from(spent) };
+
+    assert_eq!(effect_of(&travel(600), &input(&amount, None, weekday_noon())).await, None);
+    assert_eq!(effect_of(&travel(800), &input(&amount, None, weekday_noon())).await, Some(Effect::Deny));
+
+    // The cap only covers travel merchants
+    let books = StubFacts { category: Some("books"), spent: Decimal::from(800) };
+    assert_eq!(effect_of(&books, &input(&amount, None, weekday_noon())).await, None);
+}
+
+#[tokio::test]
+async fn deny_outranks_review_and_every_rule_is_explained() {
+    let amount = Money::parse("750.00", "EUR").unwrap();
+    let evening = Utc.with_ymd_and_hms(2026, 10, 14, 18, 0, 0).unwrap();
+    let policies = vec![("default".to_string(), PolicyDocument::parse(POLICY).unwrap())];
+
+    let evaluation = policy::evaluate(&policies, &NO_HISTORY, &input(&amount, Some("bronze"), evening))
+        .await
+        .unwrap();
+    assert_eq!(evaluation.rules.len(), 3);
+    assert!(evaluation.rules.iter().all(|r| !r.reasons.is_empty()));
+    assert_eq!(evaluation.decisive().unwrap().rule, "office hours");
+    assert_eq!(evaluation.effect(), Some(Effect::Deny));
+}
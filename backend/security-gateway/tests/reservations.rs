@@ -0,0 +1,133 @@
+//! Spend holds count against the daily budget until they are released,
+//! settled or expire.
+//!
+//! Needs a Postgres with docs/schema.sql applied.
+
+use chrono::Duration;
+use rust_decimal::Decimal;
+use security_gateway::reservations::{self, HoldOutcome, HoldRequest, ReservationStore};
+use security_gateway::{DeclineCode, Money};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn connect() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+    PgPool::connect(&url).await.expect("connect to DATABASE_URL")
+}
+
+async fn create_agent(pool: &PgPool, daily_limit: Decimal) -> String {
+    let agent_id = format!("test-hold-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO agents (id, owner_company, owner_email, protocol, spending_limit_daily)
+         VALUES ($1, 'Hold Test', 'hold-test@example.com', 'MCP', $2)"
+    )
+    .bind(&agent_id)
+    .bind(daily_limit)
+    .execute(pool)
+    .await
+    .expect("insert test agent");
+    agent_id
+}
+
+async fn status(pool: &PgPool, hold_id: Uuid) -> String {
+    sqlx::query_scalar("SELECT status FROM spend_reservations WHERE id = $1")
+        .bind(hold_id)
+        .fetch_one(pool)
+        .await
+        .expect("read hold status")
+}
+
+async fn hold(store: &ReservationStore, agent_id: &str, amount: &str, transaction_id: Uuid, ttl: Duration) -> HoldOutcome {
+    let nonce = Uuid::new_v4().to_string();
+    let amount = Money::parse(amount, "USD").unwrap();
+    let request = HoldRequest { agent_id, nonce: &nonce, transaction_id: Some(transaction_id), amount: &amount, ttl };
+    store.hold(&request).await.expect("place hold")
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn holds_count_until_released_settled_or_expired() {
+    let pool = connect().await;
+    let store = ReservationStore::new(pool.clone());
+    let agent_id = create_agent(&pool, Decimal::from(100)).await;
+    let ttl = Duration::minutes(15);
+
+    let first = Uuid::new_v4();
+    let HoldOutcome::Held(first_hold) = hold(&store, &agent_id, "60.00", first, ttl).await else {
+        panic!("first hold fits the budget");
+    };
+
+    // The live hold counts, so a second one would overshoot
+    let HoldOutcome::Exceeded { code, limit, .. } = hold(&store, &agent_id, "50.00", Uuid::new_v4(), ttl).await else {
+        panic!("second hold exceeds the budget");
+    };
+    assert_eq!(code, DeclineCode::DailyLimit);
+    assert_eq!(limit.used, Decimal::from(60));
+    assert_eq!(limit.remaining, Decimal::from(40));
+
+    // Releasing frees the budget; releasing twice is a no-op
+    assert!(store.release_transaction(first).await.unwrap());
+    assert!(!store.release_transaction(first).await.unwrap());
+    assert_eq!(status(&pool, first_hold).await, "released");
+
+    let second = Uuid::new_v4();
+    let HoldOutcome::Held(second_hold) = hold(&store, &agent_id, "50.00", second, ttl).await else {
+        panic!("released budget can be held again");
+    };
+    let mut conn = pool.acquire().await.unwrap();
+    assert!(reservations::settle_transaction(&mut conn, second).await.unwrap());
+    assert_eq!(status(&pool, second_hold).await, "settled");
+    // A settled hold cannot be released afterwards
+    assert!(!store.release_transaction(second).await.unwrap());
+
+    // A lapsed hold stops counting and is swept up as released
+    let HoldOutcome::Held(lapsed) = hold(&store, &agent_id, "90.00", Uuid::new_v4(), Duration::zero()).await else {
+        panic!("nothing completed, so the budget is free");
+    };
+    assert!(matches!(hold(&store, &agent_id, "90.00", Uuid::new_v4(), ttl).await, HoldOutcome::Held(_)));
+    assert!(store.release_expired().await.unwrap() >= 1);
+    assert_eq!(status(&pool, lapsed).await, "released");
+
+    sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn approved_payments_count_once_their_hold_is_settled() {
+    let pool = connect().await;
+    let store = ReservationStore::new(pool.clone());
+    let agent_id = create_agent(&pool, Decimal::from(100)).await;
+    let ttl = Duration::minutes(15);
+
+    // Parked for review under its spend hold
+    let parked: Uuid = sqlx::query_scalar(
+        "INSERT INTO transactions (agent_id, merchant_id, protocol, amount, currency, status, nonce)
+         VALUES ($1, $2, 'MCP', 60.00, 'USD', 'pending_review', $3)
+         RETURNING id"
+    )
+    .bind(&agent_id)
+    .bind(Uuid::new_v4())
+    .bind(Uuid::new_v4().to_string())
+    .fetch_one(&pool)
+    .await
+    .expect("insert parked transaction");
+    assert!(matches!(hold(&store, &agent_id, "60.00", parked, ttl).await, HoldOutcome::Held(_)));
+
+    // Approved as the review does it, but not yet accepted by the merchant
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("UPDATE transactions SET status = 'approved' WHERE id = $1").bind(parked).execute(&mut *tx).await.unwrap();
+    assert!(reservations::settle_transaction(&mut tx, parked).await.unwrap());
+    tx.commit().await.unwrap();
+
+    let HoldOutcome::Exceeded { limit, .. } = hold(&store, &agent_id, "50.00", Uuid::new_v4(), ttl).await else {
+        panic!("the approved payment still uses the budget");
+    };
+    assert_eq!(limit.used, Decimal::from(60));
+
+    // Declined when it was finally sent: the budget is free again
+    sqlx::query("UPDATE transactions SET status = 'declined' WHERE id = $1").bind(parked).execute(&pool).await.unwrap();
+    assert!(matches!(hold(&store, &agent_id, "50.00", Uuid::new_v4(), ttl).await, HoldOutcome::Held(_)));
+
+    sqlx::query("DELETE FROM transactions WHERE id = $1").bind(parked).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+}
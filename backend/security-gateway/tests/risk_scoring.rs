@@ -0,0 +1,155 @@
+//! Risk features scored against a fixed history, without Postgres.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use security_gateway::{
+    AmountStats, Decision, Money, Protocol, RiskFeature, RiskHistory, RiskScorer, RiskThresholds, SecurityContext,
+};
+
+struct FixedHistory {
+    now: DateTime<Utc>,
+    created_at: Option<DateTime<Utc>>,
+    last_hour: i64,
+    last_day: i64,
+    merchant_payments: i64,
+    stats: AmountStats,
+    by_hour: [i64; 24],
+    trust_score: Option<i32>,
+}
+
+impl FixedHistory {
+    /// An established agent with steady ~$50 payments during working hours
+    fn established(now: DateTime<Utc>) -> Self {
+        let mut by_hour = [0; 24];
+        by_hour[9..18].fill(10);
+        Self {
+            now,
+            created_at: Some(now - Duration::days(200)),
+            last_hour: 1,
+            last_day: 4,
+            merchant_payments: 12,
+            stats: AmountStats { count: 90, mean: Decimal::from(50), stddev: Decimal::from(10) },
+            by_hour,
+            trust_score: Some(80),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RiskHistory for FixedHistory {
+    async fn agent_created_at(&self, _agent_id: &str) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.created_at)
+    }
+
+    async fn transactions_since(&self, _agent_id: &str, since: DateTime<Utc>) -> Result<i64> {
+        Ok(if self.now - since > Duration::hours(2) { self.last_day } else { self.last_hour })
+    }
+
+    async fn merchant_payment_count(&self, _agent_id: &str, _merchant_id: &str) -> Result<i64> {
+        Ok(self.merchant_payments)
+    }
+
+    async fn amount_stats(&self, _agent_id: &str, _since: DateTime<Utc>) -> Result<AmountStats> {
+        Ok(self.stats)
+    }
+
+    async fn hourly_distribution(&self, _agent_id: &str, _since: DateTime<Utc>) -> Result<[i64; 24]> {
+        Ok(self.by_hour)
+    }
+
+    async fn merchant_trust_score(&self, _merchant_id: &str) -> Result<Option<i32>> {
+        Ok(self.trust_score)
+    }
+}
+
+fn context() -> SecurityContext {
+    SecurityContext {
+        agent_id: "agent_test".to_string(),
+        agent_owner: None,
+        foundational_model: None,
+        protocol: Protocol::MCP,
+        transaction_id: "tx_test".to_string(),
+        amount: None,
+        currency: "USD".to_string(),
+        merchant_id: "merchant_test".to_string(),
+        merchant_name: None,
+        timestamp: Utc::now(),
+        user_id: None,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+        payment_method_type: None,
+        payment_token: None,
+        signature: None,
+        nonce: "nonce_test".to_string(),
+        risk_score: None,
+        metadata: Default::default(),
+        raw_request: serde_json::Value::Null,
+    }
+}
+
+fn points(factors: &[security_gateway::RiskFactor], feature: RiskFeature) -> f64 {
+    factors.iter().find(|f| f.feature == feature).map(|f| f.points).unwrap()
+}
+
+#[tokio::test]
+async fn typical_payment_from_established_agent_scores_zero() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 11, 30, 0).unwrap();
+    let amount = Money::parse("55.00", "USD").unwrap();
+
+    let risk = RiskScorer::assess(&FixedHistory::established(now), &context(), Some(&amount), now).await.unwrap();
+    assert_eq!(risk.score, 0.0);
+    assert_eq!(risk.factors.len(), 7);
+}
+
+#[tokio::test]
+async fn each_anomaly_reports_its_own_contribution() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 3, 0, 0).unwrap();
+    let history = FixedHistory {
+        created_at: Some(now - Duration::hours(6)),
+        last_hour: 12,
+        merchant_payments: 0,
+        trust_score: Some(10),
+        ..FixedHistory::established(now)
+    };
+    // 4.5 standard deviations above the mean
+    let amount = Money::parse("95.00", "USD").unwrap();
+
+    let risk = RiskScorer::assess(&history, &context(), Some(&amount), now).await.unwrap();
+    assert_eq!(points(&risk.factors, RiskFeature::AgentAge), 20.0);
+    assert_eq!(points(&risk.factors, RiskFeature::HourlyVelocity), 10.0);
+    assert_eq!(points(&risk.factors, RiskFeature::DailyVelocity), 0.0);
+    assert_eq!(points(&risk.factors, RiskFeature::NewMerchant), 10.0);
+    assert_eq!(points(&risk.factors, RiskFeature::AmountDeviation), 25.0);
+    assert_eq!(points(&risk.factors, RiskFeature::TimeOfDay), 10.0);
+    assert_eq!(points(&risk.factors, RiskFeature::MerchantTrust), 10.0);
+    assert_eq!(risk.score, 85.0);
+}
+
+#[tokio::test]
+async fn thin_history_falls_back_to_absolute_amount() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 11, 0, 0).unwrap();
+    let history = FixedHistory {
+        stats: AmountStats { count: 2, mean: Decimal::from(20), stddev: Decimal::ONE },
+        by_hour: [0; 24],
+        ..FixedHistory::established(now)
+    };
+    let amount = Money::parse("750.00", "USD").unwrap();
+
+    let risk = RiskScorer::assess(&history, &context(), Some(&amount), now).await.unwrap();
+    assert_eq!(points(&risk.factors, RiskFeature::AmountDeviation), 10.0);
+    assert_eq!(points(&risk.factors, RiskFeature::TimeOfDay), 0.0);
+}
+
+#[test]
+fn stricter_threshold_wins() {
+    let thresholds = RiskThresholds { review: 80.0, decline: 95.0 }
+        .tighten(Some(60), None)
+        .tighten(Some(70), Some(90));
+    assert_eq!(thresholds, RiskThresholds { review: 60.0, decline: 90.0 });
+
+    assert_eq!(thresholds.decide(59.0), Decision::Approve);
+    assert_eq!(thresholds.decide(60.0), Decision::Review);
+    assert_eq!(thresholds.decide(90.0), Decision::Decline);
+}
@@ -0,0 +1,48 @@
+//! Daily and monthly windows start at the owner's local midnight, whatever
+//! the UTC offset or daylight saving does in between.
+
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use security_gateway::{owner_timezone, start_of_day, start_of_month_window, MonthlyWindow};
+
+fn at(instant: &str) -> DateTime<Utc> {
+    instant.parse().unwrap()
+}
+
+#[test]
+fn month_starts_at_the_owners_local_midnight() {
+    let tokyo = owner_timezone(Some("Asia/Tokyo"));
+
+    // Already 1 November in Tokyo while it is still October in UTC
+    let now = at("2026-10-31T20:00:00Z");
+    assert_eq!(start_of_month_window(tokyo, MonthlyWindow::Calendar, now), at("2026-10-31T15:00:00Z"));
+    assert_eq!(start_of_month_window(Tz::UTC, MonthlyWindow::Calendar, now), at("2026-10-01T00:00:00Z"));
+}
+
+#[test]
+fn month_start_keeps_the_offset_in_force_at_midnight_across_dst() {
+    let new_york = owner_timezone(Some("America/New_York"));
+
+    // DST ended on 1 November 2026: the month began on EDT (UTC-4), it is now EST (UTC-5)
+    let now = at("2026-11-15T12:00:00Z");
+    assert_eq!(start_of_month_window(new_york, MonthlyWindow::Calendar, now), at("2026-11-01T04:00:00Z"));
+    assert_eq!(start_of_day(new_york, now), at("2026-11-15T05:00:00Z"));
+}
+
+#[test]
+fn day_starts_at_the_first_local_time_when_dst_skips_midnight() {
+    let santiago = owner_timezone(Some("America/Santiago"));
+
+    // Clocks jumped from 00:00 to 01:00 (UTC-3) on 6 September 2026
+    let now = at("2026-09-06T15:00:00Z");
+    assert_eq!(start_of_day(santiago, now), at("2026-09-06T04:00:00Z"));
+}
+
+#[test]
+fn rolling_window_and_unknown_timezones() {
+    let now = at("2026-10-17T09:30:00Z");
+    assert_eq!(start_of_month_window(Tz::UTC, MonthlyWindow::Rolling30Days, now), now - Duration::days(30));
+    assert_eq!(owner_timezone(Some("Mars/Olympus_Mons")), Tz::UTC);
+    assert_eq!(MonthlyWindow::parse(Some("rolling_30d")), MonthlyWindow::Rolling30Days);
+    assert_eq!(MonthlyWindow::parse(Some("weekly")), MonthlyWindow::Calendar);
+}
@@ -0,0 +1,91 @@
+//! Tier profiles and promotion recommendations, without Postgres.
+
+use rust_decimal::Decimal;
+use security_gateway::tiers::{self, TierHistory, TierMove, TierProfile};
+use security_gateway::Protocol;
+
+fn profile(tier: &str, rank: i32, min_completed: i32) -> TierProfile {
+    TierProfile {
+        tier: tier.to_string(),
+        rank,
+        max_per_tx: Some(Decimal::from(100 * rank)),
+        max_daily: None,
+        max_monthly: None,
+        allowed_protocols: None,
+        velocity_per_minute: None,
+        requires_manual_review: false,
+        min_completed_transactions: min_completed,
+    }
+}
+
+fn ladder() -> Vec<TierProfile> {
+    // Deliberately out of order; rank decides
+    vec![profile("gold", 3, 100), profile("bronze", 1, 0), profile("silver", 2, 25)]
+}
+
+fn history(completed: i64, declined: i64, open_fraud_patterns: i64) -> TierHistory {
+    TierHistory { completed, declined, open_fraud_patterns }
+}
+
+#[test]
+fn protocols_are_restricted_only_when_listed() {
+    let mut bronze = profile("bronze", 1, 0);
+    assert!(bronze.allows_protocol(&Protocol::MCP));
+
+    bronze.allowed_protocols = Some(vec![]);
+    assert!(bronze.allows_protocol(&Protocol::MCP));
+
+    bronze.allowed_protocols = Some(vec!["acp".to_string()]);
+    assert!(bronze.allows_protocol(&Protocol::ACP));
+    assert!(!bronze.allows_protocol(&Protocol::MCP));
+}
+
+#[test]
+fn velocity_ceiling_never_raises_the_gateway_limit() {
+    let mut silver = profile("silver", 2, 25);
+    assert_eq!(silver.velocity_limit(10), 10);
+
+    silver.velocity_per_minute = Some(5);
+    assert_eq!(silver.velocity_limit(10), 5);
+
+    silver.velocity_per_minute = Some(30);
+    assert_eq!(silver.velocity_limit(10), 10);
+}
+
+#[test]
+fn clean_history_earns_a_promotion_one_step_up() {
+    let recommendation = tiers::recommend(&ladder(), "bronze", &history(30, 1, 0)).unwrap();
+    assert_eq!(recommendation.action, TierMove::Promote);
+    assert_eq!(recommendation.tier, "silver");
+
+    let short = tiers::recommend(&ladder(), "silver", &history(60, 0, 0)).unwrap();
+    assert_eq!(short.action, TierMove::Keep);
+    assert_eq!(short.tier, "silver");
+
+    let top = tiers::recommend(&ladder(), "gold", &history(5000, 0, 0)).unwrap();
+    assert_eq!(top.action, TierMove::Keep);
+}
+
+#[test]
+fn declines_or_fraud_findings_call_for_a_demotion() {
+    let declining = tiers::recommend(&ladder(), "gold", &history(60, 40, 0)).unwrap();
+    assert_eq!(declining.action, TierMove::Demote);
+    assert_eq!(declining.tier, "silver");
+
+    let flagged = tiers::recommend(&ladder(), "silver", &history(200, 0, 1)).unwrap();
+    assert_eq!(flagged.action, TierMove::Demote);
+    assert_eq!(flagged.tier, "bronze");
+
+    // Nowhere lower to go
+    let bottom = tiers::recommend(&ladder(), "bronze", &history(0, 0, 2)).unwrap();
+    assert_eq!(bottom.action, TierMove::Keep);
+
+    // Too few payments to judge the decline rate
+    let early = tiers::recommend(&ladder(), "silver", &history(1, 2, 0)).unwrap();
+    assert_eq!(early.action, TierMove::Keep);
+}
+
+#[test]
+fn unknown_tiers_get_no_recommendation() {
+    assert!(tiers::recommend(&ladder(), "premium", &history(100, 0, 0)).is_none());
+}
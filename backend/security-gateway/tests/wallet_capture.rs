@@ -0,0 +1,143 @@
+//! Payments approved on the proxy path are debited from the agent's wallet
+//! and posted to the ledger when they are logged; declined ones give their
+//! hold back.
+//!
+//! Needs a Postgres with docs/schema.sql applied.
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use security_gateway::checks::{AgentExistsCheck, CheckPipeline, PaymentTokenCheck, PipelineMode, SpendReservationCheck, WalletHoldCheck};
+use security_gateway::ledger::{self, AccountType};
+use security_gateway::{DeclineCode, Money, PaymentMethodType, Protocol, SecurityContext, SecurityGateway};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn connect() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for database tests");
+    PgPool::connect(&url).await.expect("connect to DATABASE_URL")
+}
+
+async fn create_merchant(pool: &PgPool) -> Uuid {
+    let merchant_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO merchants (id, email, password_hash, merchant_name, domain)
+         VALUES ($1, $2, 'x', 'Wallet Test', 'wallet-test.example.com')"
+    )
+    .bind(merchant_id)
+    .bind(format!("{}@wallet-test.example.com", merchant_id))
+    .execute(pool)
+    .await
+    .expect("insert test merchant");
+    merchant_id
+}
+
+async fn create_agent(pool: &PgPool, balance: Decimal) -> String {
+    let agent_id = format!("test-wallet-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO agents (id, owner_company, owner_email, protocol, balance, remaining_balance, held_balance)
+         VALUES ($1, 'Wallet Test', 'wallet-test@example.com', 'MCP', $2, $2, 0)"
+    )
+    .bind(&agent_id)
+    .bind(balance)
+    .execute(pool)
+    .await
+    .expect("insert test agent");
+    agent_id
+}
+
+async fn balances(pool: &PgPool, agent_id: &str) -> (Decimal, Decimal) {
+    sqlx::query_as("SELECT remaining_balance, held_balance FROM agents WHERE id = $1")
+        .bind(agent_id)
+        .fetch_one(pool)
+        .await
+        .expect("read balances")
+}
+
+fn paying(agent_id: &str, merchant_id: Uuid, amount: &str) -> SecurityContext {
+    let amount = Money::parse(amount, "USD").unwrap();
+    SecurityContext {
+        agent_id: agent_id.to_string(),
+        agent_owner: None,
+        foundational_model: None,
+        protocol: Protocol::MCP,
+        transaction_id: format!("tx_{}", Uuid::new_v4()),
+        currency: amount.currency.clone(),
+        amount: Some(amount),
+        merchant_id: merchant_id.to_string(),
+        merchant_name: None,
+        timestamp: Utc::now(),
+        user_id: None,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+        payment_method_type: None,
+        payment_token: None,
+        signature: None,
+        nonce: Uuid::new_v4().to_string(),
+        risk_score: None,
+        metadata: Default::default(),
+        raw_request: serde_json::Value::Null,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database at DATABASE_URL"]
+async fn proxied_approval_debits_the_wallet() {
+    let pool = connect().await;
+    let merchant_id = create_merchant(&pool).await;
+    let agent_id = create_agent(&pool, Decimal::from(100)).await;
+
+    let pipeline = CheckPipeline::new(PipelineMode::ShortCircuit)
+        .with_check(AgentExistsCheck)
+        .with_check(SpendReservationCheck::default())
+        .with_check(WalletHoldCheck)
+        .with_check(PaymentTokenCheck);
+    let gateway = SecurityGateway::with_pipeline(pipeline).await.expect("build gateway");
+    let pay = |ctx: SecurityContext| {
+        let gateway = &gateway;
+        async move {
+            let verification = gateway.verify(&ctx).await.unwrap();
+            gateway.log_transaction(&ctx, &verification).await.unwrap();
+            verification
+        }
+    };
+
+    assert!(pay(paying(&agent_id, merchant_id, "30.00")).await.approved);
+    assert_eq!(balances(&pool, &agent_id).await, (Decimal::from(70), Decimal::ZERO));
+
+    let over = pay(paying(&agent_id, merchant_id, "80.00")).await;
+    assert_eq!(over.decline_code, Some(DeclineCode::InsufficientFunds));
+    assert_eq!(balances(&pool, &agent_id).await, (Decimal::from(70), Decimal::ZERO));
+
+    // Held, then declined by a later check: the hold is given back
+    let mut unregistered = paying(&agent_id, merchant_id, "20.00");
+    unregistered.payment_method_type = Some(PaymentMethodType::SharedPaymentToken);
+    unregistered.payment_token = Some("spt_never_registered".to_string());
+    let declined = pay(unregistered).await;
+    assert_eq!(declined.decline_code, Some(DeclineCode::PaymentTokenInvalid));
+    assert_eq!(balances(&pool, &agent_id).await, (Decimal::from(70), Decimal::ZERO));
+
+    let (volume, count): (Decimal, i32) = sqlx::query_as("SELECT total_volume, transaction_count FROM agents WHERE id = $1")
+        .bind(&agent_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((volume, count), (Decimal::from(30), 1));
+
+    // The merchant is credited and the ledger records the purchase
+    let revenue: Decimal = sqlx::query_scalar("SELECT total_revenue FROM merchants WHERE id = $1")
+        .bind(merchant_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let mut conn = pool.acquire().await.unwrap();
+    let merchant = merchant_id.to_string();
+    let receivable = ledger::balance(&mut conn, AccountType::MerchantReceivable, Some(&merchant)).await.unwrap();
+    let fees = ledger::platform_fee(Decimal::from(30));
+    assert_eq!(revenue, Decimal::from(30) - fees);
+    assert_eq!(receivable, revenue);
+
+    sqlx::query("DELETE FROM transactions WHERE agent_id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM agents WHERE id = $1").bind(&agent_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM merchants WHERE id = $1").bind(merchant_id).execute(&pool).await.unwrap();
+}
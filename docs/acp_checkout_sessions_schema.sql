@@ -0,0 +1,20 @@
+-- ACP checkout sessions tracked by the gateway
+-- One row per merchant session; completion links it to the verified transaction
+CREATE TABLE IF NOT EXISTS acp_checkout_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(id),
+    merchant_session_id VARCHAR(255) NOT NULL,  -- id returned by the merchant
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id),
+    status VARCHAR(50) NOT NULL,  -- not_ready_for_payment, ready_for_payment, completed, canceled
+    currency VARCHAR(3),
+    total DECIMAL(15,2),  -- latest merchant-returned total
+    line_items JSONB,
+    transaction_id UUID REFERENCES transactions(id),
+    completed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE(merchant_id, merchant_session_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_acp_sessions_agent ON acp_checkout_sessions(agent_id);
+CREATE INDEX IF NOT EXISTS idx_acp_sessions_transaction ON acp_checkout_sessions(transaction_id);
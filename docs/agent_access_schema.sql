@@ -0,0 +1,16 @@
+-- Merchant allow-lists: when non-empty, only the listed agents may pay the merchant
+ALTER TABLE merchant_controls
+ADD COLUMN IF NOT EXISTS allowed_agents TEXT[];
+
+-- Who added or removed each platform blacklist entry, and why.
+-- agent_blacklist holds current bans only; removals delete the row.
+CREATE TABLE IF NOT EXISTS agent_blacklist_audit (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL,
+    action VARCHAR(20) NOT NULL CHECK (action IN ('added', 'removed')),
+    reason TEXT NOT NULL,
+    actor_id UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_blacklist_audit_agent ON agent_blacklist_audit(agent_id, created_at DESC);
@@ -0,0 +1,22 @@
+-- Atomic balance holds: pending transactions hold funds in agents.held_balance
+-- and completion debits remaining_balance with a conditional UPDATE
+ALTER TABLE agents
+ADD COLUMN IF NOT EXISTS held_balance DECIMAL(15,2) NOT NULL DEFAULT 0.00;
+
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS balance_held BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- NOT VALID: enforced for every new write without rejecting legacy rows
+DO $$
+BEGIN
+    ALTER TABLE agents ADD CONSTRAINT agents_remaining_balance_non_negative
+        CHECK (remaining_balance >= 0) NOT VALID;
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$
+BEGIN
+    ALTER TABLE agents ADD CONSTRAINT agents_held_balance_non_negative
+        CHECK (held_balance >= 0) NOT VALID;
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
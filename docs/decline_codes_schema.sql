@@ -0,0 +1,5 @@
+-- Machine-readable decline codes (AGENT_INACTIVE, PER_TX_LIMIT, REPLAY, ...)
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS decline_code VARCHAR(50);
+
+CREATE INDEX IF NOT EXISTS idx_tx_decline_code ON transactions(decline_code) WHERE decline_code IS NOT NULL;
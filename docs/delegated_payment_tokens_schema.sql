@@ -0,0 +1,21 @@
+-- Ledger of delegated payment tokens (ACP Shared Payment Tokens) seen by the gateway
+-- Tokens are stored as SHA-256 fingerprints; the agent's owner registers each token and its allowance
+CREATE TABLE IF NOT EXISTS delegated_payment_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_fingerprint VARCHAR(64) UNIQUE NOT NULL,
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id),
+    registered_by UUID NOT NULL REFERENCES users(id),
+    merchant_id VARCHAR(255),  -- NULL = any merchant
+    checkout_session_id VARCHAR(255),  -- NULL = any checkout session
+    max_amount DECIMAL(15,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    single_use BOOLEAN NOT NULL DEFAULT TRUE,
+    use_count INTEGER NOT NULL DEFAULT 0,
+    amount_used DECIMAL(15,2) NOT NULL DEFAULT 0.00,
+    last_nonce VARCHAR(255),
+    last_used_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_tokens_agent ON delegated_payment_tokens(agent_id);
@@ -0,0 +1,18 @@
+-- Fraud pattern findings: written by the pattern_normal check, resolved by admins.
+-- Unresolved high/critical findings block the agent's payments.
+CREATE TABLE IF NOT EXISTS fraud_patterns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    pattern_type VARCHAR(100) NOT NULL,  -- 'card_testing', 'merchant_hopping', 'structuring', 'round_amounts'
+    severity VARCHAR(50) NOT NULL,  -- 'low', 'medium', 'high', 'critical'
+    details JSONB NOT NULL,
+    detected_at TIMESTAMP DEFAULT NOW(),
+    resolved BOOLEAN DEFAULT false,
+    resolution_notes TEXT,
+    resolved_by UUID,
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_fraud_agent ON fraud_patterns(agent_id);
+CREATE INDEX IF NOT EXISTS idx_fraud_severity ON fraud_patterns(severity);
+CREATE INDEX IF NOT EXISTS idx_fraud_resolved ON fraud_patterns(resolved);
@@ -0,0 +1,17 @@
+-- Merchant category code (ISO 18245 MCC, e.g. '5921' package stores)
+ALTER TABLE merchants
+ADD COLUMN IF NOT EXISTS mcc VARCHAR(4);
+
+-- Owner rules per agent over merchant MCCs and line-item categories
+CREATE TABLE IF NOT EXISTS agent_category_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    scope VARCHAR(20) NOT NULL CHECK (scope IN ('merchant', 'item')),
+    category VARCHAR(50) NOT NULL,
+    effect VARCHAR(20) NOT NULL CHECK (effect IN ('allow', 'review', 'deny')),
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (agent_id, scope, category)
+);
+
+CREATE INDEX IF NOT EXISTS idx_category_rules_agent ON agent_category_rules(agent_id);
@@ -0,0 +1,101 @@
+-- Double-entry ledger. Journals are append-only and every journal balances;
+-- agents.remaining_balance and merchants.total_revenue are derived from it
+CREATE TABLE IF NOT EXISTS ledger_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_type VARCHAR(50) NOT NULL CHECK (account_type IN (
+        'agent_wallet', 'merchant_receivable', 'platform_fees', 'refunds', 'external_funding'
+    )),
+    owner_id VARCHAR(255),  -- agent or merchant id; NULL for platform-wide accounts
+    owner_key VARCHAR(255) GENERATED ALWAYS AS (COALESCE(owner_id, '')) STORED,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(account_type, owner_key, currency)
+);
+
+CREATE TABLE IF NOT EXISTS ledger_journals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(50) NOT NULL,  -- topup, withdrawal, completion, refund, opening_balance
+    reference_id UUID,
+    description TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seq BIGSERIAL,  -- posting order; entries in one transaction share created_at
+    journal_id UUID NOT NULL REFERENCES ledger_journals(id),
+    account_id UUID NOT NULL REFERENCES ledger_accounts(id),
+    direction VARCHAR(6) NOT NULL CHECK (direction IN ('debit', 'credit')),
+    amount DECIMAL(15,2) NOT NULL CHECK (amount >= 0),
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+ALTER TABLE ledger_entries ADD COLUMN IF NOT EXISTS seq BIGSERIAL;
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_account ON ledger_entries(account_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_journal ON ledger_entries(journal_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_journals_reference ON ledger_journals(reference_id);
+
+CREATE OR REPLACE FUNCTION ledger_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'ledger is append-only; post a correcting journal instead';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_append_only ON ledger_entries;
+CREATE TRIGGER ledger_entries_append_only BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
+
+DROP TRIGGER IF EXISTS ledger_journals_append_only ON ledger_journals;
+CREATE TRIGGER ledger_journals_append_only BEFORE UPDATE OR DELETE ON ledger_journals
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
+
+-- Mismatches found by the reconciliation job
+CREATE TABLE IF NOT EXISTS ledger_drift (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subject_type VARCHAR(50) NOT NULL,  -- agent_balance, merchant_revenue, journal
+    subject_id VARCHAR(255) NOT NULL,
+    expected DECIMAL(15,2) NOT NULL,  -- per the ledger
+    actual DECIMAL(15,2) NOT NULL,  -- per the derived column
+    detected_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_drift_open ON ledger_drift(detected_at DESC) WHERE resolved_at IS NULL;
+
+-- Opening balances for wallets and merchants that predate the ledger
+DO $$
+DECLARE
+    r RECORD;
+    journal UUID;
+    funding UUID;
+    account UUID;
+BEGIN
+    INSERT INTO ledger_accounts (account_type, currency) VALUES ('external_funding', 'USD')
+    ON CONFLICT (account_type, owner_key, currency) DO NOTHING;
+    SELECT id INTO funding FROM ledger_accounts
+    WHERE account_type = 'external_funding' AND owner_key = '' AND currency = 'USD';
+
+    FOR r IN
+        SELECT 'agent_wallet' AS account_type, a.id AS owner_id, a.remaining_balance AS amount
+        FROM agents a
+        WHERE COALESCE(a.remaining_balance, 0) > 0
+          AND NOT EXISTS (SELECT 1 FROM ledger_accounts la WHERE la.account_type = 'agent_wallet' AND la.owner_id = a.id)
+        UNION ALL
+        SELECT 'merchant_receivable', m.id::text, m.total_revenue
+        FROM merchants m
+        WHERE COALESCE(m.total_revenue, 0) > 0
+          AND NOT EXISTS (SELECT 1 FROM ledger_accounts la WHERE la.account_type = 'merchant_receivable' AND la.owner_id = m.id::text)
+    LOOP
+        INSERT INTO ledger_accounts (account_type, owner_id, currency)
+        VALUES (r.account_type, r.owner_id, 'USD')
+        RETURNING id INTO account;
+
+        INSERT INTO ledger_journals (kind, description)
+        VALUES ('opening_balance', 'Opening balance for ' || r.owner_id)
+        RETURNING id INTO journal;
+
+        INSERT INTO ledger_entries (journal_id, account_id, direction, amount)
+        VALUES (journal, funding, 'debit', r.amount), (journal, account, 'credit', r.amount);
+    END LOOP;
+END $$;
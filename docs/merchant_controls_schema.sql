@@ -0,0 +1,14 @@
+-- Merchant controls from the architecture doc, on top of the thresholds and
+-- allow-list added earlier. First-payment review is opt-in so existing rows
+-- keep their current behaviour.
+ALTER TABLE merchant_controls
+ADD COLUMN IF NOT EXISTS auto_approve_agents BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS require_manual_review_first_tx BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS max_agent_tx_amount DECIMAL(15,2) CHECK (max_agent_tx_amount > 0),
+ADD COLUMN IF NOT EXISTS blocked_agents TEXT[],
+ADD COLUMN IF NOT EXISTS webhook_url VARCHAR(500);
+
+-- Who must resolve a transaction parked for review: 'owner' or 'merchant'
+-- (admins always may). Rows parked before this column are the owner's.
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS review_party VARCHAR(20);
@@ -0,0 +1,9 @@
+-- Merchant upstream registry: where the gateway forwards approved agent traffic
+-- Base URL falls back to https://{domain}; ACP base URL comes from checkout_url_pattern
+ALTER TABLE merchants
+ADD COLUMN IF NOT EXISTS upstream_base_url TEXT,
+ADD COLUMN IF NOT EXISTS mcp_endpoint TEXT DEFAULT '/mcp',
+ADD COLUMN IF NOT EXISTS upstream_timeout_ms INTEGER DEFAULT 10000,
+ADD COLUMN IF NOT EXISTS upstream_allowed_headers TEXT[],  -- NULL = gateway defaults
+ADD COLUMN IF NOT EXISTS upstream_tls_verify BOOLEAN DEFAULT TRUE,
+ADD COLUMN IF NOT EXISTS upstream_ca_cert TEXT;  -- PEM, for merchants on a private CA
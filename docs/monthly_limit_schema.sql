@@ -0,0 +1,8 @@
+-- Monthly spending limits: owner timezone for daily/monthly boundaries,
+-- and per-agent choice of calendar month or rolling 30 days
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS timezone VARCHAR(64) DEFAULT 'UTC';
+
+ALTER TABLE agents
+ADD COLUMN IF NOT EXISTS monthly_limit_window VARCHAR(20) DEFAULT 'calendar'
+    CHECK (monthly_limit_window IN ('calendar', 'rolling_30d'));
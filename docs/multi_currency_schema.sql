@@ -0,0 +1,12 @@
+-- Multi-currency: limits are in the owner's base currency; transactions in other
+-- currencies store the converted amount and the rate snapshot used. Wallets are
+-- in the ledger currency, so REST transactions also store what they hold there
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS base_currency VARCHAR(3) NOT NULL DEFAULT 'USD'
+    CHECK (base_currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS base_amount DECIMAL(15,2),
+ADD COLUMN IF NOT EXISTS base_currency VARCHAR(3),
+ADD COLUMN IF NOT EXISTS fx_rate JSONB,
+ADD COLUMN IF NOT EXISTS ledger_amount DECIMAL(15,2);
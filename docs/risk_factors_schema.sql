@@ -0,0 +1,3 @@
+-- Per-feature risk contributions behind transactions.risk_score
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS risk_factors JSONB;
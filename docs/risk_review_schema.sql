@@ -0,0 +1,30 @@
+-- Risk-based review: owners and merchants set their own thresholds; transactions
+-- scoring between the review and decline thresholds wait in 'pending_review'
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS risk_review_threshold INTEGER CHECK (risk_review_threshold BETWEEN 0 AND 100),
+ADD COLUMN IF NOT EXISTS risk_decline_threshold INTEGER CHECK (risk_decline_threshold BETWEEN 0 AND 100);
+
+CREATE TABLE IF NOT EXISTS merchant_controls (
+    merchant_id UUID PRIMARY KEY REFERENCES merchants(id) ON DELETE CASCADE,
+    fraud_alert_threshold INTEGER DEFAULT 80 CHECK (fraud_alert_threshold BETWEEN 0 AND 100),
+    risk_decline_threshold INTEGER CHECK (risk_decline_threshold BETWEEN 0 AND 100),
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS review_reason TEXT,
+ADD COLUMN IF NOT EXISTS reviewed_by UUID,
+ADD COLUMN IF NOT EXISTS reviewer_role VARCHAR(20),
+ADD COLUMN IF NOT EXISTS review_note TEXT,
+ADD COLUMN IF NOT EXISTS reviewed_at TIMESTAMP;
+
+-- A parked request is kept so it can be sent to the merchant once approved. An
+-- approved transaction the merchant has not accepted yet stays 'approved' until
+-- a retry gets it through, then becomes 'completed'. It is 'sending' while a
+-- release is calling the merchant, so it is only ever sent once.
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS parked_request JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_tx_approved ON transactions(agent_id) WHERE status = 'approved';
+CREATE INDEX IF NOT EXISTS idx_tx_pending_review ON transactions(agent_id) WHERE status = 'pending_review';
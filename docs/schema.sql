@@ -6,6 +6,10 @@ CREATE TABLE IF NOT EXISTS users (
     password_hash TEXT NOT NULL,
     full_name VARCHAR(255),
     role VARCHAR(50) DEFAULT 'user',
+    timezone VARCHAR(64) DEFAULT 'UTC',
+    base_currency VARCHAR(3) NOT NULL DEFAULT 'USD' CHECK (base_currency ~ '^[A-Z]{3}$'),
+    risk_review_threshold INTEGER CHECK (risk_review_threshold BETWEEN 0 AND 100),
+    risk_decline_threshold INTEGER CHECK (risk_decline_threshold BETWEEN 0 AND 100),
     created_at TIMESTAMP DEFAULT NOW()
 );
 
@@ -21,9 +25,11 @@ CREATE TABLE IF NOT EXISTS agents (
     spending_limit_per_tx DECIMAL(15,2) DEFAULT 1000.00,
     spending_limit_daily DECIMAL(15,2) DEFAULT 10000.00,
     spending_limit_monthly DECIMAL(15,2) DEFAULT 100000.00,
+    monthly_limit_window VARCHAR(20) DEFAULT 'calendar' CHECK (monthly_limit_window IN ('calendar', 'rolling_30d')),
     tier VARCHAR(50) DEFAULT 'bronze',
     balance DECIMAL(15,2) DEFAULT 0.00,
-    remaining_balance DECIMAL(15,2) DEFAULT 0.00,
+    remaining_balance DECIMAL(15,2) DEFAULT 0.00 CONSTRAINT agents_remaining_balance_non_negative CHECK (remaining_balance >= 0),
+    held_balance DECIMAL(15,2) NOT NULL DEFAULT 0.00 CONSTRAINT agents_held_balance_non_negative CHECK (held_balance >= 0),
     status VARCHAR(50) DEFAULT 'active',
     risk_score INTEGER DEFAULT 0,
     created_at TIMESTAMP DEFAULT NOW(),
@@ -54,6 +60,24 @@ CREATE TABLE IF NOT EXISTS transactions (
     refunded_at TIMESTAMP,
     refund_reason TEXT,
     completed_at TIMESTAMP,
+    decline_code VARCHAR(50),
+    balance_held BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Amount in the owner's base currency and the FX rate snapshot used to get it
+    base_amount DECIMAL(15,2),
+    base_currency VARCHAR(3),
+    fx_rate JSONB,
+    ledger_amount DECIMAL(15,2),
+    risk_factors JSONB,
+    -- Set while a risky transaction waits in 'pending_review' and once it is resolved;
+    -- approved ones sit in 'approved' until the parked request reaches the merchant,
+    -- and in 'sending' while it is on its way
+    review_reason TEXT,
+    review_party VARCHAR(20),  -- 'owner' or 'merchant'; admins may always resolve
+    reviewed_by UUID,
+    reviewer_role VARCHAR(20),
+    review_note TEXT,
+    reviewed_at TIMESTAMP,
+    parked_request JSONB,
     created_at TIMESTAMP DEFAULT NOW()
 );
 
@@ -61,6 +85,149 @@ CREATE INDEX IF NOT EXISTS idx_tx_agent ON transactions(agent_id);
 CREATE INDEX IF NOT EXISTS idx_tx_merchant ON transactions(merchant_id);
 CREATE INDEX IF NOT EXISTS idx_tx_status ON transactions(status);
 CREATE INDEX IF NOT EXISTS idx_tx_created ON transactions(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_tx_decline_code ON transactions(decline_code) WHERE decline_code IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tx_pending_review ON transactions(agent_id) WHERE status = 'pending_review';
+CREATE INDEX IF NOT EXISTS idx_tx_approved ON transactions(agent_id) WHERE status = 'approved';
+
+-- Delegated payment token (SPT) ledger
+CREATE TABLE IF NOT EXISTS delegated_payment_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_fingerprint VARCHAR(64) UNIQUE NOT NULL,
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id),
+    registered_by UUID NOT NULL REFERENCES users(id),
+    merchant_id VARCHAR(255),
+    checkout_session_id VARCHAR(255),
+    max_amount DECIMAL(15,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    single_use BOOLEAN NOT NULL DEFAULT TRUE,
+    use_count INTEGER NOT NULL DEFAULT 0,
+    amount_used DECIMAL(15,2) NOT NULL DEFAULT 0.00,
+    last_nonce VARCHAR(255),
+    last_used_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+-- Fraud pattern findings; unresolved high/critical ones block the agent's payments
+CREATE TABLE IF NOT EXISTS fraud_patterns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    pattern_type VARCHAR(100) NOT NULL,  -- 'card_testing', 'merchant_hopping', 'structuring', 'round_amounts'
+    severity VARCHAR(50) NOT NULL,  -- 'low', 'medium', 'high', 'critical'
+    details JSONB NOT NULL,
+    detected_at TIMESTAMP DEFAULT NOW(),
+    resolved BOOLEAN DEFAULT false,
+    resolution_notes TEXT,
+    resolved_by UUID,
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_fraud_agent ON fraud_patterns(agent_id);
+CREATE INDEX IF NOT EXISTS idx_fraud_severity ON fraud_patterns(severity);
+CREATE INDEX IF NOT EXISTS idx_fraud_resolved ON fraud_patterns(resolved);
+
+CREATE INDEX IF NOT EXISTS idx_payment_tokens_agent ON delegated_payment_tokens(agent_id);
+
+-- Spend holds against daily/monthly budgets
+CREATE TABLE IF NOT EXISTS spend_reservations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    nonce VARCHAR(255) NOT NULL,
+    transaction_id UUID,
+    amount DECIMAL(15,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    status VARCHAR(20) NOT NULL DEFAULT 'held' CHECK (status IN ('held', 'settled', 'released')),
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    UNIQUE(agent_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_spend_reservations_live ON spend_reservations(agent_id, created_at) WHERE status = 'held';
+CREATE INDEX IF NOT EXISTS idx_spend_reservations_transaction ON spend_reservations(transaction_id);
+
+-- Double-entry ledger; agents.remaining_balance and merchants.total_revenue derive from it
+CREATE TABLE IF NOT EXISTS ledger_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_type VARCHAR(50) NOT NULL CHECK (account_type IN (
+        'agent_wallet', 'merchant_receivable', 'platform_fees', 'refunds', 'external_funding'
+    )),
+    owner_id VARCHAR(255),  -- agent or merchant id; NULL for platform-wide accounts
+    owner_key VARCHAR(255) GENERATED ALWAYS AS (COALESCE(owner_id, '')) STORED,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(account_type, owner_key, currency)
+);
+
+CREATE TABLE IF NOT EXISTS ledger_journals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(50) NOT NULL,  -- topup, withdrawal, completion, refund, opening_balance
+    reference_id UUID,
+    description TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seq BIGSERIAL,  -- posting order; entries in one transaction share created_at
+    journal_id UUID NOT NULL REFERENCES ledger_journals(id),
+    account_id UUID NOT NULL REFERENCES ledger_accounts(id),
+    direction VARCHAR(6) NOT NULL CHECK (direction IN ('debit', 'credit')),
+    amount DECIMAL(15,2) NOT NULL CHECK (amount >= 0),
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_account ON ledger_entries(account_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_journal ON ledger_entries(journal_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_journals_reference ON ledger_journals(reference_id);
+
+CREATE OR REPLACE FUNCTION ledger_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'ledger is append-only; post a correcting journal instead';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_append_only ON ledger_entries;
+CREATE TRIGGER ledger_entries_append_only BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
+
+DROP TRIGGER IF EXISTS ledger_journals_append_only ON ledger_journals;
+CREATE TRIGGER ledger_journals_append_only BEFORE UPDATE OR DELETE ON ledger_journals
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
+
+-- Mismatches found by the reconciliation job
+CREATE TABLE IF NOT EXISTS ledger_drift (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subject_type VARCHAR(50) NOT NULL,  -- agent_balance, merchant_revenue, journal
+    subject_id VARCHAR(255) NOT NULL,
+    expected DECIMAL(15,2) NOT NULL,  -- per the ledger
+    actual DECIMAL(15,2) NOT NULL,  -- per the derived column
+    detected_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_drift_open ON ledger_drift(detected_at DESC) WHERE resolved_at IS NULL;
+
+-- Wallet top-ups and withdrawals
+CREATE TABLE IF NOT EXISTS wallet_operations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('topup', 'withdrawal')),
+    amount DECIMAL(15,2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    status VARCHAR(20) NOT NULL CHECK (status IN ('processing', 'pending_approval', 'completed', 'rejected')),
+    idempotency_key VARCHAR(255) NOT NULL,
+    requested_by UUID NOT NULL REFERENCES users(id),
+    approved_by UUID REFERENCES users(id),  -- second approver for large top-ups
+    note TEXT,
+    journal_id UUID REFERENCES ledger_journals(id),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    UNIQUE(agent_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_operations_agent ON wallet_operations(agent_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_wallet_operations_pending ON wallet_operations(created_at) WHERE status = 'pending_approval';
 
 CREATE TABLE IF NOT EXISTS nonces (
     agent_id VARCHAR(255) NOT NULL,
@@ -81,6 +248,16 @@ CREATE TABLE IF NOT EXISTS merchants (
     business_email VARCHAR(255),
     business_address TEXT,
     checkout_url_pattern TEXT,
+    upstream_base_url TEXT,
+    mcp_endpoint TEXT DEFAULT '/mcp',
+    upstream_timeout_ms INTEGER DEFAULT 10000,
+    upstream_allowed_headers TEXT[],
+    upstream_tls_verify BOOLEAN DEFAULT TRUE,
+    upstream_ca_cert TEXT,
+    -- e.g. 'grocery'; used by spending policy conditions
+    category VARCHAR(50),
+    -- ISO 18245 merchant category code; used by agent category rules
+    mcc VARCHAR(4),
     api_key TEXT UNIQUE,
     trust_score INTEGER DEFAULT 0,
     total_revenue DECIMAL(15,2) DEFAULT 0.00,
@@ -93,6 +270,43 @@ CREATE TABLE IF NOT EXISTS merchants (
 CREATE INDEX IF NOT EXISTS idx_merchants_email ON merchants(email);
 CREATE INDEX IF NOT EXISTS idx_merchants_status ON merchants(status);
 
+-- Per-merchant risk controls
+CREATE TABLE IF NOT EXISTS merchant_controls (
+    merchant_id UUID PRIMARY KEY REFERENCES merchants(id) ON DELETE CASCADE,
+    fraud_alert_threshold INTEGER DEFAULT 80 CHECK (fraud_alert_threshold BETWEEN 0 AND 100),
+    risk_decline_threshold INTEGER CHECK (risk_decline_threshold BETWEEN 0 AND 100),
+    auto_approve_agents BOOLEAN NOT NULL DEFAULT FALSE,
+    require_manual_review_first_tx BOOLEAN NOT NULL DEFAULT FALSE,
+    -- In the ledger currency
+    max_agent_tx_amount DECIMAL(15,2) CHECK (max_agent_tx_amount > 0),
+    blocked_agents TEXT[],
+    -- When non-empty, only these agents may pay the merchant
+    allowed_agents TEXT[],
+    webhook_url VARCHAR(500),
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+-- ACP checkout sessions tracked by the gateway
+CREATE TABLE IF NOT EXISTS acp_checkout_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(id),
+    merchant_session_id VARCHAR(255) NOT NULL,
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id),
+    status VARCHAR(50) NOT NULL,
+    currency VARCHAR(3),
+    total DECIMAL(15,2),
+    line_items JSONB,
+    transaction_id UUID REFERENCES transactions(id),
+    completed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE(merchant_id, merchant_session_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_acp_sessions_agent ON acp_checkout_sessions(agent_id);
+CREATE INDEX IF NOT EXISTS idx_acp_sessions_transaction ON acp_checkout_sessions(transaction_id);
+
 CREATE TABLE IF NOT EXISTS merchant_agent_blocks (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     merchant_id UUID NOT NULL REFERENCES merchants(id),
@@ -134,6 +348,17 @@ CREATE TABLE IF NOT EXISTS agent_blacklist (
 
 CREATE INDEX IF NOT EXISTS idx_blacklist_agent ON agent_blacklist(agent_id);
 
+CREATE TABLE IF NOT EXISTS agent_blacklist_audit (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL,
+    action VARCHAR(20) NOT NULL CHECK (action IN ('added', 'removed')),
+    reason TEXT NOT NULL,
+    actor_id UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_blacklist_audit_agent ON agent_blacklist_audit(agent_id, created_at DESC);
+
 CREATE TABLE IF NOT EXISTS notifications (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     user_id UUID NOT NULL REFERENCES users(id),
@@ -184,3 +409,72 @@ CREATE INDEX IF NOT EXISTS idx_evaluations_session ON agent_evaluations(evaluati
 CREATE INDEX IF NOT EXISTS idx_evaluations_agent ON agent_evaluations(agent_id);
 CREATE INDEX IF NOT EXISTS idx_team_members_team ON agent_team_members(team_id);
 CREATE INDEX IF NOT EXISTS idx_team_members_agent ON agent_team_members(agent_id);
+
+-- Owner-defined spending policies, attached to exactly one agent or team
+CREATE TABLE IF NOT EXISTS spending_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    agent_id VARCHAR(255) REFERENCES agents(id) ON DELETE CASCADE,
+    team_id UUID REFERENCES agent_teams(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    document JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    CHECK ((agent_id IS NULL) <> (team_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_policies_agent ON spending_policies(agent_id) WHERE agent_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_policies_team ON spending_policies(team_id) WHERE team_id IS NOT NULL;
+
+-- Owner rules per agent over merchant MCCs and line-item categories
+CREATE TABLE IF NOT EXISTS agent_category_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    scope VARCHAR(20) NOT NULL CHECK (scope IN ('merchant', 'item')),
+    category VARCHAR(50) NOT NULL,
+    effect VARCHAR(20) NOT NULL CHECK (effect IN ('allow', 'review', 'deny')),
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (agent_id, scope, category)
+);
+
+CREATE INDEX IF NOT EXISTS idx_category_rules_agent ON agent_category_rules(agent_id);
+
+-- Limit and capability profile per agent tier. Maxima cap the agent's own
+-- limits in the owner's base currency; NULL leaves them uncapped.
+CREATE TABLE IF NOT EXISTS tier_profiles (
+    tier VARCHAR(50) PRIMARY KEY,
+    rank INTEGER NOT NULL UNIQUE,
+    max_per_tx DECIMAL(15,2),
+    max_daily DECIMAL(15,2),
+    max_monthly DECIMAL(15,2),
+    allowed_protocols TEXT[],  -- NULL or empty allows every protocol
+    velocity_per_minute INTEGER,
+    requires_manual_review BOOLEAN NOT NULL DEFAULT FALSE,
+    min_completed_transactions INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+INSERT INTO tier_profiles
+    (tier, rank, max_per_tx, max_daily, max_monthly, velocity_per_minute, min_completed_transactions)
+VALUES
+    ('bronze', 1, 100.00, 500.00, 2000.00, 3, 0),
+    ('silver', 2, 500.00, 2500.00, 10000.00, 5, 25),
+    ('gold', 3, 1000.00, 10000.00, 50000.00, 10, 100),
+    ('platinum', 4, 5000.00, 25000.00, 100000.00, 20, 500),
+    ('diamond', 5, NULL, NULL, NULL, 30, 2000)
+ON CONFLICT (tier) DO NOTHING;
+
+-- Every promotion and demotion
+CREATE TABLE IF NOT EXISTS agent_tier_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    from_tier VARCHAR(50),
+    to_tier VARCHAR(50) NOT NULL REFERENCES tier_profiles(tier),
+    reason TEXT NOT NULL,
+    changed_by UUID NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tier_changes_agent ON agent_tier_changes(agent_id);
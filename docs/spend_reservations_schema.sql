@@ -0,0 +1,19 @@
+-- Spend reservations: holds against an agent's daily/monthly budgets
+-- 'held' counts toward limits until expires_at; 'settled' once the transaction
+-- completes (the transaction row counts instead); 'released' on deny or expiry
+CREATE TABLE IF NOT EXISTS spend_reservations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    nonce VARCHAR(255) NOT NULL,
+    transaction_id UUID,
+    amount DECIMAL(15,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    status VARCHAR(20) NOT NULL DEFAULT 'held' CHECK (status IN ('held', 'settled', 'released')),
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    UNIQUE(agent_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_spend_reservations_live ON spend_reservations(agent_id, created_at) WHERE status = 'held';
+CREATE INDEX IF NOT EXISTS idx_spend_reservations_transaction ON spend_reservations(transaction_id);
@@ -0,0 +1,21 @@
+-- Owner-defined spending policies (JSON/YAML documents stored as JSON),
+-- attached to exactly one agent or team
+CREATE TABLE IF NOT EXISTS spending_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    agent_id VARCHAR(255) REFERENCES agents(id) ON DELETE CASCADE,
+    team_id UUID REFERENCES agent_teams(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    document JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    CHECK ((agent_id IS NULL) <> (team_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_policies_agent ON spending_policies(agent_id) WHERE agent_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_policies_team ON spending_policies(team_id) WHERE team_id IS NOT NULL;
+
+-- Merchant category (e.g. 'grocery') for policy conditions
+ALTER TABLE merchants
+ADD COLUMN IF NOT EXISTS category VARCHAR(50);
@@ -0,0 +1,37 @@
+-- Limit and capability profile per agent tier. Maxima cap the agent's own
+-- limits in the owner's base currency; NULL leaves them uncapped.
+CREATE TABLE IF NOT EXISTS tier_profiles (
+    tier VARCHAR(50) PRIMARY KEY,
+    rank INTEGER NOT NULL UNIQUE,
+    max_per_tx DECIMAL(15,2),
+    max_daily DECIMAL(15,2),
+    max_monthly DECIMAL(15,2),
+    allowed_protocols TEXT[],  -- NULL or empty allows every protocol
+    velocity_per_minute INTEGER,
+    requires_manual_review BOOLEAN NOT NULL DEFAULT FALSE,
+    min_completed_transactions INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+INSERT INTO tier_profiles
+    (tier, rank, max_per_tx, max_daily, max_monthly, velocity_per_minute, min_completed_transactions)
+VALUES
+    ('bronze', 1, 100.00, 500.00, 2000.00, 3, 0),
+    ('silver', 2, 500.00, 2500.00, 10000.00, 5, 25),
+    ('gold', 3, 1000.00, 10000.00, 50000.00, 10, 100),
+    ('platinum', 4, 5000.00, 25000.00, 100000.00, 20, 500),
+    ('diamond', 5, NULL, NULL, NULL, 30, 2000)
+ON CONFLICT (tier) DO NOTHING;
+
+-- Every promotion and demotion
+CREATE TABLE IF NOT EXISTS agent_tier_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    from_tier VARCHAR(50),
+    to_tier VARCHAR(50) NOT NULL REFERENCES tier_profiles(tier),
+    reason TEXT NOT NULL,
+    changed_by UUID NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tier_changes_agent ON agent_tier_changes(agent_id);
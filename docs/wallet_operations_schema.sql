@@ -0,0 +1,21 @@
+-- Owner-initiated wallet top-ups and withdrawals
+-- idempotency_key makes retries safe; large top-ups wait in 'pending_approval'
+CREATE TABLE IF NOT EXISTS wallet_operations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    agent_id VARCHAR(255) NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('topup', 'withdrawal')),
+    amount DECIMAL(15,2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    status VARCHAR(20) NOT NULL CHECK (status IN ('processing', 'pending_approval', 'completed', 'rejected')),
+    idempotency_key VARCHAR(255) NOT NULL,
+    requested_by UUID NOT NULL REFERENCES users(id),
+    approved_by UUID REFERENCES users(id),  -- second approver for large top-ups
+    note TEXT,
+    journal_id UUID REFERENCES ledger_journals(id),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    UNIQUE(agent_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_operations_agent ON wallet_operations(agent_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_wallet_operations_pending ON wallet_operations(created_at) WHERE status = 'pending_approval';
@@ -1,6 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { Bot, Search, TrendingUp, DollarSign, Activity, AlertCircle, Shield, User, Eye, Ban, ChevronDown, ChevronRight } from 'lucide-react';
 import axios from 'axios';
+import { fromMoney } from '../services/money';
 
 const AdminAllAgents = () => {
   const [agents, setAgents] = useState([]);
@@ -30,7 +31,7 @@ const AdminAllAgents = () => {
       });
       console.log('Admin agents loaded:', response.data);
       console.log("Setting agents state:", response.data);
-      setAgents(response.data);
+      setAgents(fromMoney(response.data));
       setError(null);
     } catch (err) {
       console.error('Failed to load agents:', err);
@@ -13,6 +13,7 @@ import 'reactflow/dist/style.css';
 import { networkService, teamService } from '../services/api';
 import { Bot, Store, ArrowLeft, Target, TrendingUp, Award, Shield, Clock, DollarSign, Package, ChevronDown, ChevronUp } from 'lucide-react';
 import EvaluationModal from './EvaluationModal';
+import { fromMoney } from '../services/money';
 
 const TeamGraphView = () => {
   const navigate = useNavigate();
@@ -172,7 +173,7 @@ const TeamGraphView = () => {
       });
       const data = await response.json();
       console.log('📜 Transactions loaded:', data);
-      setTransactions(data);
+      setTransactions(fromMoney(data));
       setShowTransactions(true);
     } catch (err) {
       console.error('Failed to load transactions:', err);
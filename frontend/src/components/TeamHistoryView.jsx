@@ -2,6 +2,7 @@ import React, { useState, useEffect, useCallback } from 'react';
 import { useNavigate, useParams } from 'react-router-dom';
 import { ArrowLeft, Calendar, Trophy, TrendingDown, Shield, Store } from 'lucide-react';
 import { teamService } from '../services/api';
+import { fromMoney } from '../services/money';
 
 const TeamHistoryView = () => {
   const navigate = useNavigate();
@@ -24,7 +25,7 @@ const TeamHistoryView = () => {
       });
       const data = await response.json();
       console.log('Evaluation history data:', data);
-      setEvaluations(data);
+      setEvaluations(fromMoney(data));
     } catch (err) {
       console.error('Failed to load history:', err);
       alert('Failed to load evaluation history');
@@ -1,4 +1,5 @@
 import axios from 'axios';
+import { toMoney, fromMoney } from './money';
 
 const API_BASE_URL = 'http://localhost:8081/api/v1';
 
@@ -14,6 +15,11 @@ api.interceptors.request.use((config) => {
   return config;
 });
 
+api.interceptors.response.use((response) => {
+  response.data = fromMoney(response.data);
+  return response;
+});
+
 export const agentService = {
   registerAgent: async (agentData) => {
     const userStr = localStorage.getItem('user');
@@ -38,7 +44,7 @@ export const agentService = {
         monthly: agentData.spending_limit_monthly,
         per_transaction: agentData.spending_limit_per_tx,
       },
-      balance: agentData.balance,
+      balance: toMoney(agentData.balance),
       protocol: agentData.protocol,
       foundational_model: agentData.foundational_model,
       owner_email: ownerEmail,
@@ -98,7 +104,7 @@ export const agentService = {
     const response = await api.post('/transactions', {
       agent_id: agentId,
       merchant_id: transactionData.merchant_id,
-      amount: transactionData.amount,
+      amount: toMoney(transactionData.amount),
       checkout_url: transactionData.checkout_url || `https://example.com/checkout/${Date.now()}`,
       items: transactionData.items || (transactionData.description ? [transactionData.description] : null),
     });
@@ -142,7 +148,7 @@ export const merchantTransactionService = {
         Authorization: `Bearer ${token}`,
       },
     });
-    return response.data;
+    return fromMoney(response.data);
   },
 
   getTransactionStats: async (merchantId) => {
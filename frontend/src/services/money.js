@@ -0,0 +1,38 @@
+// The API sends and receives amounts as { minor_units, currency }.
+
+const ZERO_DECIMAL = ['BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF', 'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF'];
+const THREE_DECIMAL = ['BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND'];
+
+const exponent = (currency) => {
+  const code = (currency || 'USD').toUpperCase();
+  if (ZERO_DECIMAL.includes(code)) return 0;
+  if (THREE_DECIMAL.includes(code)) return 3;
+  return 2;
+};
+
+// Build a Money object from a major-unit amount typed by the user, without float maths
+export const toMoney = (amount, currency = 'USD') => {
+  const places = exponent(currency);
+  const [whole, fraction = ''] = String(amount).trim().split('.');
+  const negative = whole.startsWith('-');
+  const digits = `${whole.replace('-', '') || '0'}${fraction.padEnd(places, '0').slice(0, places)}`;
+  const minorUnits = parseInt(digits, 10) * (negative ? -1 : 1);
+  return { minor_units: minorUnits, currency: currency.toUpperCase() };
+};
+
+const isMoney = (value) =>
+  value !== null && typeof value === 'object' && Number.isInteger(value.minor_units) && typeof value.currency === 'string';
+
+// Replace Money objects in a response with major-unit numbers for display
+export const fromMoney = (value) => {
+  if (isMoney(value)) {
+    return value.minor_units / 10 ** exponent(value.currency);
+  }
+  if (Array.isArray(value)) {
+    return value.map(fromMoney);
+  }
+  if (value !== null && typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([key, v]) => [key, fromMoney(v)]));
+  }
+  return value;
+};